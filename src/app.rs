@@ -1,18 +1,59 @@
+pub mod auth;
+pub mod aws_signature;
+pub mod code_snippets;
+pub mod collections;
+pub mod curl_import;
+pub mod digest_auth;
+pub mod dotenv;
+pub mod dynamic_variables;
+pub mod environment_diagnostics;
+pub mod environment_file;
 pub mod environment_injector;
+pub mod environment_watcher;
+pub mod hmac_signature;
+pub mod http_client;
+pub mod http_file;
+pub mod import;
+pub mod insomnia_import;
+pub mod jwt;
+pub mod oauth2;
+pub mod openapi_import;
+pub mod postman_import;
+pub mod project;
 pub mod request_method;
 pub mod request_sender;
 pub mod resource;
+pub mod response_capture;
+pub mod secret_vault;
 pub mod syntax_highlighting;
 pub mod tab_state;
 pub mod tab_viewer;
+pub mod variable_scopes;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 
 use crate::app::tab_state::TabState;
-use crate::app::tab_viewer::{Tab, TabViewer};
+use crate::app::tab_viewer::{import_curl, Tab, TabViewer};
 
+use crate::app::collections::{folder_at_mut, Folder, SavedRequest};
+use crate::app::environment_file::{create_environment, environment_file_dialog, load_environment};
+use crate::app::environment_watcher::{diff_environments, EnvironmentWatcher, CHECK_INTERVAL};
+use crate::app::http_file::read_http_file;
+use crate::app::import::{import_file, write_environments};
+use crate::app::project::{
+    create_project, load_project, load_project_file, project_environment, project_environments,
+    save_project, save_project_file, ProjectChange, ProjectFile, ProjectSync, ENVIRONMENTS_DIR,
+};
+use crate::app::secret_vault::strip_secrets;
+use crate::ui::ui_collections::{ui_collections, ui_save_request};
+use crate::ui::ui_commands::ui_commands;
+use crate::ui::ui_environment::{ui_environment_editor, ui_environment_save};
 use crate::ui::ui_history::ui_history;
+use crate::ui::ui_project_settings::ui_project_settings;
+use crate::ui::ui_secret_vault::ui_secret_vault;
 use egui_dock::{DockArea, DockState, NodeIndex, Style, SurfaceIndex};
+use serde_json::{Map, Value};
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct Config {
@@ -24,6 +65,9 @@ pub struct HttpApp {
     open_requests: TabViewer,
     tree: DockState<Tab>,
     loaded_initial: bool,
+    watcher: EnvironmentWatcher,
+    project_sync: ProjectSync,
+    window_title: String,
 }
 
 impl Default for HttpApp {
@@ -45,8 +89,45 @@ impl Default for HttpApp {
                 new_tab_name_temp: "".to_owned(),
                 tab_name_to_change: "".to_owned(),
                 env_modal_opened: false,
+                global_variables: Default::default(),
+                project_variables: Default::default(),
+                project_variables_path: Default::default(),
+                project_variables_dirty: false,
+                globals_modal_opened: false,
+                project_modal_opened: false,
+                overrides_modal_opened: false,
+                resolved_modal_opened: false,
+                secret_names: Default::default(),
+                secrets_modal_opened: false,
+                vault: Default::default(),
+                vault_passphrase: "".to_owned(),
+                reveal_secrets: false,
+                snippet_keep_variables: false,
+                new_secret_name: "".to_owned(),
+                new_secret_value: "".to_owned(),
+                commands: vec![],
+                commands_enabled: false,
+                commands_modal_opened: false,
+                diagnostics_modal_opened: false,
+                oauth2_tokens: Default::default(),
+                collections: vec![],
+                save_request_tab: None,
+                save_request_name: "".to_owned(),
+                save_request_folder: vec![],
+                project_path: Default::default(),
+                project: Default::default(),
+                project_dirty: false,
+                project_settings_opened: false,
+                insecure_projects: Default::default(),
+                import_report: None,
+                curl_import_tab: None,
+                curl_command: "".to_owned(),
+                command_runner: Default::default(),
             },
             tree: DockState::new(vec!["Test".to_owned()]),
+            watcher: Default::default(),
+            project_sync: Default::default(),
+            window_title: "".to_owned(),
         }
     }
 }
@@ -73,6 +154,34 @@ impl HttpApp {
                 serde_json::from_str::<TabViewer>(open_requests_str.unwrap().as_str()).unwrap();
             default.open_requests = open_requests;
         }
+        // Secret values aren't persisted, read them back from the environment files
+        let viewer = &mut default.open_requests;
+        if !viewer.project_variables_dirty {
+            if let Ok(variables) = load_environment(&viewer.project_variables_path, None) {
+                viewer.project_variables = variables;
+            }
+        }
+        for state in default.open_requests.open_requests.values_mut() {
+            if state.environment_dirty || state.environment_path.as_os_str().is_empty() {
+                continue;
+            }
+            if let Ok(environment) =
+                load_environment(&state.environment_path, state.environment_format)
+            {
+                state.environment = environment;
+            }
+        }
+        // The project directory is the source of truth for the collections
+        let viewer = &mut default.open_requests;
+        if !viewer.project_path.as_os_str().is_empty() {
+            if let Ok(collections) = load_project(&viewer.project_path) {
+                viewer.collections = collections;
+            }
+            viewer.project = load_project_file(&viewer.project_path).unwrap_or_default();
+            default
+                .project_sync
+                .loaded(&viewer.project_path, &viewer.collections);
+        }
         let tree_str = storage.get_string("tree");
 
         if tree_str.is_some() {
@@ -86,6 +195,624 @@ impl HttpApp {
     }
 }
 
+impl HttpApp {
+    // Global and project variables are shared by all tabs so their windows are
+    // shown once for the whole app instead of from within a tab.
+    fn variables_windows(&mut self, ctx: &egui::Context) {
+        let mut toasts = egui_toast::Toasts::new()
+            .anchor(egui::Align2::CENTER_TOP, (10.0, 10.0))
+            .direction(egui::Direction::TopDown);
+        let viewer = &mut self.open_requests;
+
+        egui::Window::new("Global variables")
+            .open(&mut viewer.globals_modal_opened)
+            .show(ctx, |ui| {
+                ui.label("Available in every tab, any other layer can override them.");
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        ui_environment_editor(
+                            ui,
+                            &mut viewer.global_variables,
+                            "globals_editor",
+                            &mut viewer.secret_names,
+                        );
+                    });
+            });
+
+        let mut project_modal_opened = viewer.project_modal_opened;
+        egui::Window::new("Project variables")
+            .open(&mut project_modal_opened)
+            .show(ctx, |ui| {
+                ui.label("Shared by every tab and stored in a project file.");
+                ui.horizontal(|ui| {
+                    if ui.button("Load file").clicked() {
+                        load_project_variables(viewer, &mut toasts);
+                    }
+                    if ui.button("New file").clicked() {
+                        let file = environment_file_dialog()
+                            .set_file_name("project.json")
+                            .save_file();
+                        if let Some(file_path) = file {
+                            if create_environment(&file_path).is_ok() {
+                                viewer.project_variables_path = file_path;
+                                viewer.project_variables_dirty = true;
+                            }
+                        }
+                    }
+                });
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        let variables = &mut viewer.project_variables;
+                        let secret_names = &mut viewer.secret_names;
+                        if ui_environment_editor(ui, variables, "project_editor", secret_names) {
+                            viewer.project_variables_dirty = true;
+                        }
+                    });
+                ui.separator();
+                ui_environment_save(
+                    ui,
+                    &viewer.project_variables,
+                    &viewer.project_variables_path,
+                    None,
+                    &mut viewer.project_variables_dirty,
+                    &mut toasts,
+                );
+            });
+        viewer.project_modal_opened = project_modal_opened;
+
+        egui::Window::new("Secrets")
+            .open(&mut viewer.secrets_modal_opened)
+            .show(ctx, |ui| {
+                ui_secret_vault(
+                    ui,
+                    &mut viewer.vault,
+                    &mut viewer.vault_passphrase,
+                    &mut viewer.new_secret_name,
+                    &mut viewer.new_secret_value,
+                    &mut viewer.secret_names,
+                    &mut toasts,
+                );
+            });
+
+        egui::Window::new("Commands")
+            .open(&mut viewer.commands_modal_opened)
+            .show(ctx, |ui| {
+                ui_commands(
+                    ui,
+                    &mut viewer.commands,
+                    &viewer.command_runner,
+                    &mut viewer.commands_enabled,
+                );
+            });
+
+        toasts.show(ctx);
+    }
+}
+
+impl HttpApp {
+    // Focuses the tab of a saved request, or opens the request in a new tab.
+    fn open_saved_request(&mut self, saved: SavedRequest) {
+        let viewer = &mut self.open_requests;
+        // Closed tabs keep their state, only a tab that is still in the tree counts
+        let open_tab = viewer
+            .open_requests
+            .iter()
+            .find(|(_, state)| state.saved_request.as_ref() == Some(&saved.id))
+            .map(|(name, _)| name.clone())
+            .filter(|name| self.tree.find_tab(name).is_some());
+
+        match open_tab {
+            Some(tab) => {
+                if let Some(location) = self.tree.find_tab(&tab) {
+                    self.tree.set_active_tab(location);
+                }
+                viewer.active_tab = Some(tab);
+            }
+            None => {
+                let state = new_tab_state(&viewer.project_path, &viewer.project, saved.to_state());
+                self.open_tab(&saved.name, state);
+            }
+        }
+    }
+
+    // Adds a tab named after the request, with a number when the name is taken.
+    fn open_tab(&mut self, name: &str, state: TabState) {
+        let viewer = &mut self.open_requests;
+        let mut tab = name.to_owned();
+        let mut index = 2;
+        while self.tree.find_tab(&tab).is_some() {
+            tab = format!("{} ({})", name, index);
+            index += 1;
+        }
+        viewer.open_requests.insert(tab.clone(), state);
+        self.tree.push_to_focused_leaf(tab.clone());
+        viewer.counter += 1;
+        if let Some(location) = self.tree.find_tab(&tab) {
+            self.tree.set_active_tab(location);
+        }
+        viewer.active_tab = Some(tab);
+    }
+
+    // Opens every request of a `.http` file in a tab, the variables of the file
+    // become overrides of the tab so they're never written to an environment.
+    fn open_http_file(&mut self, path: &Path) -> Result<(), String> {
+        let file = read_http_file(path)?;
+        for request in file.requests {
+            let viewer = &self.open_requests;
+            let mut state =
+                new_tab_state(&viewer.project_path, &viewer.project, request.to_state());
+            state.saved_request = None;
+            state.variable_overrides = file.variables.clone();
+            self.open_tab(&request.name, state);
+        }
+        if !file.report.is_empty() {
+            self.open_requests.import_report = Some(file.report);
+        }
+        Ok(())
+    }
+
+    fn save_request_window(&mut self, ctx: &egui::Context) {
+        let viewer = &mut self.open_requests;
+        let Some(tab) = viewer.save_request_tab.clone() else {
+            return;
+        };
+
+        let mut opened = true;
+        let mut saved = false;
+        egui::Window::new("Save request")
+            .open(&mut opened)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                saved = ui_save_request(
+                    ui,
+                    &viewer.collections,
+                    &mut viewer.save_request_name,
+                    &mut viewer.save_request_folder,
+                );
+            });
+
+        if saved {
+            if viewer.collections.is_empty() {
+                viewer.collections.push(Folder::new("My collection"));
+            }
+            // The chosen folder may have been deleted in the meantime
+            if folder_at_mut(&mut viewer.collections, &viewer.save_request_folder).is_none() {
+                viewer.save_request_folder = vec![0];
+            }
+            let folder = folder_at_mut(&mut viewer.collections, &viewer.save_request_folder);
+            if let (Some(state), Some(folder)) = (viewer.open_requests.get_mut(&tab), folder) {
+                let request = SavedRequest::new(viewer.save_request_name.trim().to_owned(), state);
+                state.saved_request = Some(request.id.clone());
+                folder.requests.push(request);
+            }
+            opened = false;
+        }
+        if !opened {
+            viewer.save_request_tab = None;
+        }
+    }
+}
+
+impl HttpApp {
+    // Moves the collections of the app into a new project directory.
+    fn new_project(&mut self, path: PathBuf) -> Result<(), String> {
+        let viewer = &mut self.open_requests;
+        create_project(&path)?;
+        // Files already in the directory aren't the app's to remove
+        save_project(&path, &viewer.collections, &BTreeSet::new())?;
+        viewer.project = load_project_file(&path)?;
+        viewer.project_dirty = false;
+        self.project_sync.loaded(&path, &viewer.collections);
+        viewer.project_path = path;
+        Ok(())
+    }
+
+    fn open_project(&mut self, path: PathBuf) -> Result<(), String> {
+        let viewer = &mut self.open_requests;
+        let project = load_project_file(&path)?;
+        viewer.collections = load_project(&path)?;
+        viewer.project = project;
+        viewer.project_dirty = false;
+        self.project_sync.loaded(&path, &viewer.collections);
+        viewer.project_path = path;
+        Ok(())
+    }
+
+    fn project_settings_window(&mut self, ctx: &egui::Context) {
+        let viewer = &mut self.open_requests;
+        let mut toasts = egui_toast::Toasts::new()
+            .anchor(egui::Align2::CENTER_TOP, (10.0, 10.0))
+            .direction(egui::Direction::TopDown);
+        let environments = match viewer.project_settings_opened {
+            true => project_environments(&viewer.project_path),
+            false => vec![],
+        };
+
+        egui::Window::new("Project settings")
+            .open(&mut viewer.project_settings_opened)
+            .show(ctx, |ui| {
+                let path = &viewer.project_path;
+                // Kept in the app, so it isn't committed along with the project
+                let mut insecure = viewer.insecure_projects.contains(path);
+                if ui_project_settings(ui, &mut viewer.project, path, &environments, &mut insecure)
+                {
+                    viewer.project_dirty = true;
+                }
+                match insecure {
+                    true => viewer.insecure_projects.insert(path.clone()),
+                    false => viewer.insecure_projects.remove(path),
+                };
+                ui.separator();
+                let save = ui.add_enabled(viewer.project_dirty, egui::Button::new("Save"));
+                if save.clicked() {
+                    let saved = save_project_file(path, &viewer.project);
+                    if saved.is_ok() {
+                        viewer.project_dirty = false;
+                        self.project_sync.refresh(path);
+                    }
+                    project_toast(&mut toasts, "saved", saved);
+                }
+            });
+        toasts.show(ctx);
+    }
+
+    fn sync_project(&mut self, ctx: &egui::Context) {
+        let viewer = &mut self.open_requests;
+        if viewer.project_path.as_os_str().is_empty() {
+            return;
+        }
+        // Keep polling while the app is idle
+        ctx.request_repaint_after(CHECK_INTERVAL);
+        if self.project_sync.has_conflict() {
+            self.project_conflict_window(ctx);
+            return;
+        }
+
+        let change = self
+            .project_sync
+            .check(&viewer.project_path, &viewer.collections);
+        let (text, kind) = match change {
+            None | Some(ProjectChange::Saved) => return,
+            Some(ProjectChange::Reloaded(project, collections)) => {
+                viewer.collections = collections;
+                // Unsaved edits of the settings are kept
+                if !viewer.project_dirty {
+                    viewer.project = project;
+                }
+                (
+                    "Project reloaded from disk".to_owned(),
+                    egui_toast::ToastKind::Info,
+                )
+            }
+            Some(ProjectChange::Conflict) => (
+                "The project changed on disk and in the app".to_owned(),
+                egui_toast::ToastKind::Warning,
+            ),
+            Some(ProjectChange::Failed(error)) => (
+                format!("Project not synced: {}", error),
+                egui_toast::ToastKind::Error,
+            ),
+        };
+        let mut toasts = egui_toast::Toasts::new()
+            .anchor(egui::Align2::CENTER_TOP, (10.0, 10.0))
+            .direction(egui::Direction::TopDown);
+        toasts.add(egui_toast::Toast {
+            text: text.into(),
+            kind,
+            options: egui_toast::ToastOptions::default()
+                .duration_in_seconds(5.0)
+                .show_progress(true)
+                .show_icon(true),
+        });
+        toasts.show(ctx);
+    }
+}
+
+impl HttpApp {
+    // Nothing is synced until one of the two versions is picked.
+    fn project_conflict_window(&mut self, ctx: &egui::Context) {
+        let mut toasts = egui_toast::Toasts::new()
+            .anchor(egui::Align2::CENTER_TOP, (10.0, 10.0))
+            .direction(egui::Direction::TopDown);
+        egui::Window::new("Project changed on disk")
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label("The collections were changed in the app and in the project directory since they were last synced.");
+                ui.horizontal(|ui| {
+                    if ui.button("Reload from disk").clicked() {
+                        let path = self.open_requests.project_path.clone();
+                        let reloaded = self.open_project(path);
+                        project_toast(&mut toasts, "reloaded", reloaded);
+                    }
+                    if ui.button("Keep the app's version").clicked() {
+                        let viewer = &self.open_requests;
+                        let saved = self
+                            .project_sync
+                            .overwrite(&viewer.project_path, &viewer.collections);
+                        project_toast(&mut toasts, "saved", saved);
+                    }
+                });
+            });
+        toasts.show(ctx);
+    }
+
+    // Adds the imported collections, environments are written next to the
+    // project's or to a folder the user picks.
+    fn import(&mut self, path: &Path) -> Result<(), String> {
+        let imported = import_file(path)?;
+        let viewer = &mut self.open_requests;
+        let mut report = vec![format!(
+            "Imported {} requests in {} collections and {} environments",
+            imported.request_count(),
+            imported.collections.len(),
+            imported.environments.len()
+        )];
+        if !imported.environments.is_empty() {
+            let directory = match viewer.project_path.as_os_str().is_empty() {
+                false => Some(viewer.project_path.join(ENVIRONMENTS_DIR)),
+                true => rfd::FileDialog::new()
+                    .set_title("Folder for the imported environments")
+                    .pick_folder(),
+            };
+            match directory {
+                Some(directory) => {
+                    for path in write_environments(&directory, &imported.environments)? {
+                        report.push(format!("Environment written to {}", path.display()));
+                    }
+                }
+                None => report.push("No folder was picked, environments were skipped".to_owned()),
+            }
+        }
+        report.extend(imported.report);
+        viewer.secret_names.extend(imported.secret_names);
+        viewer.collections.extend(imported.collections);
+        viewer.import_report = Some(report);
+        Ok(())
+    }
+
+    fn curl_import_window(&mut self, ctx: &egui::Context) {
+        let viewer = &mut self.open_requests;
+        let Some(tab) = viewer.curl_import_tab.clone() else {
+            return;
+        };
+        let mut toasts = egui_toast::Toasts::new()
+            .anchor(egui::Align2::CENTER_TOP, (10.0, 10.0))
+            .direction(egui::Direction::TopDown);
+
+        let mut opened = true;
+        let mut imported = false;
+        egui::Window::new("Import curl")
+            .open(&mut opened)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label("Paste a curl command, it replaces the request of the tab");
+                ui.label("Files of @file bodies are read and listed in the report");
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut viewer.curl_command)
+                                .code_editor()
+                                .desired_rows(8)
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                if ui.button("Import").clicked() {
+                    if let Some(state) = viewer.open_requests.get_mut(&tab) {
+                        imported = import_curl(
+                            state,
+                            &viewer.curl_command,
+                            true,
+                            &mut viewer.import_report,
+                            &mut toasts,
+                        );
+                    }
+                }
+            });
+        toasts.show(ctx);
+
+        if imported || !opened {
+            viewer.curl_import_tab = None;
+        }
+    }
+
+    fn import_report_window(&mut self, ctx: &egui::Context) {
+        let viewer = &mut self.open_requests;
+        let Some(report) = &viewer.import_report else {
+            return;
+        };
+        let mut opened = true;
+        egui::Window::new("Import report")
+            .open(&mut opened)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for line in report {
+                        ui.label(line);
+                    }
+                });
+            });
+        if !opened {
+            viewer.import_report = None;
+        }
+    }
+}
+
+// New tabs start with the default environment of the project.
+fn new_tab_state(project_path: &Path, project: &ProjectFile, mut state: TabState) -> TabState {
+    if let Some(path) = project_environment(project_path, project) {
+        if let Ok(environment) = load_environment(&path, None) {
+            state.environment = environment;
+            state.environment_path = path;
+        }
+    }
+    state
+}
+
+fn project_toast(toasts: &mut egui_toast::Toasts, action: &str, result: Result<(), String>) {
+    let (text, kind) = match result {
+        Ok(()) => (
+            format!("Project {}", action),
+            egui_toast::ToastKind::Success,
+        ),
+        Err(error) => (
+            format!("Project not {}: {}", action, error),
+            egui_toast::ToastKind::Error,
+        ),
+    };
+    toasts.add(egui_toast::Toast {
+        text: text.into(),
+        kind,
+        options: egui_toast::ToastOptions::default()
+            .duration_in_seconds(3.0)
+            .show_progress(true)
+            .show_icon(true),
+    });
+}
+
+impl HttpApp {
+    // Reloads environment files that were changed outside of the app, for example
+    // by a script refreshing tokens.
+    fn watch_environments(&mut self, ctx: &egui::Context) {
+        let viewer = &mut self.open_requests;
+        let mut paths: BTreeSet<PathBuf> = viewer
+            .open_requests
+            .values()
+            .map(|state| state.environment_path.clone())
+            .collect();
+        paths.insert(viewer.project_variables_path.clone());
+        paths.remove(&PathBuf::new());
+        if paths.is_empty() {
+            return;
+        }
+
+        // Keep polling while the app is idle
+        ctx.request_repaint_after(CHECK_INTERVAL);
+
+        let changed = self.watcher.changed_files(&paths);
+        if changed.is_empty() {
+            return;
+        }
+
+        let mut toasts = egui_toast::Toasts::new()
+            .anchor(egui::Align2::CENTER_TOP, (10.0, 10.0))
+            .direction(egui::Direction::TopDown);
+        for path in changed {
+            for (tab, state) in viewer.open_requests.iter_mut() {
+                if state.environment_path == path {
+                    let name = format!("Environment of {}", tab);
+                    let loaded = load_environment(&path, state.environment_format);
+                    reload_watched(
+                        &name,
+                        &loaded,
+                        &mut state.environment,
+                        state.environment_dirty,
+                        &mut toasts,
+                    );
+                }
+            }
+            if viewer.project_variables_path == path {
+                let loaded = load_environment(&path, None);
+                reload_watched(
+                    "Project variables",
+                    &loaded,
+                    &mut viewer.project_variables,
+                    viewer.project_variables_dirty,
+                    &mut toasts,
+                );
+            }
+        }
+        toasts.show(ctx);
+    }
+}
+
+// A file that fails to parse, for example while a script is halfway through
+// writing it, keeps the last good values. Unsaved edits made in the app are
+// never overwritten.
+fn reload_watched(
+    name: &str,
+    loaded: &Result<Map<String, Value>, String>,
+    variables: &mut Map<String, Value>,
+    dirty: bool,
+    toasts: &mut egui_toast::Toasts,
+) {
+    let (text, kind) = match loaded {
+        Ok(_) if dirty => (
+            format!(
+                "{} changed on disk, not reloaded because of unsaved changes",
+                name
+            ),
+            egui_toast::ToastKind::Warning,
+        ),
+        Ok(new_variables) => {
+            let changes = diff_environments(variables, new_variables);
+            *variables = new_variables.clone();
+            if changes.is_empty() {
+                return;
+            }
+            (
+                format!("{} reloaded: {}", name, changes.summary()),
+                egui_toast::ToastKind::Info,
+            )
+        }
+        Err(error) => (
+            format!("{} not reloaded, keeping previous values: {}", name, error),
+            egui_toast::ToastKind::Error,
+        ),
+    };
+    toasts.add(egui_toast::Toast {
+        text: text.into(),
+        kind,
+        options: egui_toast::ToastOptions::default()
+            .duration_in_seconds(5.0)
+            .show_progress(true)
+            .show_icon(true),
+    });
+}
+
+// Secret variables are kept out of the app storage, they are only held in memory,
+// in the vault or in the environment files the user picked.
+fn strip_persisted_secrets(open_requests: &mut Value, secret_names: &BTreeSet<String>) {
+    let strip = |value: Option<&mut Value>| {
+        if let Some(Value::Object(variables)) = value {
+            strip_secrets(variables, secret_names);
+        }
+    };
+    strip(open_requests.get_mut("global_variables"));
+    strip(open_requests.get_mut("project_variables"));
+    if let Some(Value::Object(tabs)) = open_requests.get_mut("open_requests") {
+        for state in tabs.values_mut() {
+            strip(state.get_mut("environment"));
+            strip(state.get_mut("variable_overrides"));
+            strip(state.get_mut("captured_values"));
+        }
+    }
+}
+
+fn load_project_variables(viewer: &mut TabViewer, toasts: &mut egui_toast::Toasts) {
+    let file = environment_file_dialog().pick_file();
+    if let Some(file_path) = file {
+        match load_environment(&file_path, None) {
+            Ok(variables) => {
+                viewer.project_variables = variables;
+                viewer.project_variables_path = file_path;
+                viewer.project_variables_dirty = false;
+            }
+            Err(error) => {
+                toasts.add(egui_toast::Toast {
+                    text: format!("Unable to load project variables: {}", error).into(),
+                    kind: egui_toast::ToastKind::Error,
+                    options: egui_toast::ToastOptions::default()
+                        .duration_in_seconds(3.0)
+                        .show_progress(true)
+                        .show_icon(true),
+                });
+            }
+        }
+    }
+}
+
 impl eframe::App for HttpApp {
     fn auto_save_interval(&self) -> std::time::Duration {
         std::time::Duration::from_secs(10)
@@ -93,12 +820,9 @@ impl eframe::App for HttpApp {
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         // println!("Saving state {:?}", self.history_items);
-        storage.set_string(
-            "open_requests",
-            serde_json::to_value(&self.open_requests)
-                .unwrap()
-                .to_string(),
-        );
+        let mut open_requests = serde_json::to_value(&self.open_requests).unwrap();
+        strip_persisted_secrets(&mut open_requests, &self.open_requests.secret_names);
+        storage.set_string("open_requests", open_requests.to_string());
         // println!("Saving state {:?}", self.history_items);
         storage.set_string(
             "tree",
@@ -112,6 +836,15 @@ impl eframe::App for HttpApp {
 
         self.open_requests.tab_name_modal = Some(self.open_requests.prompt_modal(ctx));
 
+        let title = match self.open_requests.project.name.as_str() {
+            "" => "Requestor".to_owned(),
+            name => format!("{} - Requestor", name),
+        };
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+
         egui::SidePanel::left("left_panel")
             .resizable(true)
             .show(ctx, |ui| {
@@ -119,6 +852,109 @@ impl eframe::App for HttpApp {
                 ui.vertical(|ui| {
                     ui.set_width_range(80.0..=400.0);
 
+                    let mut toasts = egui_toast::Toasts::new()
+                        .anchor(egui::Align2::CENTER_TOP, (10.0, 10.0))
+                        .direction(egui::Direction::TopDown);
+                    egui::menu::bar(ui, |ui| {
+                        ui.menu_button("Project", |ui| {
+                            if ui.button("New").clicked() {
+                                if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                                    let created = self.new_project(path);
+                                    project_toast(&mut toasts, "created", created);
+                                }
+                                ui.close_menu();
+                            }
+                            if ui.button("Open").clicked() {
+                                if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                                    let opened = self.open_project(path);
+                                    project_toast(&mut toasts, "opened", opened);
+                                }
+                                ui.close_menu();
+                            }
+                            let viewer = &mut self.open_requests;
+                            let has_project = !viewer.project_path.as_os_str().is_empty();
+                            // The collections stay in the app, they're no longer written out
+                            if ui
+                                .add_enabled(has_project, egui::Button::new("Close"))
+                                .clicked()
+                            {
+                                viewer.project_path = Default::default();
+                                viewer.project = Default::default();
+                                viewer.project_dirty = false;
+                                viewer.project_settings_opened = false;
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if ui
+                                .add_enabled(has_project, egui::Button::new("Settings"))
+                                .clicked()
+                            {
+                                viewer.project_settings_opened = true;
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if ui.button("Open .http file").clicked() {
+                                let path = rfd::FileDialog::new()
+                                    .add_filter("http", &["http", "rest"])
+                                    .add_filter("all files", &["*"])
+                                    .pick_file();
+                                if let Some(path) = path {
+                                    if let Err(error) = self.open_http_file(&path) {
+                                        toasts.add(egui_toast::Toast {
+                                            text: format!("Unable to open the file: {}", error)
+                                                .into(),
+                                            kind: egui_toast::ToastKind::Error,
+                                            options: egui_toast::ToastOptions::default()
+                                                .duration_in_seconds(5.0)
+                                                .show_progress(true)
+                                                .show_icon(true),
+                                        });
+                                    }
+                                }
+                                ui.close_menu();
+                            }
+                            if ui.button("Import").clicked() {
+                                let path = rfd::FileDialog::new()
+                                    .add_filter("json", &["json"])
+                                    .add_filter("yaml", &["yaml", "yml"])
+                                    .add_filter("all files", &["*"])
+                                    .pick_file();
+                                if let Some(path) = path {
+                                    if let Err(error) = self.import(&path) {
+                                        toasts.add(egui_toast::Toast {
+                                            text: format!("Import failed: {}", error).into(),
+                                            kind: egui_toast::ToastKind::Error,
+                                            options: egui_toast::ToastOptions::default()
+                                                .duration_in_seconds(5.0)
+                                                .show_progress(true)
+                                                .show_icon(true),
+                                        });
+                                    }
+                                }
+                                ui.close_menu();
+                            }
+                        });
+                        if !self.open_requests.project.name.is_empty() {
+                            ui.label(&self.open_requests.project.name).on_hover_text(
+                                self.open_requests.project_path.display().to_string(),
+                            );
+                        }
+                    });
+
+                    let opened = egui::CollapsingHeader::new("Collections")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            ui_collections(ui, &mut self.open_requests.collections, &mut toasts)
+                        })
+                        .body_returned
+                        .flatten();
+                    toasts.show(ui.ctx());
+                    if let Some(saved) = opened {
+                        self.open_saved_request(saved);
+                    }
+                    ui.separator();
+                    ui.label("History");
+
                     let selected_item = ui_history(ui, &self.open_requests.history_items);
 
                     match selected_item {
@@ -155,6 +991,14 @@ impl eframe::App for HttpApp {
                 });
             });
 
+        self.variables_windows(ctx);
+        self.save_request_window(ctx);
+        self.project_settings_window(ctx);
+        self.curl_import_window(ctx);
+        self.import_report_window(ctx);
+        self.watch_environments(ctx);
+        self.sync_project(ctx);
+
         DockArea::new(&mut self.tree)
             .show_add_buttons(true)
             .style(Style::from_egui(ctx.style().as_ref()))
@@ -169,9 +1013,14 @@ impl eframe::App for HttpApp {
                     NodeIndex::from(node),
                 ));
                 let new_tab = format!("Tab {}", self.open_requests.counter.to_string());
+                let state = new_tab_state(
+                    &self.open_requests.project_path,
+                    &self.open_requests.project,
+                    TabState::default(),
+                );
                 self.open_requests
                     .open_requests
-                    .insert(new_tab.clone(), TabState::default());
+                    .insert(new_tab.clone(), state);
                 self.tree.push_to_focused_leaf(new_tab.clone());
                 self.open_requests.active_tab = Some(new_tab);
                 self.open_requests.counter += 1;
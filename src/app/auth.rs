@@ -0,0 +1,344 @@
+use std::fmt;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use url::Url;
+
+use super::aws_signature::{sign_request, AwsSigV4};
+use super::digest_auth::DigestAuth;
+use super::environment_injector::inject_environment;
+use super::hmac_signature::{sign_hmac, HmacSigning, KeySource};
+use super::jwt::{JwtAuth, JwtKeySource};
+use super::oauth2::OAuth2Config;
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum AuthMode {
+    #[default]
+    None,
+    Basic,
+    Bearer,
+    ApiKey,
+    Digest,
+    AwsSigV4,
+    Hmac,
+    Jwt,
+    OAuth2,
+}
+
+impl AuthMode {
+    pub const ALL: [AuthMode; 9] = [
+        AuthMode::None,
+        AuthMode::Basic,
+        AuthMode::Bearer,
+        AuthMode::ApiKey,
+        AuthMode::Digest,
+        AuthMode::AwsSigV4,
+        AuthMode::Hmac,
+        AuthMode::Jwt,
+        AuthMode::OAuth2,
+    ];
+
+    // Name of the settings of the mode in `Auth` and in saved files.
+    pub fn settings_key(&self) -> Option<&'static str> {
+        match self {
+            AuthMode::None => None,
+            AuthMode::Basic => Some("basic"),
+            AuthMode::Bearer => Some("bearer"),
+            AuthMode::ApiKey => Some("api_key"),
+            AuthMode::Digest => Some("digest"),
+            AuthMode::AwsSigV4 => Some("aws"),
+            AuthMode::Hmac => Some("hmac"),
+            AuthMode::Jwt => Some("jwt"),
+            AuthMode::OAuth2 => Some("oauth2"),
+        }
+    }
+}
+
+impl fmt::Display for AuthMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthMode::None => write!(f, "No auth"),
+            AuthMode::Basic => write!(f, "Basic"),
+            AuthMode::Bearer => write!(f, "Bearer token"),
+            AuthMode::ApiKey => write!(f, "API key"),
+            AuthMode::Digest => write!(f, "Digest"),
+            AuthMode::AwsSigV4 => write!(f, "AWS Signature v4"),
+            AuthMode::Hmac => write!(f, "HMAC signature"),
+            AuthMode::Jwt => write!(f, "JWT"),
+            AuthMode::OAuth2 => write!(f, "OAuth 2.0"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    QueryParam,
+}
+
+impl fmt::Display for ApiKeyLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiKeyLocation::Header => write!(f, "Header"),
+            ApiKeyLocation::QueryParam => write!(f, "Query param"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BearerAuth {
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyAuth {
+    pub name: String,
+    pub value: String,
+    pub location: ApiKeyLocation,
+}
+
+impl Default for ApiKeyAuth {
+    fn default() -> Self {
+        Self {
+            name: "X-API-Key".to_owned(),
+            value: "".to_owned(),
+            location: ApiKeyLocation::Header,
+        }
+    }
+}
+
+// Settings of every mode are kept so switching between modes doesn't lose them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Auth {
+    #[serde(default)]
+    pub mode: AuthMode,
+    #[serde(default)]
+    pub basic: BasicAuth,
+    #[serde(default)]
+    pub bearer: BearerAuth,
+    #[serde(default)]
+    pub api_key: ApiKeyAuth,
+    #[serde(default)]
+    pub digest: DigestAuth,
+    #[serde(default)]
+    pub aws: AwsSigV4,
+    #[serde(default)]
+    pub hmac: HmacSigning,
+    #[serde(default)]
+    pub jwt: JwtAuth,
+    #[serde(default)]
+    pub oauth2: OAuth2Config,
+}
+
+impl Auth {
+    // The fields of the selected mode that hold credentials, by their name in
+    // the settings. Paths of key files aren't secret.
+    pub fn secret_fields(&mut self) -> Vec<(&'static str, &mut String)> {
+        match self.mode {
+            AuthMode::None => vec![],
+            AuthMode::Basic => vec![("password", &mut self.basic.password)],
+            AuthMode::Bearer => vec![("token", &mut self.bearer.token)],
+            AuthMode::ApiKey => vec![("value", &mut self.api_key.value)],
+            AuthMode::Digest => vec![("password", &mut self.digest.password)],
+            AuthMode::AwsSigV4 => vec![
+                ("secret_key", &mut self.aws.secret_key),
+                ("session_token", &mut self.aws.session_token),
+            ],
+            AuthMode::Hmac if self.hmac.key_source == KeySource::File => vec![],
+            AuthMode::Hmac => vec![("key", &mut self.hmac.key)],
+            AuthMode::Jwt if self.jwt.key_source == JwtKeySource::File => vec![],
+            AuthMode::Jwt => vec![("key", &mut self.jwt.key)],
+            AuthMode::OAuth2 => vec![
+                ("client_secret", &mut self.oauth2.client_secret),
+                ("password", &mut self.oauth2.password),
+                ("refresh_token", &mut self.oauth2.refresh_token),
+            ],
+        }
+    }
+}
+
+// Adds the credentials of the selected mode to the request, the fields can use
+// variables like any other input. The OAuth 2.0 token is taken from the cache,
+// the JWT is the one signed when the request was sent. Signing modes return the
+// text they signed.
+pub fn apply_auth(
+    request: &mut ehttp::Request,
+    auth: &Auth,
+    variables: &Map<String, Value>,
+    oauth2_token: Option<&str>,
+    jwt_token: Option<&str>,
+) -> Result<Option<String>, String> {
+    let inject = |text: &String| inject_environment(text, variables).0;
+    match auth.mode {
+        AuthMode::None => (),
+        AuthMode::Basic | AuthMode::Bearer => {
+            if let Some(value) = authorization(auth, variables) {
+                set_header(&mut request.headers, "Authorization".to_owned(), value);
+            }
+        }
+        AuthMode::ApiKey => {
+            let name = inject(&auth.api_key.name);
+            let value = inject(&auth.api_key.value);
+            if name.is_empty() {
+                return Ok(None);
+            }
+            match auth.api_key.location {
+                ApiKeyLocation::Header => set_header(&mut request.headers, name, value),
+                ApiKeyLocation::QueryParam => {
+                    if let Ok(mut url) = Url::parse(&request.url) {
+                        url.query_pairs_mut().append_pair(&name, &value);
+                        request.url = url.to_string();
+                    }
+                }
+            }
+        }
+        // Needs the challenge of the server, answered when the request is sent
+        AuthMode::Digest => (),
+        AuthMode::AwsSigV4 => {
+            let canonical = sign_request(request, &auth.aws.resolved(variables), Utc::now())?;
+            return Ok(Some(canonical));
+        }
+        AuthMode::Hmac => return sign_hmac(request, &auth.hmac, variables).map(Some),
+        AuthMode::Jwt => {
+            let token = jwt_token.ok_or("No JWT yet, it's signed on send")?;
+            let value = format!("Bearer {}", token);
+            set_header(&mut request.headers, "Authorization".to_owned(), value);
+        }
+        AuthMode::OAuth2 => {
+            let token = oauth2_token.ok_or("No OAuth 2.0 token yet, it's requested on send")?;
+            let value = format!("Bearer {}", token);
+            set_header(&mut request.headers, "Authorization".to_owned(), value);
+        }
+    }
+    Ok(None)
+}
+
+// Values generated from the auth settings, they're masked like secrets.
+pub fn auth_credentials(
+    auth: &Auth,
+    variables: &Map<String, Value>,
+    oauth2_token: Option<&str>,
+    jwt_token: Option<&str>,
+) -> Vec<String> {
+    let mut credentials = vec![];
+    match auth.mode {
+        AuthMode::None | AuthMode::Digest => (),
+        AuthMode::Basic | AuthMode::Bearer => {
+            if let Some(value) = authorization(auth, variables) {
+                let credential = value.split_once(' ').map_or(value.as_str(), |(_, c)| c);
+                credentials.push(credential.to_owned());
+            }
+        }
+        AuthMode::ApiKey => {
+            credentials.push(inject_environment(&auth.api_key.value, variables).0);
+        }
+        AuthMode::AwsSigV4 => {
+            let aws = auth.aws.resolved(variables);
+            credentials.extend([aws.secret_key, aws.session_token]);
+        }
+        AuthMode::Hmac => credentials.push(inject_environment(&auth.hmac.key, variables).0),
+        AuthMode::Jwt => {
+            if auth.jwt.key_source == JwtKeySource::Text {
+                credentials.push(inject_environment(&auth.jwt.key, variables).0);
+            }
+            credentials.extend(jwt_token.map(|token| token.to_owned()));
+        }
+        AuthMode::OAuth2 => credentials.extend(oauth2_token.map(|token| token.to_owned())),
+    }
+    credentials.retain(|credential| !credential.is_empty());
+    credentials
+}
+
+fn authorization(auth: &Auth, variables: &Map<String, Value>) -> Option<String> {
+    let inject = |text: &String| inject_environment(text, variables).0;
+    match auth.mode {
+        AuthMode::Basic => {
+            let username = inject(&auth.basic.username);
+            let password = inject(&auth.basic.password);
+            // Nothing filled in yet, `Basic Og==` would only be rejected
+            if username.is_empty() && password.is_empty() {
+                return None;
+            }
+            let credentials = format!("{}:{}", username, password);
+            Some(format!("Basic {}", STANDARD.encode(credentials)))
+        }
+        AuthMode::Bearer => {
+            let token = inject(&auth.bearer.token);
+            (!token.is_empty()).then(|| format!("Bearer {}", token))
+        }
+        _ => None,
+    }
+}
+
+// A header set by the tab or the auth mode replaces the default one with the
+// same name, the same way it is replaced when the request is sent.
+pub fn set_header(headers: &mut ehttp::Headers, key: String, value: String) {
+    let existing = headers
+        .headers
+        .iter_mut()
+        .find(|(k, _)| k.eq_ignore_ascii_case(&key));
+    match existing {
+        Some(header) => *header = (key, value),
+        None => headers.insert(key, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn authorization_header(auth: &Auth, variables: &Map<String, Value>) -> Option<String> {
+        let mut request = ehttp::Request::get("https://example.com");
+        apply_auth(&mut request, auth, variables, None, None).unwrap();
+        request
+            .headers
+            .headers
+            .into_iter()
+            .find(|(name, _)| name == "Authorization")
+            .map(|(_, value)| value)
+    }
+
+    #[test]
+    fn basic_auth_without_credentials_sends_no_header() {
+        let auth = Auth {
+            mode: AuthMode::Basic,
+            ..Default::default()
+        };
+        assert_eq!(authorization_header(&auth, &Map::new()), None);
+    }
+
+    #[test]
+    fn basic_auth_injects_variables() {
+        let mut auth = Auth {
+            mode: AuthMode::Basic,
+            ..Default::default()
+        };
+        auth.basic.username = "{user}".to_owned();
+        auth.basic.password = "open sesame".to_owned();
+        let variables = json!({"user": "Aladdin"}).as_object().unwrap().clone();
+        assert_eq!(
+            authorization_header(&auth, &variables).as_deref(),
+            Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==")
+        );
+    }
+
+    #[test]
+    fn bearer_without_token_sends_no_header() {
+        let auth = Auth {
+            mode: AuthMode::Bearer,
+            ..Default::default()
+        };
+        assert_eq!(authorization_header(&auth, &Map::new()), None);
+    }
+}
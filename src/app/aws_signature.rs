@@ -0,0 +1,309 @@
+use chrono::{DateTime, Utc};
+use hmac::Hmac;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use super::auth::set_header;
+use super::environment_injector::inject_environment;
+use super::hmac_signature::{hex, mac};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+// Everything but the unreserved characters is encoded, as required by SigV4
+const URI_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AwsSigV4 {
+    pub access_key: String,
+    pub secret_key: String,
+    pub session_token: String,
+    pub region: String,
+    pub service: String,
+}
+
+impl Default for AwsSigV4 {
+    fn default() -> Self {
+        Self {
+            access_key: "".to_owned(),
+            secret_key: "".to_owned(),
+            session_token: "".to_owned(),
+            region: "us-east-1".to_owned(),
+            service: "execute-api".to_owned(),
+        }
+    }
+}
+
+impl AwsSigV4 {
+    pub fn resolved(&self, variables: &Map<String, Value>) -> AwsSigV4 {
+        let inject = |text: &String| inject_environment(text, variables).0;
+        AwsSigV4 {
+            access_key: inject(&self.access_key),
+            secret_key: inject(&self.secret_key),
+            session_token: inject(&self.session_token),
+            region: inject(&self.region),
+            service: inject(&self.service),
+        }
+    }
+}
+
+// Signs the request as it is, so it has to be the last change before sending.
+// Returns the canonical request that was signed.
+pub fn sign_request(
+    request: &mut ehttp::Request,
+    config: &AwsSigV4,
+    now: DateTime<Utc>,
+) -> Result<String, String> {
+    if config.access_key.is_empty() || config.secret_key.is_empty() {
+        return Err("The AWS access key and secret key are required".to_owned());
+    }
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let payload_hash = hex_sha256(&request.body);
+    set_header(
+        &mut request.headers,
+        "X-Amz-Date".to_owned(),
+        amz_date.clone(),
+    );
+    set_header(
+        &mut request.headers,
+        "X-Amz-Content-Sha256".to_owned(),
+        payload_hash,
+    );
+    if !config.session_token.is_empty() {
+        let token = config.session_token.clone();
+        set_header(
+            &mut request.headers,
+            "X-Amz-Security-Token".to_owned(),
+            token,
+        );
+    }
+
+    let (canonical, signed_headers) = canonical_request(request, &config.service)?;
+    let (scope, signature) = signature(config, now, &canonical)?;
+
+    let authorization = format!(
+        "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+        ALGORITHM, config.access_key, scope, signed_headers, signature
+    );
+    set_header(
+        &mut request.headers,
+        "Authorization".to_owned(),
+        authorization,
+    );
+    Ok(canonical)
+}
+
+// The credential scope and the signature of the canonical request.
+fn signature(
+    config: &AwsSigV4,
+    now: DateTime<Utc>,
+    canonical: &str,
+) -> Result<(String, String), String> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let scope = format!("{}/{}/{}/aws4_request", date, config.region, config.service);
+    let string_to_sign = format!(
+        "{}\n{}\n{}\n{}",
+        ALGORITHM,
+        amz_date,
+        scope,
+        hex_sha256(canonical.as_bytes())
+    );
+
+    let mut key = format!("AWS4{}", config.secret_key).into_bytes();
+    for part in [&date, &config.region, &config.service, "aws4_request"] {
+        key = mac::<Hmac<Sha256>>(&key, part.as_bytes())?;
+    }
+    let signature = mac::<Hmac<Sha256>>(&key, string_to_sign.as_bytes())?;
+    Ok((scope, hex(&signature)))
+}
+
+// The canonical request and the signed header names.
+fn canonical_request(request: &ehttp::Request, service: &str) -> Result<(String, String), String> {
+    let url = Url::parse(&request.url).map_err(|err| err.to_string())?;
+
+    // S3 encodes the path once, every other service twice
+    let path = url
+        .path()
+        .split('/')
+        .map(|segment| {
+            let decoded = percent_decode_str(segment).decode_utf8_lossy();
+            let encoded = utf8_percent_encode(&decoded, URI_ENCODE).to_string();
+            match service {
+                "s3" => encoded,
+                _ => utf8_percent_encode(&encoded, URI_ENCODE).to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| {
+            let encode = |text: &str| utf8_percent_encode(text, URI_ENCODE).to_string();
+            (encode(&k), encode(&v))
+        })
+        .collect();
+    query.sort();
+    let query = query
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    // The host header is added by the http client, it's signed from the url
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_owned(),
+    };
+    let mut headers = vec![("host".to_owned(), host)];
+    for (k, v) in &request.headers {
+        let name = k.to_ascii_lowercase();
+        if name == "authorization" || name == "host" {
+            continue;
+        }
+        let value = v.split_whitespace().collect::<Vec<_>>().join(" ");
+        headers.push((name, value));
+    }
+    headers.sort();
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(k, v)| format!("{}:{}\n", k, v))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(k, _)| k.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method,
+        path,
+        query,
+        canonical_headers,
+        signed_headers,
+        hex_sha256(&request.body)
+    );
+    Ok((canonical, signed_headers))
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const EMPTY_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    // A request of the AWS SigV4 test suite, signed on 2015-08-30 12:36:00
+    fn suite_request(
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> ehttp::Request {
+        let mut request = ehttp::Request::get(format!("https://example.amazonaws.com{}", path));
+        request.method = method.to_owned();
+        request.headers = ehttp::Headers::new(headers);
+        request.headers.insert("X-Amz-Date", "20150830T123600Z");
+        request.body = body.as_bytes().to_vec();
+        request
+    }
+
+    fn canonical(request: &ehttp::Request, service: &str) -> String {
+        canonical_request(request, service).unwrap().0
+    }
+
+    #[test]
+    fn get_vanilla() {
+        let request = suite_request("GET", "/", &[], "");
+        let (canonical, signed_headers) = canonical_request(&request, "service").unwrap();
+        assert_eq!(
+            canonical,
+            format!(
+                "GET\n/\n\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\nhost;x-amz-date\n{}",
+                EMPTY_HASH
+            )
+        );
+        assert_eq!(signed_headers, "host;x-amz-date");
+
+        let config = AwsSigV4 {
+            access_key: "AKIDEXAMPLE".to_owned(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_owned(),
+            service: "service".to_owned(),
+            ..Default::default()
+        };
+        let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+        let (scope, signature) = signature(&config, now, &canonical).unwrap();
+        assert_eq!(scope, "20150830/us-east-1/service/aws4_request");
+        assert_eq!(
+            signature,
+            "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn get_vanilla_query_order_key_case() {
+        let request = suite_request("GET", "/?Param2=value2&Param1=value1", &[], "");
+        let canonical = canonical(&request, "service");
+        assert_eq!(
+            canonical.lines().nth(2),
+            Some("Param1=value1&Param2=value2")
+        );
+    }
+
+    #[test]
+    fn get_vanilla_query_unreserved() {
+        let unreserved = "-._~0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ_abcdefghijklmnopqrstuvwxyz";
+        let query = format!("{}={}", unreserved, unreserved);
+        let request = suite_request("GET", &format!("/?{}", query), &[], "");
+        assert_eq!(
+            canonical(&request, "service").lines().nth(2),
+            Some(query.as_str())
+        );
+    }
+
+    #[test]
+    fn get_header_value_trim() {
+        let headers = [("My-Header1", " value1"), ("My-Header2", " \"a   b   c\"")];
+        let request = suite_request("GET", "/", &headers, "");
+        let (canonical, signed_headers) = canonical_request(&request, "service").unwrap();
+        assert!(canonical.contains("\nmy-header1:value1\nmy-header2:\"a b c\"\nx-amz-date:"));
+        assert_eq!(signed_headers, "host;my-header1;my-header2;x-amz-date");
+    }
+
+    #[test]
+    fn post_x_www_form_urlencoded() {
+        let headers = [("Content-Type", "application/x-www-form-urlencoded")];
+        let request = suite_request("POST", "/", &headers, "Param1=value1");
+        assert_eq!(
+            canonical(&request, "service"),
+            "POST\n/\n\ncontent-type:application/x-www-form-urlencoded\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\ncontent-type;host;x-amz-date\n9095672bbd1f56dfc5b65f3e153adc8731a4a654192329106275f4c7b24d0b6e"
+        );
+    }
+
+    #[test]
+    fn paths_are_encoded_twice_except_for_s3() {
+        // The suite signs paths encoded once, like S3
+        let request = suite_request("GET", "/example space/\u{1234}", &[], "");
+        assert_eq!(
+            canonical(&request, "s3").lines().nth(1),
+            Some("/example%20space/%E1%88%B4")
+        );
+        assert_eq!(
+            canonical(&request, "service").lines().nth(1),
+            Some("/example%2520space/%25E1%2588%25B4")
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{Map, Value};
+
+use super::auth::AuthMode;
+use super::environment_injector::inject_environment;
+use super::request_sender::resolve_request;
+use super::tab_state::TabState;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnippetLanguage {
+    Curl,
+    Httpie,
+    Reqwest,
+    PythonRequests,
+    Fetch,
+    GoNetHttp,
+}
+
+impl SnippetLanguage {
+    pub const ALL: [SnippetLanguage; 6] = [
+        SnippetLanguage::Curl,
+        SnippetLanguage::Httpie,
+        SnippetLanguage::Reqwest,
+        SnippetLanguage::PythonRequests,
+        SnippetLanguage::Fetch,
+        SnippetLanguage::GoNetHttp,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SnippetLanguage::Curl => "curl",
+            SnippetLanguage::Httpie => "HTTPie",
+            SnippetLanguage::Reqwest => "Rust reqwest",
+            SnippetLanguage::PythonRequests => "Python requests",
+            SnippetLanguage::Fetch => "JavaScript fetch",
+            SnippetLanguage::GoNetHttp => "Go net/http",
+        }
+    }
+}
+
+// Credentials the generated code passes to the auth helper of the client
+// instead of a ready made header, so they stay readable.
+#[derive(Debug, PartialEq)]
+enum Credentials {
+    None,
+    Basic(String, String),
+    Digest(String, String),
+}
+
+struct Snippet {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: String,
+    credentials: Credentials,
+}
+
+// Code sending the request of the tab. With `keep_variables` the `{variables}`
+// are left in the code, otherwise it's the request as it would be sent.
+pub fn code_snippet(
+    language: SnippetLanguage,
+    state: &TabState,
+    variables: &Map<String, Value>,
+    keep_variables: bool,
+) -> Result<String, String> {
+    let empty = Map::new();
+    let variables = if keep_variables { &empty } else { variables };
+    let snippet = snippet_request(state, variables)?;
+    Ok(match language {
+        SnippetLanguage::Curl => curl(&snippet),
+        SnippetLanguage::Httpie => httpie(&snippet),
+        SnippetLanguage::Reqwest => reqwest(&snippet),
+        SnippetLanguage::PythonRequests => python_requests(&snippet),
+        SnippetLanguage::Fetch => fetch(&snippet),
+        SnippetLanguage::GoNetHttp => go_net_http(&snippet),
+    })
+}
+
+fn snippet_request(state: &TabState, variables: &Map<String, Value>) -> Result<Snippet, String> {
+    let inject = |text: &String| inject_environment(text, variables).0;
+    let credentials = match state.auth.mode {
+        AuthMode::Basic => Credentials::Basic(
+            inject(&state.auth.basic.username),
+            inject(&state.auth.basic.password),
+        ),
+        AuthMode::Digest => Credentials::Digest(
+            inject(&state.auth.digest.username),
+            inject(&state.auth.digest.password),
+        ),
+        _ => Credentials::None,
+    };
+    let (request, _) = if credentials == Credentials::None {
+        resolve_request(state, variables)?
+    } else {
+        let mut state = state.clone();
+        state.auth.mode = AuthMode::None;
+        resolve_request(&state, variables)?
+    };
+    Ok(Snippet {
+        method: request.method,
+        url: request.url,
+        headers: request.headers.headers,
+        body: String::from_utf8_lossy(&request.body).into_owned(),
+        credentials,
+    })
+}
+
+// Single quotes keep everything literal in POSIX shells, a quote itself is
+// closed, escaped and reopened.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+// A json string is also a valid string literal in Python, JavaScript and Go.
+fn string_literal(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+fn curl(snippet: &Snippet) -> String {
+    let mut lines = vec![];
+    match snippet.method.as_str() {
+        "GET" => lines.push(format!("curl {}", shell_quote(&snippet.url))),
+        method => lines.push(format!("curl -X {} {}", method, shell_quote(&snippet.url))),
+    }
+    for (name, value) in &snippet.headers {
+        lines.push(format!(
+            "-H {}",
+            shell_quote(&format!("{}: {}", name, value))
+        ));
+    }
+    match &snippet.credentials {
+        Credentials::None => (),
+        Credentials::Basic(username, password) => lines.push(format!(
+            "-u {}",
+            shell_quote(&format!("{}:{}", username, password))
+        )),
+        Credentials::Digest(username, password) => lines.push(format!(
+            "--digest -u {}",
+            shell_quote(&format!("{}:{}", username, password))
+        )),
+    }
+    if !snippet.body.is_empty() {
+        lines.push(format!("--data-raw {}", shell_quote(&snippet.body)));
+    }
+    lines.join(" \\\n  ")
+}
+
+fn httpie(snippet: &Snippet) -> String {
+    let mut lines = vec![format!(
+        "http {} {}",
+        snippet.method,
+        shell_quote(&snippet.url)
+    )];
+    match &snippet.credentials {
+        Credentials::None => (),
+        Credentials::Basic(username, password) => lines.push(format!(
+            "-a {}",
+            shell_quote(&format!("{}:{}", username, password))
+        )),
+        Credentials::Digest(username, password) => lines.push(format!(
+            "-A digest -a {}",
+            shell_quote(&format!("{}:{}", username, password))
+        )),
+    }
+    if !snippet.body.is_empty() {
+        lines.push(format!("--raw {}", shell_quote(&snippet.body)));
+    }
+    for (name, value) in &snippet.headers {
+        // `Name;` is how HTTPie sends a header without a value
+        let item = match value.is_empty() {
+            true => format!("{};", name),
+            false => format!("{}:{}", name, value),
+        };
+        lines.push(shell_quote(&item));
+    }
+    lines.join(" \\\n  ")
+}
+
+fn reqwest(snippet: &Snippet) -> String {
+    // Debug formatting of a str is a valid Rust string literal
+    let mut code = String::from("fn main() -> Result<(), Box<dyn std::error::Error>> {\n");
+    code.push_str("    let client = reqwest::blocking::Client::new();\n");
+    code.push_str("    let response = client\n");
+    code.push_str(&format!(
+        "        .request(reqwest::Method::{}, {:?})\n",
+        snippet.method, snippet.url
+    ));
+    for (name, value) in &snippet.headers {
+        code.push_str(&format!("        .header({:?}, {:?})\n", name, value));
+    }
+    match &snippet.credentials {
+        Credentials::None => (),
+        Credentials::Basic(username, password) => code.push_str(&format!(
+            "        .basic_auth({:?}, Some({:?}))\n",
+            username, password
+        )),
+        Credentials::Digest(..) => {
+            code.push_str("        // Digest auth isn't supported by reqwest\n")
+        }
+    }
+    if !snippet.body.is_empty() {
+        code.push_str(&format!("        .body({:?})\n", snippet.body));
+    }
+    code.push_str("        .send()?;\n");
+    code.push_str("    println!(\"{}\", response.status());\n");
+    code.push_str("    println!(\"{}\", response.text()?);\n");
+    code.push_str("    Ok(())\n");
+    code.push_str("}\n");
+    code
+}
+
+fn python_requests(snippet: &Snippet) -> String {
+    let mut code = String::from("import requests\n");
+    if let Credentials::Digest(..) = snippet.credentials {
+        code.push_str("from requests.auth import HTTPDigestAuth\n");
+    }
+    code.push_str("\nresponse = requests.request(\n");
+    code.push_str(&format!("    {},\n", string_literal(&snippet.method)));
+    code.push_str(&format!("    {},\n", string_literal(&snippet.url)));
+    if !snippet.headers.is_empty() {
+        code.push_str("    headers={\n");
+        for (name, value) in &snippet.headers {
+            code.push_str(&format!(
+                "        {}: {},\n",
+                string_literal(name),
+                string_literal(value)
+            ));
+        }
+        code.push_str("    },\n");
+    }
+    match &snippet.credentials {
+        Credentials::None => (),
+        Credentials::Basic(username, password) => code.push_str(&format!(
+            "    auth=({}, {}),\n",
+            string_literal(username),
+            string_literal(password)
+        )),
+        Credentials::Digest(username, password) => code.push_str(&format!(
+            "    auth=HTTPDigestAuth({}, {}),\n",
+            string_literal(username),
+            string_literal(password)
+        )),
+    }
+    if !snippet.body.is_empty() {
+        // Encoded so text that isn't latin-1 is sent as utf-8
+        code.push_str(&format!(
+            "    data={}.encode(),\n",
+            string_literal(&snippet.body)
+        ));
+    }
+    code.push_str(")\n");
+    code.push_str("print(response.status_code)\n");
+    code.push_str("print(response.text)\n");
+    code
+}
+
+fn fetch(snippet: &Snippet) -> String {
+    let mut code = format!(
+        "const response = await fetch({}, {{\n",
+        string_literal(&snippet.url)
+    );
+    code.push_str(&format!("  method: {},\n", string_literal(&snippet.method)));
+    // Encoded here, btoa only takes latin1 text
+    let basic = match &snippet.credentials {
+        Credentials::Basic(username, password) => {
+            Some(STANDARD.encode(format!("{}:{}", username, password)))
+        }
+        _ => None,
+    };
+    if !snippet.headers.is_empty() || basic.is_some() {
+        code.push_str("  headers: {\n");
+        for (name, value) in &snippet.headers {
+            code.push_str(&format!(
+                "    {}: {},\n",
+                string_literal(name),
+                string_literal(value)
+            ));
+        }
+        if let Some(credentials) = basic {
+            code.push_str(&format!(
+                "    \"Authorization\": {},\n",
+                string_literal(&format!("Basic {}", credentials))
+            ));
+        }
+        code.push_str("  },\n");
+    }
+    if let Credentials::Digest(..) = snippet.credentials {
+        code.push_str("  // Digest auth isn't supported by fetch\n");
+    }
+    if !snippet.body.is_empty() {
+        code.push_str(&format!("  body: {},\n", string_literal(&snippet.body)));
+    }
+    code.push_str("});\n");
+    code.push_str("console.log(response.status);\n");
+    code.push_str("console.log(await response.text());\n");
+    code
+}
+
+fn go_net_http(snippet: &Snippet) -> String {
+    let has_body = !snippet.body.is_empty();
+    let mut code = String::from("package main\n\nimport (\n\t\"fmt\"\n\t\"io\"\n\t\"net/http\"\n");
+    if has_body {
+        code.push_str("\t\"strings\"\n");
+    }
+    code.push_str(")\n\nfunc main() {\n");
+    let body = match has_body {
+        true => format!("strings.NewReader({})", string_literal(&snippet.body)),
+        false => "nil".to_owned(),
+    };
+    code.push_str(&format!(
+        "\treq, err := http.NewRequest({}, {}, {})\n",
+        string_literal(&snippet.method),
+        string_literal(&snippet.url),
+        body
+    ));
+    code.push_str("\tif err != nil {\n\t\tpanic(err)\n\t}\n");
+    for (name, value) in &snippet.headers {
+        code.push_str(&format!(
+            "\treq.Header.Set({}, {})\n",
+            string_literal(name),
+            string_literal(value)
+        ));
+    }
+    match &snippet.credentials {
+        Credentials::None => (),
+        Credentials::Basic(username, password) => code.push_str(&format!(
+            "\treq.SetBasicAuth({}, {})\n",
+            string_literal(username),
+            string_literal(password)
+        )),
+        Credentials::Digest(..) => code.push_str("\t// Digest auth isn't supported by net/http\n"),
+    }
+    code.push_str("\tresp, err := http.DefaultClient.Do(req)\n");
+    code.push_str("\tif err != nil {\n\t\tpanic(err)\n\t}\n");
+    code.push_str("\tdefer resp.Body.Close()\n");
+    code.push_str("\tdata, err := io.ReadAll(resp.Body)\n");
+    code.push_str("\tif err != nil {\n\t\tpanic(err)\n\t}\n");
+    code.push_str("\tfmt.Println(resp.Status)\n");
+    code.push_str("\tfmt.Println(string(data))\n");
+    code.push_str("}\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_shell_arguments() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("$HOME \"a\" `b`"), "'$HOME \"a\" `b`'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn escapes_string_literals() {
+        assert_eq!(string_literal("plain"), "\"plain\"");
+        assert_eq!(
+            string_literal("{\"a\": \"b\\c\"}\n\t"),
+            r#""{\"a\": \"b\\c\"}\n\t""#
+        );
+        assert_eq!(string_literal("é\u{1}"), "\"é\\u0001\"");
+    }
+
+    #[test]
+    fn fetch_encodes_basic_credentials() {
+        let snippet = Snippet {
+            method: "GET".to_owned(),
+            url: "https://example.com".to_owned(),
+            headers: vec![],
+            body: "".to_owned(),
+            credentials: Credentials::Basic("jäsøn".to_owned(), "pässword".to_owned()),
+        };
+        let code = fetch(&snippet);
+        assert!(!code.contains("btoa"));
+        let encoded = STANDARD.encode("jäsøn:pässword");
+        assert!(code.contains(&format!("    \"Authorization\": \"Basic {}\",\n", encoded)));
+    }
+}
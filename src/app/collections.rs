@@ -0,0 +1,161 @@
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
+
+use super::auth::Auth;
+use super::request_method::RequestMethod;
+use super::response_capture::CaptureRule;
+use super::tab_state::TabState;
+
+// The parts of a tab that make up a request, the environment and the response
+// stay with the tab.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedRequest {
+    pub id: String,
+    pub name: String,
+    pub method: RequestMethod,
+    pub url: String,
+    pub request_header_keys: Vec<String>,
+    pub request_header_values: Vec<String>,
+    pub query_param_keys: Vec<String>,
+    pub query_param_values: Vec<String>,
+    pub request_body: String,
+    #[serde(default)]
+    pub auth: Auth,
+    #[serde(default)]
+    pub capture_rules: Vec<CaptureRule>,
+}
+
+impl SavedRequest {
+    pub fn new(name: String, state: &TabState) -> Self {
+        let id = Alphanumeric.sample_string(&mut rand::thread_rng(), 16);
+        Self::from_state(id, name, state)
+    }
+
+    fn from_state(id: String, name: String, state: &TabState) -> Self {
+        Self {
+            id,
+            name,
+            method: state.method,
+            url: state.url.clone(),
+            request_header_keys: state.request_header_keys.clone(),
+            request_header_values: state.request_header_values.clone(),
+            query_param_keys: state.query_param_keys.clone(),
+            query_param_values: state.query_param_values.clone(),
+            request_body: state.request_body.clone(),
+            auth: state.auth.clone(),
+            capture_rules: state.capture_rules.clone(),
+        }
+    }
+
+    // Overwrites the saved request with the tab, keeping its id and name.
+    pub fn update(&mut self, state: &TabState) {
+        *self = Self::from_state(self.id.clone(), self.name.clone(), state);
+    }
+
+    pub fn to_state(&self) -> TabState {
+        TabState {
+            method: self.method,
+            url: self.url.clone(),
+            request_header_keys: self.request_header_keys.clone(),
+            request_header_values: self.request_header_values.clone(),
+            query_param_keys: self.query_param_keys.clone(),
+            query_param_values: self.query_param_values.clone(),
+            request_body: self.request_body.clone(),
+            auth: self.auth.clone(),
+            capture_rules: self.capture_rules.clone(),
+            saved_request: Some(self.id.clone()),
+            ..Default::default()
+        }
+    }
+
+    // Compared as json so every part of the auth settings counts without
+    // having to keep an equality impl in sync with them.
+    pub fn matches(&self, state: &TabState) -> bool {
+        let current = Self::from_state(self.id.clone(), self.name.clone(), state);
+        serde_json::to_value(self).ok() == serde_json::to_value(current).ok()
+    }
+}
+
+// A collection is a top level folder.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Folder {
+    pub name: String,
+    #[serde(default)]
+    pub folders: Vec<Folder>,
+    #[serde(default)]
+    pub requests: Vec<SavedRequest>,
+}
+
+impl Folder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn find(&self, id: &str) -> Option<&SavedRequest> {
+        self.requests
+            .iter()
+            .find(|request| request.id == id)
+            .or_else(|| self.folders.iter().find_map(|folder| folder.find(id)))
+    }
+
+    fn find_mut(&mut self, id: &str) -> Option<&mut SavedRequest> {
+        if let Some(index) = self.requests.iter().position(|request| request.id == id) {
+            return self.requests.get_mut(index);
+        }
+        self.folders
+            .iter_mut()
+            .find_map(|folder| folder.find_mut(id))
+    }
+
+    fn paths(&self, path: Vec<usize>, name: String, paths: &mut Vec<(Vec<usize>, String)>) {
+        paths.push((path.clone(), name.clone()));
+        for (index, folder) in self.folders.iter().enumerate() {
+            let mut folder_path = path.clone();
+            folder_path.push(index);
+            folder.paths(folder_path, format!("{} / {}", name, folder.name), paths);
+        }
+    }
+}
+
+pub fn find_request<'a>(collections: &'a [Folder], id: &str) -> Option<&'a SavedRequest> {
+    collections.iter().find_map(|folder| folder.find(id))
+}
+
+pub fn find_request_mut<'a>(
+    collections: &'a mut [Folder],
+    id: &str,
+) -> Option<&'a mut SavedRequest> {
+    collections
+        .iter_mut()
+        .find_map(|folder| folder.find_mut(id))
+}
+
+// Every collection and folder with its position in the tree, in display order.
+pub fn folder_paths(collections: &[Folder]) -> Vec<(Vec<usize>, String)> {
+    let mut paths = vec![];
+    for (index, collection) in collections.iter().enumerate() {
+        collection.paths(vec![index], collection.name.clone(), &mut paths);
+    }
+    paths
+}
+
+pub fn folder_at_mut<'a>(collections: &'a mut [Folder], path: &[usize]) -> Option<&'a mut Folder> {
+    let (first, rest) = path.split_first()?;
+    let mut folder = collections.get_mut(*first)?;
+    for index in rest {
+        folder = folder.folders.get_mut(*index)?;
+    }
+    Some(folder)
+}
+
+// Whether the tab was opened from or saved to a request that it no longer matches.
+pub fn has_unsaved_changes(collections: &[Folder], state: &TabState) -> bool {
+    state
+        .saved_request
+        .as_ref()
+        .and_then(|id| find_request(collections, id))
+        .is_some_and(|saved| !saved.matches(state))
+}
@@ -0,0 +1,675 @@
+use std::fs;
+
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use url::form_urlencoded;
+
+use super::auth::AuthMode;
+use super::import::{multipart_body, multipart_content_type, ImportedRequest};
+use super::tab_state::TabState;
+
+// Short options that take a value, the value can follow the letter directly.
+const SHORT_WITH_VALUE: &str = "XHdFuAebomxTwEcKrUyYztCDQ";
+
+// Options that only change what curl prints or how it connects.
+const IGNORED: &[&str] = &[
+    "-s",
+    "--silent",
+    "-S",
+    "--show-error",
+    "-v",
+    "--verbose",
+    "-i",
+    "--include",
+    "-O",
+    "--remote-name",
+    "-f",
+    "--fail",
+    "--fail-with-body",
+    "-#",
+    "--progress-bar",
+    "--no-progress-meter",
+    "-N",
+    "--no-buffer",
+    "-g",
+    "--globoff",
+    "--compressed",
+    "--path-as-is",
+    "--http1.0",
+    "--http1.1",
+    "--http2",
+    "--http2-prior-knowledge",
+    "--http3",
+    "-4",
+    "--ipv4",
+    "-6",
+    "--ipv6",
+    "--basic",
+];
+
+const IGNORED_WITH_VALUE: &[&str] = &[
+    "-o",
+    "--output",
+    "-w",
+    "--write-out",
+    "-D",
+    "--dump-header",
+    "-c",
+    "--cookie-jar",
+    "--retry",
+    "--retry-delay",
+    "--retry-max-time",
+];
+
+pub fn is_curl_command(text: &str) -> bool {
+    let text = text.trim_start();
+    text.starts_with("curl ") || text.starts_with("curl.exe ")
+}
+
+// Replaces the request of the tab with the one of a curl command, what
+// can't be converted is returned as report lines. Files of `@file` bodies are
+// only read with `read_files`, every file read is listed in the report.
+pub fn apply_curl_command(
+    state: &mut TabState,
+    command: &str,
+    read_files: bool,
+) -> Result<Vec<String>, String> {
+    let mut report = vec![];
+    let request = parse_curl_command(command, state, read_files, &mut report)?;
+    let saved = request.into_saved("curl", &mut report);
+    state.method = saved.method;
+    state.url = saved.url;
+    state.request_header_keys = saved.request_header_keys;
+    state.request_header_values = saved.request_header_values;
+    state.request_body = saved.request_body;
+    state.auth = saved.auth;
+    // Sending fills them from the url as well, set now so the tab shows them
+    let (keys, values) = query_params(&state.url);
+    state.query_param_keys = keys;
+    state.query_param_values = values;
+    Ok(report)
+}
+
+fn parse_curl_command(
+    command: &str,
+    state: &TabState,
+    read_files: bool,
+    report: &mut Vec<String>,
+) -> Result<ImportedRequest, String> {
+    let mut arguments = split_arguments(command)?.into_iter();
+    match arguments.next() {
+        Some(program) if program == "curl" || program == "curl.exe" => {}
+        _ => return Err("Not a curl command".to_owned()),
+    }
+
+    let mut request = ImportedRequest {
+        name: "curl".to_owned(),
+        ..Default::default()
+    };
+    let mut method = None;
+    let mut head = false;
+    let mut get = false;
+    let mut data: Vec<String> = vec![];
+    let mut json = false;
+    let mut form: Vec<(String, String, String)> = vec![];
+    let mut user = None;
+    let mut digest = false;
+    let mut aws_sigv4 = None;
+    let mut urls: Vec<String> = vec![];
+
+    // Options with their value, the value is empty for flags
+    let mut options = vec![];
+    let mut positional_only = false;
+    while let Some(argument) = arguments.next() {
+        if positional_only || !argument.starts_with('-') || argument == "-" {
+            urls.push(argument);
+        } else if argument == "--" {
+            positional_only = true;
+        } else if argument.starts_with("--") {
+            let value = match takes_value(&argument) {
+                true => arguments
+                    .next()
+                    .ok_or(format!("{} is missing its value", argument))?,
+                false => String::new(),
+            };
+            options.push((argument, value));
+        } else {
+            // Short options can be grouped, as in `-sSL` or `-XPOST`
+            let letters: Vec<char> = argument.chars().skip(1).collect();
+            for (index, letter) in letters.iter().enumerate() {
+                let option = format!("-{}", letter);
+                if takes_value(&option) {
+                    let mut value: String = letters[index + 1..].iter().collect();
+                    if value.is_empty() {
+                        value = arguments
+                            .next()
+                            .ok_or(format!("{} is missing its value", option))?;
+                    }
+                    options.push((option, value));
+                    break;
+                }
+                options.push((option, String::new()));
+            }
+        }
+    }
+
+    for (option, value) in options {
+        match option.as_str() {
+            "-X" | "--request" => method = Some(value),
+            "-H" | "--header" => match value.split_once(':') {
+                // `Name:` removes a header curl would add, there's nothing to remove
+                Some((_, header_value)) if header_value.trim().is_empty() => {}
+                Some((name, header_value)) => request
+                    .headers
+                    .push((name.trim().to_owned(), header_value.trim().to_owned())),
+                None => match value.strip_suffix(';') {
+                    Some(name) => request
+                        .headers
+                        .push((name.trim().to_owned(), "".to_owned())),
+                    None => report.push(format!("curl: header {} was skipped", value)),
+                },
+            },
+            "-d" | "--data" | "--data-ascii" => match value.strip_prefix('@') {
+                Some(path) => {
+                    let contents = read_file(path, read_files, report);
+                    data.push(contents.replace(['\r', '\n'], ""));
+                }
+                None => data.push(value),
+            },
+            "--data-raw" => data.push(value),
+            "--data-binary" => match value.strip_prefix('@') {
+                Some(path) => data.push(read_file(path, read_files, report)),
+                None => data.push(value),
+            },
+            "--json" => {
+                json = true;
+                match value.strip_prefix('@') {
+                    Some(path) => data.push(read_file(path, read_files, report)),
+                    None => data.push(value),
+                }
+            }
+            "--data-urlencode" => data.push(data_urlencode(&value, read_files, report)),
+            "-F" | "--form" => {
+                if let Some(part) = form_part(&value, read_files, report) {
+                    form.push(part);
+                }
+            }
+            "--form-string" => {
+                let (name, part_value) = value.split_once('=').unwrap_or((&value, ""));
+                form.push((name.to_owned(), "".to_owned(), part_value.to_owned()));
+            }
+            "-G" | "--get" => get = true,
+            "-I" | "--head" => head = true,
+            "-u" | "--user" => user = Some(value),
+            "--digest" => digest = true,
+            "--oauth2-bearer" => {
+                request.auth.mode = AuthMode::Bearer;
+                request.auth.bearer.token = value;
+            }
+            "--aws-sigv4" => aws_sigv4 = Some(value),
+            "-A" | "--user-agent" => request.headers.push(("User-Agent".to_owned(), value)),
+            "-e" | "--referer" => request.headers.push(("Referer".to_owned(), value)),
+            "-b" | "--cookie" => {
+                if value.contains('=') {
+                    request.headers.push(("Cookie".to_owned(), value));
+                } else {
+                    report.push(format!("curl: cookie file {} was skipped", value));
+                }
+            }
+            "--url" => urls.push(value),
+            "-k" | "--insecure" => {
+                if !state.project.request.accept_invalid_certs {
+                    report.push(format!(
+                        "curl: {} accepts invalid certificates, enable it in the project settings",
+                        option
+                    ));
+                }
+            }
+            "-L" | "--location" => {
+                if !state.project.request.follow_redirects {
+                    report.push(format!(
+                        "curl: {} follows redirects, enable it in the project settings",
+                        option
+                    ));
+                }
+            }
+            "-m" | "--max-time" => {
+                let seconds = value.parse::<f64>().map(|seconds| seconds.ceil() as u64);
+                if seconds != Ok(state.project.request.timeout_seconds) {
+                    report.push(format!(
+                        "curl: {} {} sets a timeout, set it in the project settings",
+                        option, value
+                    ));
+                }
+            }
+            "-T" | "--upload-file" => {
+                report.push(format!(
+                    "curl: upload of file {} was skipped, file bodies aren't supported",
+                    value
+                ));
+                method.get_or_insert_with(|| "PUT".to_owned());
+            }
+            "--ntlm" | "--negotiate" | "--anyauth" => report.push(format!(
+                "curl: {} auth isn't supported, the credentials were sent as basic auth",
+                option.trim_start_matches('-')
+            )),
+            option if IGNORED.contains(&option) || IGNORED_WITH_VALUE.contains(&option) => {}
+            option if value.is_empty() => {
+                report.push(format!("curl: option {} was ignored", option))
+            }
+            option => report.push(format!("curl: option {} {} was ignored", option, value)),
+        }
+    }
+
+    let mut urls = urls.into_iter();
+    let url = urls.next().ok_or("The curl command has no url")?;
+    for extra in urls {
+        report.push(format!(
+            "curl: only the first url is used, {} was skipped",
+            extra
+        ));
+    }
+    request.url = if url.contains("://") || url.starts_with('{') {
+        url
+    } else {
+        format!("http://{}", url)
+    };
+
+    if let Some(user) = user {
+        let (username, password) = match user.split_once(':') {
+            Some((username, password)) => (username.to_owned(), password.to_owned()),
+            None => (user, "".to_owned()),
+        };
+        if let Some(provider) = aws_sigv4 {
+            // `provider1[:provider2[:region[:service]]]`, region and service
+            // are taken from the host by curl when they're missing
+            let mut parts = provider.split(':').skip(2);
+            request.auth.mode = AuthMode::AwsSigV4;
+            request.auth.aws.access_key = username;
+            request.auth.aws.secret_key = password;
+            request.auth.aws.region = parts.next().unwrap_or_default().to_owned();
+            request.auth.aws.service = parts.next().unwrap_or_default().to_owned();
+            if request.auth.aws.service.is_empty() {
+                report.push("curl: --aws-sigv4 has no region or service, fill them in".to_owned());
+            }
+        } else if digest {
+            request.auth.mode = AuthMode::Digest;
+            request.auth.digest.username = username;
+            request.auth.digest.password = password;
+        } else {
+            request.auth.mode = AuthMode::Basic;
+            request.auth.basic.username = username;
+            request.auth.basic.password = password;
+        }
+    }
+
+    if !data.is_empty() && get {
+        let separator = if request.url.contains('?') { '&' } else { '?' };
+        request.url = format!("{}{}{}", request.url, separator, data.join("&"));
+    } else if !data.is_empty() {
+        request.body = data.join("&");
+        if json {
+            request.set_content_type("application/json");
+            if !request.has_header("Accept") {
+                request
+                    .headers
+                    .push(("Accept".to_owned(), "application/json".to_owned()));
+            }
+        } else {
+            request.set_content_type("application/x-www-form-urlencoded");
+        }
+    }
+    if !form.is_empty() {
+        if !data.is_empty() {
+            report.push("curl: -F can't be combined with -d, the form was skipped".to_owned());
+        } else {
+            // The boundary of a Content-Type given by hand wouldn't match the body
+            request.headers.retain(|(name, value)| {
+                !(name.eq_ignore_ascii_case("Content-Type") && value.starts_with("multipart/"))
+            });
+            request.body = multipart_body(&form);
+            request.set_content_type(&multipart_content_type());
+        }
+    }
+
+    let has_body = !request.body.is_empty();
+    request.method = match method {
+        Some(method) => method,
+        None if head => "HEAD".to_owned(),
+        None if has_body => "POST".to_owned(),
+        None => "GET".to_owned(),
+    };
+    Ok(request)
+}
+
+fn takes_value(option: &str) -> bool {
+    match option.strip_prefix('-') {
+        Some(letter) if letter.len() == 1 => SHORT_WITH_VALUE.contains(letter),
+        _ => {
+            IGNORED_WITH_VALUE.contains(&option)
+                || matches!(
+                    option,
+                    "--request"
+                        | "--header"
+                        | "--data"
+                        | "--data-ascii"
+                        | "--data-raw"
+                        | "--data-binary"
+                        | "--data-urlencode"
+                        | "--json"
+                        | "--form"
+                        | "--form-string"
+                        | "--user"
+                        | "--user-agent"
+                        | "--referer"
+                        | "--cookie"
+                        | "--url"
+                        | "--oauth2-bearer"
+                        | "--aws-sigv4"
+                        | "--max-time"
+                        | "--connect-timeout"
+                        | "--max-redirs"
+                        | "--upload-file"
+                        | "--proxy"
+                        | "--cert"
+                        | "--key"
+                        | "--cacert"
+                )
+        }
+    }
+}
+
+// Splits the command like a POSIX shell would, with single, double and `$'...'`
+// quotes and lines continued with a backslash.
+fn split_arguments(command: &str) -> Result<Vec<String>, String> {
+    let mut arguments = vec![];
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_argument {
+                    arguments.push(std::mem::take(&mut current));
+                    in_argument = false;
+                }
+            }
+            '\\' => match chars.next() {
+                // A line continuation, or one that lost its newline when pasted
+                // into a single line input
+                Some(next) if next.is_whitespace() && !in_argument => {}
+                Some('\n') => {}
+                Some(next) => {
+                    current.push(next);
+                    in_argument = true;
+                }
+                None => {}
+            },
+            '\'' => {
+                in_argument = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated ' quote in the curl command".to_owned()),
+                    }
+                }
+            }
+            '"' => {
+                in_argument = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(next @ ('$' | '`' | '"' | '\\')) => current.push(next),
+                            Some('\n') => {}
+                            Some(next) => {
+                                current.push('\\');
+                                current.push(next);
+                            }
+                            None => break,
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated \" quote in the curl command".to_owned()),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_argument = true;
+                ansi_c_quoted(&mut chars, &mut current)?;
+            }
+            c => {
+                current.push(c);
+                in_argument = true;
+            }
+        }
+    }
+    if in_argument {
+        arguments.push(current);
+    }
+    Ok(arguments)
+}
+
+// `$'...'` strings, browsers use them for bodies with quotes or newlines.
+fn ansi_c_quoted(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    current: &mut String,
+) -> Result<(), String> {
+    loop {
+        match chars.next() {
+            Some('\'') => return Ok(()),
+            Some('\\') => {
+                let escaped = chars.next();
+                match escaped {
+                    Some('n') => current.push('\n'),
+                    Some('t') => current.push('\t'),
+                    Some('r') => current.push('\r'),
+                    Some(c @ ('\\' | '\'' | '"' | '?')) => current.push(c),
+                    Some(kind @ ('x' | 'u' | 'U')) => {
+                        let length = match kind {
+                            'x' => 2,
+                            'u' => 4,
+                            _ => 8,
+                        };
+                        let mut digits = String::new();
+                        while digits.len() < length {
+                            match chars.peek() {
+                                Some(c) if c.is_ascii_hexdigit() => digits.push(*c),
+                                _ => break,
+                            }
+                            chars.next();
+                        }
+                        match u32::from_str_radix(&digits, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                        {
+                            Some(c) => current.push(c),
+                            None => {
+                                current.push('\\');
+                                current.push(kind);
+                                current.push_str(&digits);
+                            }
+                        }
+                    }
+                    Some(c) => {
+                        current.push('\\');
+                        current.push(c);
+                    }
+                    None => break,
+                }
+            }
+            Some(c) => current.push(c),
+            None => break,
+        }
+    }
+    Err("Unterminated $' quote in the curl command".to_owned())
+}
+
+fn read_file(path: &str, read_files: bool, report: &mut Vec<String>) -> String {
+    if !read_files {
+        report.push(format!(
+            "curl: file {} wasn't read, use \"Request\" -> \"Import curl\" to read it",
+            path
+        ));
+        return "".to_owned();
+    }
+    match fs::read_to_string(path) {
+        Ok(contents) => {
+            report.push(format!("curl: file {} was read into the request", path));
+            contents
+        }
+        Err(err) => {
+            report.push(format!("curl: file {} couldn't be read: {}", path, err));
+            "".to_owned()
+        }
+    }
+}
+
+// `content`, `=content`, `name=content`, `@file` and `name@file`, as curl
+// reads them.
+fn data_urlencode(value: &str, read_files: bool, report: &mut Vec<String>) -> String {
+    let encode = |text: &str| utf8_percent_encode(text, NON_ALPHANUMERIC).to_string();
+    if let Some((name, content)) = value.split_once('=') {
+        return match name {
+            "" => encode(content),
+            name => format!("{}={}", name, encode(content)),
+        };
+    }
+    match value.split_once('@') {
+        Some(("", path)) => encode(&read_file(path, read_files, report)),
+        Some((name, path)) => format!("{}={}", name, encode(&read_file(path, read_files, report))),
+        None => encode(value),
+    }
+}
+
+// A `-F name=value;type=text/plain` part as (name, content type, value).
+fn form_part(
+    value: &str,
+    read_files: bool,
+    report: &mut Vec<String>,
+) -> Option<(String, String, String)> {
+    let (name, content) = value.split_once('=').unwrap_or((value, ""));
+    let (content, content_type) = match content.split_once(";type=") {
+        Some((content, content_type)) => (content, content_type.to_owned()),
+        None => (content, "".to_owned()),
+    };
+    if let Some(path) = content.strip_prefix('@') {
+        report.push(format!(
+            "curl: file {} of form field {} was skipped, file uploads aren't supported",
+            path, name
+        ));
+        return None;
+    }
+    let content = match content.strip_prefix('<') {
+        Some(path) => read_file(path, read_files, report),
+        None => content.to_owned(),
+    };
+    Some((name.to_owned(), content_type, content))
+}
+
+fn query_params(url: &str) -> (Vec<String>, Vec<String>) {
+    let query = url
+        .split_once('?')
+        .map(|(_, query)| query.split('#').next().unwrap_or_default())
+        .unwrap_or_default();
+    let (mut keys, mut values): (Vec<String>, Vec<String>) =
+        form_urlencoded::parse(query.as_bytes())
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .unzip();
+    if keys.is_empty() {
+        keys.push("".to_owned());
+        values.push("".to_owned());
+    }
+    (keys, values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(command: &str) -> Vec<String> {
+        split_arguments(command).unwrap()
+    }
+
+    #[test]
+    fn splits_quoted_arguments() {
+        assert_eq!(
+            split("curl  -H 'A: b c'  x"),
+            vec!["curl", "-H", "A: b c", "x"]
+        );
+        // Quotes next to each other make a single argument
+        assert_eq!(split(r#"a'b'"c"d"#), vec!["abcd"]);
+        assert_eq!(split("'' \"\""), vec!["", ""]);
+        // Nothing is escaped in single quotes
+        assert_eq!(split(r#"'a \"b\" \n'"#), vec![r#"a \"b\" \n"#]);
+        // Only $, `, " and \ are escaped in double quotes
+        assert_eq!(split(r#""\$a \"b\" \\ \n""#), vec![r#"$a "b" \ \n"#]);
+        assert_eq!(split(r"a\ b \'c"), vec!["a b", "'c"]);
+    }
+
+    #[test]
+    fn splits_continued_lines() {
+        assert_eq!(
+            split("curl \\\n  -X POST \\\n  example.com"),
+            vec!["curl", "-X", "POST", "example.com"]
+        );
+        // Pasted into a single line the newline is gone
+        assert_eq!(split("curl \\   example.com"), vec!["curl", "example.com"]);
+        assert_eq!(split("\"a\\\nb\""), vec!["ab"]);
+    }
+
+    #[test]
+    fn splits_ansi_c_quotes() {
+        assert_eq!(
+            split(r#"$'{\"a\":\n\t1}' $'\x41é\''"#),
+            vec!["{\"a\":\n\t1}", "Aé'"]
+        );
+        assert_eq!(split(r"$'\q \xzz'"), vec![r"\q \xzz"]);
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        assert_eq!(
+            split_arguments("curl 'a").unwrap_err(),
+            "Unterminated ' quote in the curl command"
+        );
+        assert_eq!(
+            split_arguments("curl \"a").unwrap_err(),
+            "Unterminated \" quote in the curl command"
+        );
+        assert_eq!(
+            split_arguments("curl $'a").unwrap_err(),
+            "Unterminated $' quote in the curl command"
+        );
+    }
+
+    #[test]
+    fn only_reads_files_when_asked() {
+        let path = std::env::temp_dir().join("requestor_curl_body.json");
+        fs::write(&path, "{\"a\": 1}").unwrap();
+        let command = format!("curl --data-binary @{} https://example.com", path.display());
+
+        let mut state = TabState::default();
+        let report = apply_curl_command(&mut state, &command, false).unwrap();
+        assert_eq!(state.request_body, "");
+        assert_eq!(
+            report,
+            vec![format!(
+                "curl: file {} wasn't read, use \"Request\" -> \"Import curl\" to read it",
+                path.display()
+            )]
+        );
+
+        let mut state = TabState::default();
+        let report = apply_curl_command(&mut state, &command, true).unwrap();
+        assert_eq!(state.request_body, "{\"a\": 1}");
+        assert_eq!(
+            report,
+            vec![format!(
+                "curl: file {} was read into the request",
+                path.display()
+            )]
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use md5::Md5;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use super::auth::set_header;
+use super::environment_injector::inject_environment;
+use super::http_client::{fetch_blocking, RequestOptions};
+
+// The attr-char of RFC 8187, everything else is percent-encoded in `username*`
+const ATTR_CHAR: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'!')
+    .remove(b'#')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'+')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'^')
+    .remove(b'_')
+    .remove(b'`')
+    .remove(b'|')
+    .remove(b'~');
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum DigestAlgorithm {
+    #[default]
+    Md5,
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    fn hash(&self, data: &str) -> String {
+        match self {
+            DigestAlgorithm::Md5 => format!("{:x}", Md5::digest(data.as_bytes())),
+            DigestAlgorithm::Sha256 => format!("{:x}", Sha256::digest(data.as_bytes())),
+        }
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DigestAlgorithm::Md5 => write!(f, "MD5"),
+            DigestAlgorithm::Sha256 => write!(f, "SHA-256"),
+        }
+    }
+}
+
+// The algorithm is the preferred one, servers can offer a challenge for each.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestAuth {
+    pub username: String,
+    pub password: String,
+    pub algorithm: DigestAlgorithm,
+}
+
+impl DigestAuth {
+    pub fn resolved(&self, variables: &Map<String, Value>) -> DigestAuth {
+        DigestAuth {
+            username: inject_environment(&self.username, variables).0,
+            password: inject_environment(&self.password, variables).0,
+            algorithm: self.algorithm,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Challenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    /// `auth` when the server supports it, otherwise the RFC 2069 response is used.
+    qop: Option<String>,
+    algorithm: DigestAlgorithm,
+    session: bool,
+}
+
+// One request of the exchange, shown in the Info tab of the response.
+#[derive(Debug, Clone)]
+pub struct DigestLeg {
+    pub request: String,
+    pub authorization: Option<String>,
+    pub status: String,
+    pub challenge: Option<String>,
+}
+
+// The last challenge of every origin, so following requests reuse the nonce and
+// count it instead of doing the round-trip again.
+#[derive(Default, Clone)]
+pub struct DigestSession {
+    challenges: Arc<Mutex<HashMap<String, (Challenge, u32)>>>,
+}
+
+impl DigestSession {
+    fn next(&self, origin: &str) -> Option<(Challenge, u32)> {
+        let mut challenges = self.challenges.lock().unwrap();
+        let (challenge, count) = challenges.get_mut(origin)?;
+        *count += 1;
+        Some((challenge.clone(), *count))
+    }
+
+    fn store(&self, origin: String, challenge: Challenge) {
+        self.challenges
+            .lock()
+            .unwrap()
+            .insert(origin, (challenge, 1));
+    }
+}
+
+// Sends the request and answers the 401 challenge of the server. Blocks, so it
+// runs on the thread of the request.
+pub fn fetch_with_digest(
+    request: &ehttp::Request,
+    auth: &DigestAuth,
+    session: &DigestSession,
+    options: &RequestOptions,
+) -> Result<(ehttp::Response, Vec<DigestLeg>), String> {
+    let origin = Url::parse(&request.url)
+        .map_err(|err| err.to_string())?
+        .origin()
+        .ascii_serialization();
+    let mut legs = vec![];
+
+    // A reused nonce the server considers stale is answered with a new challenge
+    let response = match session.next(&origin) {
+        Some((challenge, count)) => {
+            let authorized = authorize(request, auth, &challenge, count)?;
+            let response = fetch_blocking(&authorized, options)?;
+            legs.push(leg(&authorized, &response));
+            response
+        }
+        None => {
+            let response = fetch_blocking(request, options)?;
+            legs.push(leg(request, &response));
+            response
+        }
+    };
+    if response.status != 401 {
+        return Ok((response, legs));
+    }
+
+    let header = response.headers.get("www-authenticate").unwrap_or_default();
+    let challenge = select_challenge(header, auth.algorithm)?;
+    session.store(origin, challenge.clone());
+
+    let authorized = authorize(request, auth, &challenge, 1)?;
+    let response = fetch_blocking(&authorized, options)?;
+    legs.push(leg(&authorized, &response));
+    Ok((response, legs))
+}
+
+fn authorize(
+    request: &ehttp::Request,
+    auth: &DigestAuth,
+    challenge: &Challenge,
+    count: u32,
+) -> Result<ehttp::Request, String> {
+    let url = Url::parse(&request.url).map_err(|err| err.to_string())?;
+    let uri = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_owned(),
+    };
+    let algorithm = challenge.algorithm;
+    let cnonce = Alphanumeric.sample_string(&mut rand::thread_rng(), 16);
+    let nc = format!("{:08x}", count);
+    let response = digest_response(auth, challenge, &request.method, &uri, &nc, &cnonce);
+
+    // Names that aren't ASCII are sent percent-encoded in `username*`
+    let mut value = match auth.username.is_ascii() {
+        true => format!("Digest username={}", quoted(&auth.username)),
+        false => format!(
+            "Digest username*=UTF-8''{}",
+            utf8_percent_encode(&auth.username, ATTR_CHAR)
+        ),
+    };
+    value.push_str(&format!(
+        ", realm={}, nonce={}, uri={}, algorithm={}{}",
+        quoted(&challenge.realm),
+        quoted(&challenge.nonce),
+        quoted(&uri),
+        algorithm,
+        if challenge.session { "-sess" } else { "" }
+    ));
+    if let Some(qop) = &challenge.qop {
+        value.push_str(&format!(", qop={}, nc={}, cnonce=\"{}\"", qop, nc, cnonce));
+    }
+    value.push_str(&format!(", response=\"{}\"", response));
+    if let Some(opaque) = &challenge.opaque {
+        value.push_str(&format!(", opaque={}", quoted(opaque)));
+    }
+
+    let mut authorized = request.clone();
+    set_header(&mut authorized.headers, "Authorization".to_owned(), value);
+    Ok(authorized)
+}
+
+fn digest_response(
+    auth: &DigestAuth,
+    challenge: &Challenge,
+    method: &str,
+    uri: &str,
+    nc: &str,
+    cnonce: &str,
+) -> String {
+    let algorithm = challenge.algorithm;
+    let mut ha1 = algorithm.hash(&format!(
+        "{}:{}:{}",
+        auth.username, challenge.realm, auth.password
+    ));
+    if challenge.session {
+        ha1 = algorithm.hash(&format!("{}:{}:{}", ha1, challenge.nonce, cnonce));
+    }
+    let ha2 = algorithm.hash(&format!("{}:{}", method, uri));
+    match &challenge.qop {
+        Some(qop) => algorithm.hash(&format!(
+            "{}:{}:{}:{}:{}:{}",
+            ha1, challenge.nonce, nc, cnonce, qop, ha2
+        )),
+        None => algorithm.hash(&format!("{}:{}:{}", ha1, challenge.nonce, ha2)),
+    }
+}
+
+// A quoted-string, the quote and backslash are escaped with a backslash.
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn leg(request: &ehttp::Request, response: &ehttp::Response) -> DigestLeg {
+    DigestLeg {
+        request: format!("{} {}", request.method, request.url),
+        authorization: request.headers.get("Authorization").map(|v| v.to_owned()),
+        status: format!("{} {}", response.status, response.status_text),
+        challenge: response
+            .headers
+            .get("www-authenticate")
+            .map(|v| v.to_owned()),
+    }
+}
+
+// Picks the Digest challenge with the preferred algorithm, or the first one
+// that can be answered.
+fn select_challenge(header: &str, preferred: DigestAlgorithm) -> Result<Challenge, String> {
+    let mut supported = vec![];
+    let mut problem = None;
+    for (scheme, params) in parse_challenges(header) {
+        if !scheme.eq_ignore_ascii_case("digest") {
+            continue;
+        }
+        match challenge(&params) {
+            Ok(challenge) => supported.push(challenge),
+            Err(error) => problem = Some(error),
+        }
+    }
+
+    if let Some(index) = supported.iter().position(|c| c.algorithm == preferred) {
+        return Ok(supported.swap_remove(index));
+    }
+    match (supported.into_iter().next(), problem) {
+        (Some(challenge), _) => Ok(challenge),
+        (None, Some(problem)) => Err(problem),
+        (None, None) if header.is_empty() => {
+            Err("The server answered 401 without a challenge".to_owned())
+        }
+        (None, None) => Err(format!("The server didn't ask for Digest auth: {}", header)),
+    }
+}
+
+fn challenge(params: &HashMap<String, String>) -> Result<Challenge, String> {
+    let algorithm = params.get("algorithm").map_or("MD5", |a| a.as_str());
+    let (algorithm, session) = match algorithm.to_ascii_uppercase().as_str() {
+        "MD5" => (DigestAlgorithm::Md5, false),
+        "MD5-SESS" => (DigestAlgorithm::Md5, true),
+        "SHA-256" => (DigestAlgorithm::Sha256, false),
+        "SHA-256-SESS" => (DigestAlgorithm::Sha256, true),
+        other => return Err(format!("The {} Digest algorithm isn't supported", other)),
+    };
+    let qop = match params.get("qop") {
+        Some(qop) if qop.split(',').any(|q| q.trim() == "auth") => Some("auth".to_owned()),
+        Some(qop) => return Err(format!("Digest qop={} isn't supported", qop)),
+        None => None,
+    };
+    Ok(Challenge {
+        realm: params.get("realm").cloned().unwrap_or_default(),
+        nonce: params
+            .get("nonce")
+            .cloned()
+            .ok_or("The challenge has no nonce")?,
+        opaque: params.get("opaque").cloned(),
+        qop,
+        algorithm,
+        session,
+    })
+}
+
+// Splits a WWW-Authenticate header into its challenges. A challenge is a scheme
+// followed by `key=value` params, values can be quoted and contain commas.
+fn parse_challenges(header: &str) -> Vec<(String, HashMap<String, String>)> {
+    let mut challenges: Vec<(String, HashMap<String, String>)> = vec![];
+    let mut chars = header.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        let mut token = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ',' && *c != '=') {
+            token.push(c);
+        }
+        if token.is_empty() {
+            break;
+        }
+        while chars.next_if(|c| *c == ' ').is_some() {}
+
+        if chars.next_if_eq(&'=').is_none() {
+            challenges.push((token, HashMap::new()));
+            continue;
+        }
+        while chars.next_if(|c| *c == ' ').is_some() {}
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    _ => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                value.push(c);
+            }
+        }
+        if let Some((_, params)) = challenges.last_mut() {
+            params.insert(token.to_ascii_lowercase(), value.trim().to_owned());
+        }
+    }
+    challenges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example of RFC 7616 section 3.9.1
+    const CHALLENGES: &str = "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", algorithm=SHA-256, nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\", Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", algorithm=MD5, nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"";
+    const CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+    fn mufasa(algorithm: DigestAlgorithm) -> DigestAuth {
+        DigestAuth {
+            username: "Mufasa".to_owned(),
+            password: "Circle of Life".to_owned(),
+            algorithm,
+        }
+    }
+
+    #[test]
+    fn parses_the_challenges_of_rfc_7616() {
+        let challenges = parse_challenges(CHALLENGES);
+        assert_eq!(challenges.len(), 2);
+        let (scheme, params) = &challenges[0];
+        assert_eq!(scheme, "Digest");
+        assert_eq!(params["realm"], "http-auth@example.org");
+        assert_eq!(params["qop"], "auth, auth-int");
+        assert_eq!(params["algorithm"], "SHA-256");
+        assert_eq!(
+            params["nonce"],
+            "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v"
+        );
+        assert_eq!(challenges[1].1["algorithm"], "MD5");
+    }
+
+    #[test]
+    fn parses_escapes_and_other_schemes() {
+        let challenges =
+            parse_challenges("Basic realm=\"a\", Digest realm=\"say \\\"hi\\\\\", nonce=n");
+        assert_eq!(challenges[0].0, "Basic");
+        assert_eq!(challenges[1].1["realm"], "say \"hi\\");
+        assert_eq!(challenges[1].1["nonce"], "n");
+    }
+
+    #[test]
+    fn computes_the_responses_of_rfc_7616() {
+        for (algorithm, expected) in [
+            (DigestAlgorithm::Md5, "8ca523f5e9506fed4657c9700eebdbec"),
+            (
+                DigestAlgorithm::Sha256,
+                "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1",
+            ),
+        ] {
+            let challenge = select_challenge(CHALLENGES, algorithm).unwrap();
+            assert_eq!(challenge.algorithm, algorithm);
+            let response = digest_response(
+                &mufasa(algorithm),
+                &challenge,
+                "GET",
+                "/dir/index.html",
+                "00000001",
+                CNONCE,
+            );
+            assert_eq!(response, expected);
+        }
+    }
+
+    #[test]
+    fn escapes_quoted_params() {
+        let challenge = select_challenge(
+            "Digest realm=\"a \\\"b\\\"\", nonce=\"n\", opaque=\"o\\\\\"",
+            DigestAlgorithm::Md5,
+        )
+        .unwrap();
+        let auth = DigestAuth {
+            username: "x\"y\\z".to_owned(),
+            ..Default::default()
+        };
+        let request = ehttp::Request::get("http://example.org/a%22b?c=\"d\"");
+        let authorized = authorize(&request, &auth, &challenge, 1).unwrap();
+        let header = authorized.headers.get("Authorization").unwrap();
+        assert!(header.starts_with("Digest username=\"x\\\"y\\\\z\", realm=\"a \\\"b\\\"\""));
+        assert!(header.contains(", opaque=\"o\\\\\""));
+        assert_eq!(parse_challenges(header)[0].1["username"], "x\"y\\z");
+    }
+
+    #[test]
+    fn encodes_names_that_arent_ascii() {
+        let challenge = select_challenge("Digest realm=\"r\", nonce=\"n\"", DigestAlgorithm::Md5);
+        let auth = DigestAuth {
+            username: "Jäsøn Doe".to_owned(),
+            ..Default::default()
+        };
+        let request = ehttp::Request::get("http://example.org/");
+        let authorized = authorize(&request, &auth, &challenge.unwrap(), 1).unwrap();
+        let header = authorized.headers.get("Authorization").unwrap();
+        assert!(header.starts_with("Digest username*=UTF-8''J%C3%A4s%C3%B8n%20Doe, "));
+    }
+}
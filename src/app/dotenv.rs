@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+
+use serde_json::{Map, Value};
+
+// A line of the file, or several lines for a quoted multi-line value.
+enum Entry<'a> {
+    Other(&'a str),
+    Assignment {
+        key: &'a str,
+        raw: String,
+        value: Result<String, String>,
+    },
+}
+
+// Parses `KEY=value` lines. Supported are `#` comments, an optional `export`
+// prefix, single quoted values (taken literally), double quoted values (with
+// escapes, may span multiple lines) and unquoted values (trimmed, a ` #` starts
+// a comment).
+pub fn parse_dotenv(contents: &str) -> Result<Map<String, Value>, String> {
+    let mut environment = Map::new();
+    for (line_number, entry) in entries(contents) {
+        match entry {
+            Entry::Assignment { key, value, .. } => {
+                let value = value.map_err(|err| format!("Line {}: {}", line_number, err))?;
+                environment.insert(key.to_owned(), Value::String(value));
+            }
+            Entry::Other(line) if is_blank_or_comment(line) => (),
+            Entry::Other(_) => {
+                return Err(format!("Line {}: expected KEY=value", line_number));
+            }
+        }
+    }
+    Ok(environment)
+}
+
+// Updates the lines of the original file in place: comments and unchanged lines
+// are kept as they were, removed keys are dropped and new keys are appended.
+pub fn serialize_dotenv(environment: &Map<String, Value>, original: &str) -> String {
+    let mut written: HashSet<&str> = HashSet::new();
+    let mut lines: Vec<String> = vec![];
+
+    for (_, entry) in entries(original) {
+        match entry {
+            Entry::Other(line) => lines.push(line.to_owned()),
+            Entry::Assignment { key, raw, value } => {
+                let new_value = match environment.get(key) {
+                    Some(new_value) if !written.contains(key) => value_text(new_value),
+                    _ => continue,
+                };
+                if value.as_ref() == Ok(&new_value) {
+                    lines.push(raw);
+                } else {
+                    lines.push(format!("{}={}", key, quote(&new_value)));
+                }
+                written.insert(key);
+            }
+        }
+    }
+
+    for (key, value) in environment {
+        if !written.contains(key.as_str()) {
+            lines.push(format!("{}={}", key, quote(&value_text(value))));
+        }
+    }
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    contents
+}
+
+// Keys assigned more than once with the line of every repeated assignment, the
+// last assignment wins.
+pub fn dotenv_duplicate_keys(contents: &str) -> Vec<(usize, String)> {
+    let mut seen = HashSet::new();
+    let mut duplicates = vec![];
+    for (line_number, entry) in entries(contents) {
+        if let Entry::Assignment { key, .. } = entry {
+            if !seen.insert(key) {
+                duplicates.push((line_number, key.to_owned()));
+            }
+        }
+    }
+    duplicates
+}
+
+fn entries(contents: &str) -> Vec<(usize, Entry<'_>)> {
+    let mut entries = vec![];
+    let mut lines = contents.lines().enumerate();
+
+    while let Some((idx, line)) = lines.next() {
+        let (key, rest) = match split_assignment(line) {
+            Some(assignment) => assignment,
+            None => {
+                entries.push((idx + 1, Entry::Other(line)));
+                continue;
+            }
+        };
+
+        let mut raw = line.to_owned();
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut quoted = quoted.to_owned();
+            // Keep reading lines until the closing quote
+            while find_closing_quote(&quoted).is_none() {
+                match lines.next() {
+                    Some((_, next)) => {
+                        raw.push('\n');
+                        raw.push_str(next);
+                        quoted.push('\n');
+                        quoted.push_str(next);
+                    }
+                    None => break,
+                }
+            }
+            match find_closing_quote(&quoted) {
+                Some(end) => Ok(unescape(&quoted[..end])),
+                None => Err(format!("missing closing quote for {}", key)),
+            }
+        } else if let Some(quoted) = rest.strip_prefix('\'') {
+            match quoted.find('\'') {
+                Some(end) => Ok(quoted[..end].to_owned()),
+                None => Err(format!("missing closing quote for {}", key)),
+            }
+        } else {
+            let value = match rest.find(" #") {
+                Some(comment) => &rest[..comment],
+                None => rest,
+            };
+            Ok(value.trim().to_owned())
+        };
+
+        entries.push((idx + 1, Entry::Assignment { key, raw, value }));
+    }
+
+    entries
+}
+
+fn split_assignment(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') {
+        return None;
+    }
+    let trimmed = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+    let (key, rest) = trimmed.split_once('=')?;
+    let key = key.trim();
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((key, rest.trim_start()))
+}
+
+fn is_blank_or_comment(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+// Position of the first double quote that isn't escaped.
+fn find_closing_quote(raw: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, c) in raw.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(idx),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape(raw: &str) -> String {
+    let mut value = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            Some(other) => value.push(other),
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => "".to_owned(),
+        _ => value.to_string(),
+    }
+}
+
+// Values are only quoted when they would not survive being read back unquoted.
+fn quote(value: &str) -> String {
+    let needs_quotes = value.starts_with(['"', '\''])
+        || value.contains(" #")
+        || value.contains(['\n', '\r', '\t'])
+        || value.trim() != value;
+    if !needs_quotes {
+        return value.to_owned();
+    }
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_quotes_comments_and_export() {
+        let contents = "# comment\nexport A=1\nB=\"multi\nline\"\nC='lit # \\n'\nD=plain value # trailing\n\nE=\"esc\\\"q\\t\"\n";
+        let environment = parse_dotenv(contents).unwrap();
+        assert_eq!(environment["A"], json!("1"));
+        assert_eq!(environment["B"], json!("multi\nline"));
+        assert_eq!(environment["C"], json!("lit # \\n"));
+        assert_eq!(environment["D"], json!("plain value"));
+        assert_eq!(environment["E"], json!("esc\"q\t"));
+    }
+
+    #[test]
+    fn reports_the_line_of_errors() {
+        assert_eq!(
+            parse_dotenv("A=1\nnot an assignment\n").unwrap_err(),
+            "Line 2: expected KEY=value"
+        );
+        assert!(parse_dotenv("A=\"open\nB=2\n")
+            .unwrap_err()
+            .contains("missing closing quote for A"));
+    }
+
+    #[test]
+    fn serialize_keeps_unchanged_lines() {
+        let original = "# keep me\nexport A=1\nB='x' # note\nC=3\n";
+        let mut environment = parse_dotenv(original).unwrap();
+        environment.insert("C".to_owned(), json!("needs quotes "));
+        environment.remove("A");
+        environment.insert("D".to_owned(), json!(4));
+        assert_eq!(
+            serialize_dotenv(&environment, original),
+            "# keep me\nB='x' # note\nC=\"needs quotes \"\nD=4\n"
+        );
+    }
+
+    #[test]
+    fn quoted_values_read_back_the_same() {
+        for value in [
+            "a #b",
+            " padded",
+            "line\nbreak",
+            "\"quoted\"",
+            "back\\slash \"x\"",
+        ] {
+            let mut environment = Map::new();
+            environment.insert("K".to_owned(), json!(value));
+            let written = serialize_dotenv(&environment, "");
+            assert_eq!(parse_dotenv(&written).unwrap(), environment, "{}", written);
+        }
+    }
+
+    #[test]
+    fn finds_duplicate_keys() {
+        let duplicates = dotenv_duplicate_keys("A=1\nB=2\nA=3\n");
+        assert_eq!(duplicates, vec![(3, "A".to_owned())]);
+    }
+}
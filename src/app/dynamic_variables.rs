@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::environment_injector::referenced_variables;
+use super::tab_state::TabState;
+
+pub const ENV_PREFIX: &str = "$env.";
+pub const CMD_PREFIX: &str = "$cmd.";
+
+// A local command whose output is available as `{$cmd.name}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandVariable {
+    pub name: String,
+    pub command: String,
+    /// 0 keeps the output until it's refreshed by hand.
+    pub ttl_seconds: u64,
+}
+
+impl Default for CommandVariable {
+    fn default() -> Self {
+        Self {
+            name: "".to_owned(),
+            command: "".to_owned(),
+            ttl_seconds: 300,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CommandOutput {
+    pub command: String,
+    pub started: Instant,
+    /// `None` while the command is still running.
+    pub result: Option<Result<String, String>>,
+}
+
+// Runs the commands on a background thread, tools such as `gcloud` can take a
+// few seconds, and keeps their output until the TTL runs out.
+#[derive(Default)]
+pub struct CommandRunner {
+    outputs: Arc<Mutex<HashMap<String, CommandOutput>>>,
+}
+
+impl CommandRunner {
+    pub fn output(&self, name: &str) -> Option<CommandOutput> {
+        self.outputs.lock().unwrap().get(name).cloned()
+    }
+
+    // Drops the cached output so the command runs again the next time it's used.
+    pub fn refresh(&self, name: &str) {
+        self.outputs.lock().unwrap().remove(name);
+    }
+
+    fn value(&self, ctx: &egui::Context, variable: &CommandVariable) -> Option<String> {
+        let mut outputs = self.outputs.lock().unwrap();
+        if is_stale(outputs.get(&variable.name), variable) {
+            let previous = outputs.get(&variable.name).and_then(|o| o.result.clone());
+            outputs.insert(
+                variable.name.clone(),
+                CommandOutput {
+                    command: variable.command.clone(),
+                    started: Instant::now(),
+                    result: None,
+                },
+            );
+            self.spawn(ctx, variable);
+            // Keep using the previous output until the new one arrives
+            return previous.and_then(|result| result.ok());
+        }
+
+        outputs
+            .get(&variable.name)
+            .and_then(|output| output.result.clone())
+            .and_then(|result| result.ok())
+    }
+
+    fn spawn(&self, ctx: &egui::Context, variable: &CommandVariable) {
+        let outputs = self.outputs.clone();
+        let ctx = ctx.clone();
+        let name = variable.name.clone();
+        let command = variable.command.clone();
+        thread::spawn(move || {
+            let result = run_command(&command);
+            if let Some(output) = outputs.lock().unwrap().get_mut(&name) {
+                if output.command == command {
+                    output.result = Some(result);
+                }
+            }
+            ctx.request_repaint();
+        });
+    }
+}
+
+// Whether the command has to run again. Variables are resolved on every frame,
+// so a TTL of 0 means the output is kept until it's refreshed.
+fn is_stale(output: Option<&CommandOutput>, variable: &CommandVariable) -> bool {
+    let ttl = Duration::from_secs(variable.ttl_seconds);
+    match output {
+        Some(output) if output.result.is_none() => false,
+        Some(output) if output.command != variable.command => true,
+        Some(output) => variable.ttl_seconds > 0 && output.started.elapsed() > ttl,
+        None => true,
+    }
+}
+
+fn run_command(command: &str) -> Result<String, String> {
+    let output = if cfg!(target_os = "windows") {
+        Command::new("cmd").args(["/C", command]).output()
+    } else {
+        Command::new("sh").args(["-c", command]).output()
+    }
+    .map_err(|err| err.to_string())?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{}: {}", output.status, stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_owned())
+}
+
+// Every `{name}` used by the request of the tab, including its auth settings
+// and the project base url and headers it's sent with.
+pub fn request_references(state: &TabState) -> Vec<String> {
+    let mut texts = vec![&state.url, &state.request_body, &state.project.base_url];
+    texts.extend(&state.request_header_keys);
+    texts.extend(&state.request_header_values);
+    texts.extend(&state.query_param_keys);
+    texts.extend(&state.query_param_values);
+    for header in &state.project.headers {
+        texts.extend([&header.name, &header.value]);
+    }
+    let mut references: Vec<String> = texts
+        .into_iter()
+        .flat_map(|text| referenced_variables(text))
+        .collect();
+    // Only the settings of the selected mode are used
+    let active = state.auth.mode.settings_key().unwrap_or_default();
+    if let Ok(Value::Object(auth)) = serde_json::to_value(&state.auth) {
+        if let Some(settings) = auth.get(active) {
+            value_references(settings, &mut references);
+        }
+    }
+    references
+}
+
+fn value_references(value: &Value, references: &mut Vec<String>) {
+    match value {
+        Value::String(text) => references.extend(referenced_variables(text)),
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| value_references(item, references)),
+        Value::Object(map) => map
+            .values()
+            .for_each(|item| value_references(item, references)),
+        _ => (),
+    }
+}
+
+// `{$env.NAME}` references are read from the environment of the process. Only
+// the referenced ones are looked up so the rest never shows up in the app.
+pub fn os_environment_variables(references: &[String]) -> Map<String, Value> {
+    references
+        .iter()
+        .filter_map(|reference| {
+            let name = reference.strip_prefix(ENV_PREFIX)?;
+            let value = std::env::var(name).ok()?;
+            Some((reference.clone(), Value::String(value)))
+        })
+        .collect()
+}
+
+// `{$cmd.name}` references, commands only run when the user allowed it.
+pub fn command_variables(
+    ctx: &egui::Context,
+    references: &[String],
+    commands: &[CommandVariable],
+    runner: &CommandRunner,
+    enabled: bool,
+) -> Map<String, Value> {
+    if !enabled {
+        return Map::new();
+    }
+    references
+        .iter()
+        .filter_map(|reference| {
+            let name = reference.strip_prefix(CMD_PREFIX)?;
+            let variable = commands
+                .iter()
+                .find(|c| c.name == name && !c.command.is_empty())?;
+            let value = runner.value(ctx, variable)?;
+            Some((reference.clone(), Value::String(value)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::auth::{apply_auth, AuthMode};
+    use crate::app::project::Pair;
+
+    fn command(ttl_seconds: u64) -> CommandVariable {
+        CommandVariable {
+            name: "token".to_owned(),
+            command: "echo abc".to_owned(),
+            ttl_seconds,
+        }
+    }
+
+    fn output(command: &str, age: u64, result: Option<Result<String, String>>) -> CommandOutput {
+        CommandOutput {
+            command: command.to_owned(),
+            started: Instant::now() - Duration::from_secs(age),
+            result,
+        }
+    }
+
+    #[test]
+    fn caches_command_output() {
+        let done = Some(Ok("abc".to_owned()));
+        assert!(is_stale(None, &command(60)));
+        // A running command isn't started again
+        assert!(!is_stale(
+            Some(&output("echo abc", 120, None)),
+            &command(60)
+        ));
+        assert!(!is_stale(
+            Some(&output("echo abc", 30, done.clone())),
+            &command(60)
+        ));
+        assert!(is_stale(
+            Some(&output("echo abc", 120, done.clone())),
+            &command(60)
+        ));
+        // A TTL of 0 keeps the output until it's refreshed
+        assert!(!is_stale(
+            Some(&output("echo abc", 120, done.clone())),
+            &command(0)
+        ));
+        assert!(is_stale(Some(&output("echo other", 0, done)), &command(0)));
+    }
+
+    #[test]
+    fn finds_references_of_the_auth_and_project() {
+        let mut state = TabState {
+            url: "/users".to_owned(),
+            ..Default::default()
+        };
+        state.project.base_url = "{$env.API_URL}".to_owned();
+        state.project.headers = vec![Pair {
+            name: "X-Team".to_owned(),
+            value: "{$cmd.team}".to_owned(),
+        }];
+        state.auth.mode = AuthMode::Basic;
+        state.auth.basic.username = "{user}".to_owned();
+        state.auth.basic.password = "{$cmd.password}".to_owned();
+        // Settings of the other modes aren't used
+        state.auth.bearer.token = "{$cmd.token}".to_owned();
+        assert_eq!(
+            request_references(&state),
+            vec!["$env.API_URL", "$cmd.team", "user", "$cmd.password"]
+        );
+    }
+
+    #[test]
+    fn resolves_commands_in_the_bearer_token() {
+        let mut state = TabState::default();
+        state.auth.mode = AuthMode::Bearer;
+        state.auth.bearer.token = "{$cmd.token}".to_owned();
+        let references = request_references(&state);
+        assert_eq!(references, vec!["$cmd.token"]);
+
+        let ctx = egui::Context::default();
+        let runner = CommandRunner::default();
+        let commands = [command(0)];
+        // The first lookup starts the command in the background
+        let variables = loop {
+            let variables = command_variables(&ctx, &references, &commands, &runner, true);
+            if !variables.is_empty() {
+                break variables;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        let mut request = ehttp::Request::get("https://example.com");
+        apply_auth(&mut request, &state.auth, &variables, None, None).unwrap();
+        assert_eq!(request.headers.get("Authorization"), Some("Bearer abc"));
+        assert!(command_variables(&ctx, &references, &commands, &runner, false).is_empty());
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use super::dotenv::{dotenv_duplicate_keys, parse_dotenv};
+use super::environment_file::{parse_environment, EnvironmentFormat};
+use super::variable_scopes::ResolvedVariable;
+
+// Key toml uses for date and time values when they're read as json.
+const TOML_DATETIME: &str = "$__toml_private_datetime";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "Error"),
+            Severity::Warning => write!(f, "Warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Line and column, both starting at 1.
+    pub position: Option<(usize, usize)>,
+}
+
+impl Diagnostic {
+    fn error(message: String, position: Option<(usize, usize)>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message,
+            position,
+        }
+    }
+
+    fn warning(message: String, position: Option<(usize, usize)>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message,
+            position,
+        }
+    }
+}
+
+// Checks an environment file without loading it, so a broken file can be fixed
+// while the previous values stay in use.
+pub fn check_environment_file(path: &Path, format: Option<EnvironmentFormat>) -> Vec<Diagnostic> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) => return vec![Diagnostic::error(error.to_string(), None)],
+    };
+    let format = EnvironmentFormat::resolve(path, format);
+
+    if let Some(error) = syntax_error(&contents, format) {
+        return vec![error];
+    }
+
+    let mut diagnostics = match format {
+        EnvironmentFormat::Json => json_duplicate_keys(&contents),
+        EnvironmentFormat::Dotenv => dotenv_duplicate_keys(&contents)
+            .into_iter()
+            .map(|(line, key)| duplicate_key(&key, (line, 1)))
+            .collect(),
+        // The yaml and toml parsers already reject duplicate keys
+        EnvironmentFormat::Yaml | EnvironmentFormat::Toml => vec![],
+    };
+
+    match parse_environment(&contents, format) {
+        Ok(environment) => diagnostics.extend(unsupported_values(&environment, "")),
+        Err(error) => diagnostics.push(Diagnostic::error(error, None)),
+    }
+    diagnostics
+}
+
+// Variables the requests use that no layer defines, `references` holds the
+// names used by each tab.
+pub fn missing_variables(
+    references: &[(String, Vec<String>)],
+    resolved: &BTreeMap<String, ResolvedVariable>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for (tab, names) in references {
+        let mut reported = HashSet::new();
+        for name in names {
+            if !resolved.contains_key(name) && reported.insert(name) {
+                let message = format!("{{{}}} is used by {} but isn't defined", name, tab);
+                diagnostics.push(Diagnostic::warning(message, None));
+            }
+        }
+    }
+    diagnostics
+}
+
+fn syntax_error(contents: &str, format: EnvironmentFormat) -> Option<Diagnostic> {
+    match format {
+        EnvironmentFormat::Json => {
+            let error = serde_json::from_str::<Value>(contents).err()?;
+            let message = strip_position(&error.to_string());
+            Some(Diagnostic::error(
+                message,
+                Some((error.line(), error.column())),
+            ))
+        }
+        EnvironmentFormat::Yaml => {
+            let error = serde_yaml::from_str::<serde_yaml::Value>(contents).err()?;
+            let position = error.location().map(|l| (l.line(), l.column()));
+            Some(Diagnostic::error(
+                strip_position(&error.to_string()),
+                position,
+            ))
+        }
+        EnvironmentFormat::Toml => {
+            let error = toml::from_str::<toml::Table>(contents).err()?;
+            let position = error.span().map(|span| line_column(contents, span.start));
+            Some(Diagnostic::error(error.message().to_owned(), position))
+        }
+        EnvironmentFormat::Dotenv => {
+            let error = parse_dotenv(contents).err()?;
+            // Errors start with the line number, `Line 3: ...`
+            let position = error
+                .strip_prefix("Line ")
+                .and_then(|rest| rest.split_once(':'))
+                .and_then(|(line, _)| line.parse().ok())
+                .map(|line| (line, 1));
+            Some(Diagnostic::error(error, position))
+        }
+    }
+}
+
+// The serde_json and serde_yaml messages end with the position, which is shown
+// in its own column.
+fn strip_position(message: &str) -> String {
+    match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_owned(),
+        None => message.to_owned(),
+    }
+}
+
+fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+fn duplicate_key(key: &str, position: (usize, usize)) -> Diagnostic {
+    let message = format!("Duplicate key {}, only the last value is used", key);
+    Diagnostic::warning(message, Some(position))
+}
+
+// Values that can't be injected into a request, the editor only creates the
+// supported ones but files written by hand can contain anything.
+fn unsupported_values(environment: &Map<String, Value>, prefix: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for (k, v) in environment {
+        let name = format!("{}{}", prefix, k);
+        let problem = match v {
+            Value::Null => Some("is null"),
+            Value::Object(map) if map.contains_key(TOML_DATETIME) => Some("is a toml date"),
+            Value::Object(map) if map.is_empty() => Some("is an empty object"),
+            Value::Array(items) if items.is_empty() => Some("is an empty array"),
+            Value::Object(map) => {
+                diagnostics.extend(unsupported_values(map, &format!("{}.", name)));
+                None
+            }
+            Value::Array(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    let mut single = Map::new();
+                    single.insert(idx.to_string(), item.clone());
+                    diagnostics.extend(unsupported_values(&single, &format!("{}.", name)));
+                }
+                None
+            }
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            let message = format!("{} {} and can't be used in a request", name, problem);
+            diagnostics.push(Diagnostic::warning(message, None));
+        }
+    }
+    diagnostics
+}
+
+// serde_json keeps the last value of a duplicated key without complaining, so
+// the keys are collected while walking the text of the file.
+fn json_duplicate_keys(contents: &str) -> Vec<Diagnostic> {
+    struct Scope {
+        object: bool,
+        expecting_key: bool,
+        keys: HashSet<String>,
+    }
+
+    let mut diagnostics = vec![];
+    let mut scopes: Vec<Scope> = vec![];
+    let mut chars = contents.chars();
+    let (mut line, mut column) = (1, 0);
+
+    while let Some(c) = chars.next() {
+        column += 1;
+        match c {
+            '\n' => {
+                line += 1;
+                column = 0;
+            }
+            '{' | '[' => scopes.push(Scope {
+                object: c == '{',
+                expecting_key: c == '{',
+                keys: HashSet::new(),
+            }),
+            '}' | ']' => {
+                scopes.pop();
+            }
+            ',' => {
+                if let Some(scope) = scopes.last_mut() {
+                    scope.expecting_key = scope.object;
+                }
+            }
+            '"' => {
+                let position = (line, column);
+                let mut text = String::new();
+                while let Some(c) = chars.next() {
+                    column += 1;
+                    match c {
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                column += 1;
+                                text.push(escaped);
+                            }
+                        }
+                        '"' => break,
+                        _ => text.push(c),
+                    }
+                }
+                if let Some(scope) = scopes.last_mut() {
+                    if scope.expecting_key {
+                        scope.expecting_key = false;
+                        if !scope.keys.insert(text.clone()) {
+                            diagnostics.push(duplicate_key(&text, position));
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::variable_scopes::VariableScope;
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<(&str, Option<(usize, usize)>)> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.message.as_str(), diagnostic.position))
+            .collect()
+    }
+
+    #[test]
+    fn finds_duplicate_json_keys() {
+        let contents = r#"{
+  "a": 1,
+  "nested": {"b": 1, "b": 2, "é": {"c": 1}, "é": 3},
+  "list": [{"a": 1}, {"a": 2}],
+  "a": "x,\"a\""
+}"#;
+        assert_eq!(
+            messages(&json_duplicate_keys(contents)),
+            vec![
+                (
+                    "Duplicate key b, only the last value is used",
+                    Some((3, 22))
+                ),
+                (
+                    "Duplicate key é, only the last value is used",
+                    Some((3, 45))
+                ),
+                ("Duplicate key a, only the last value is used", Some((5, 3))),
+            ]
+        );
+        assert!(json_duplicate_keys(r#"{"a": {"a": {"a": 1}}}"#).is_empty());
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        let contents = "name = \"é\"\nkey = €€x";
+        assert_eq!(line_column(contents, 0), (1, 1));
+        assert_eq!(line_column(contents, contents.find('x').unwrap()), (2, 9));
+        // Offsets past the end stay on the last line
+        assert_eq!(line_column(contents, 1000), (2, 10));
+    }
+
+    #[test]
+    fn reports_missing_variables_once_per_tab() {
+        let resolved = BTreeMap::from([(
+            "host".to_owned(),
+            ResolvedVariable {
+                value: Value::from("localhost"),
+                scope: VariableScope::Environment,
+                shadowed: vec![],
+            },
+        )]);
+        let references = vec![
+            (
+                "Login".to_owned(),
+                vec!["host".to_owned(), "token".to_owned(), "token".to_owned()],
+            ),
+            ("Users".to_owned(), vec!["token".to_owned()]),
+        ];
+        let diagnostics = missing_variables(&references, &resolved);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                ("{token} is used by Login but isn't defined", None),
+                ("{token} is used by Users but isn't defined", None),
+            ]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity == Severity::Warning));
+    }
+
+    #[test]
+    fn checks_environment_files() {
+        let path = std::env::temp_dir().join("requestor_diagnostics.json");
+        fs::write(&path, "{\n  \"a\": 1,\n  \"a\": null\n}").unwrap();
+        assert_eq!(
+            messages(&check_environment_file(&path, None)),
+            vec![
+                ("Duplicate key a, only the last value is used", Some((3, 3))),
+                ("a is null and can't be used in a request", None),
+            ]
+        );
+        fs::write(&path, "{\n  \"a\": 1,\n}").unwrap();
+        let diagnostics = check_environment_file(&path, None);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].position, Some((3, 1)));
+        fs::remove_file(path).unwrap();
+    }
+}
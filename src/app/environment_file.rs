@@ -0,0 +1,333 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::dotenv::{parse_dotenv, serialize_dotenv};
+use super::environment_injector::flatten_environment;
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum EnvironmentFormat {
+    Json,
+    Dotenv,
+    Yaml,
+    Toml,
+}
+
+impl EnvironmentFormat {
+    pub const ALL: [EnvironmentFormat; 4] = [
+        EnvironmentFormat::Json,
+        EnvironmentFormat::Dotenv,
+        EnvironmentFormat::Yaml,
+        EnvironmentFormat::Toml,
+    ];
+
+    // Dotenv files are usually named `.env` or `.env.local`, which have no extension.
+    pub fn from_path(path: &Path) -> Self {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if file_name == ".env" || file_name.starts_with(".env.") {
+            return EnvironmentFormat::Dotenv;
+        }
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "env" => EnvironmentFormat::Dotenv,
+            "yaml" | "yml" => EnvironmentFormat::Yaml,
+            "toml" => EnvironmentFormat::Toml,
+            _ => EnvironmentFormat::Json,
+        }
+    }
+
+    // An explicitly selected format wins over the file extension.
+    pub fn resolve(path: &Path, selected: Option<EnvironmentFormat>) -> Self {
+        selected.unwrap_or_else(|| EnvironmentFormat::from_path(path))
+    }
+}
+
+impl fmt::Display for EnvironmentFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvironmentFormat::Json => write!(f, "JSON"),
+            EnvironmentFormat::Dotenv => write!(f, "dotenv"),
+            EnvironmentFormat::Yaml => write!(f, "YAML"),
+            EnvironmentFormat::Toml => write!(f, "TOML"),
+        }
+    }
+}
+
+// Adds the filters for every supported format to a file dialog.
+pub fn environment_file_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new()
+        .add_filter("json", &["json", "txt"])
+        .add_filter("dotenv", &["env"])
+        .add_filter("yaml", &["yaml", "yml"])
+        .add_filter("toml", &["toml"])
+        .add_filter("all files", &["*"])
+}
+
+pub fn load_environment(
+    file_path: &Path,
+    format: Option<EnvironmentFormat>,
+) -> Result<Map<String, Value>, String> {
+    let contents = fs::read_to_string(file_path).map_err(|err| err.to_string())?;
+    parse_environment(&contents, EnvironmentFormat::resolve(file_path, format))
+}
+
+pub fn parse_environment(
+    contents: &str,
+    format: EnvironmentFormat,
+) -> Result<Map<String, Value>, String> {
+    let parsed: Value = match format {
+        EnvironmentFormat::Json => serde_json::from_str(contents).map_err(|err| err.to_string())?,
+        EnvironmentFormat::Dotenv => Value::Object(parse_dotenv(contents)?),
+        EnvironmentFormat::Yaml if contents.trim().is_empty() => Value::Object(Map::new()),
+        EnvironmentFormat::Yaml => serde_yaml::from_str(contents).map_err(|err| err.to_string())?,
+        EnvironmentFormat::Toml => toml::from_str(contents).map_err(|err| err.to_string())?,
+    };
+    match parsed {
+        Value::Object(obj) => Ok(obj),
+        _ => Err(format!("Environment file must contain a {} object", format)),
+    }
+}
+
+// Writes the environment back to its source file. For JSON the indentation, line
+// endings and trailing newline of the existing file are kept, dotenv and TOML
+// files keep their comments and only the changed values are rewritten, so saving
+// from the app produces a minimal diff. YAML can't be edited in place, a file
+// that would lose comments or formatting is left alone and an error returned.
+pub fn save_environment(
+    file_path: &Path,
+    environment: &Map<String, Value>,
+    format: Option<EnvironmentFormat>,
+) -> Result<(), String> {
+    let original = fs::read_to_string(file_path).unwrap_or_default();
+    let contents = match EnvironmentFormat::resolve(file_path, format) {
+        EnvironmentFormat::Json => serialize_json(environment, &original)?,
+        EnvironmentFormat::Dotenv => {
+            let flattened: Map<String, Value> =
+                flatten_environment(environment).into_iter().collect();
+            serialize_dotenv(&flattened, &original)
+        }
+        EnvironmentFormat::Yaml => serialize_yaml(environment, &original)?,
+        EnvironmentFormat::Toml => serialize_toml(environment, &original)?,
+    };
+    fs::write(file_path, contents).map_err(|err| err.to_string())
+}
+
+// Creates a new, empty environment file.
+pub fn create_environment(file_path: &Path) -> Result<(), String> {
+    let contents = match EnvironmentFormat::from_path(file_path) {
+        EnvironmentFormat::Json => "{}\n",
+        _ => "",
+    };
+    fs::write(file_path, contents).map_err(|err| err.to_string())
+}
+
+fn serialize_json(environment: &Map<String, Value>, original: &str) -> Result<String, String> {
+    let mut serialized = match detect_indent(original) {
+        Some(indent) => {
+            let mut buf = Vec::new();
+            let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+            let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+            environment
+                .serialize(&mut serializer)
+                .map_err(|err| err.to_string())?;
+            String::from_utf8(buf).map_err(|err| err.to_string())?
+        }
+        None => serde_json::to_string(environment).map_err(|err| err.to_string())?,
+    };
+
+    if original.contains("\r\n") {
+        serialized = serialized.replace('\n', "\r\n");
+    }
+    if original.is_empty() || original.ends_with('\n') {
+        serialized.push_str(if original.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        });
+    }
+    Ok(serialized)
+}
+
+// Only files that serde_yaml writes back identically, such as those saved by the
+// app before, are rewritten.
+fn serialize_yaml(environment: &Map<String, Value>, original: &str) -> Result<String, String> {
+    if !original.trim().is_empty() {
+        let current = parse_environment(original, EnvironmentFormat::Yaml)?;
+        let rewritten = serde_yaml::to_string(&current).map_err(|err| err.to_string())?;
+        if rewritten != original {
+            return Err(
+                "saving would drop the comments and formatting of the YAML file, \
+                edit it in a text editor instead"
+                    .to_owned(),
+            );
+        }
+    }
+    serde_yaml::to_string(environment).map_err(|err| err.to_string())
+}
+
+fn serialize_toml(environment: &Map<String, Value>, original: &str) -> Result<String, String> {
+    let mut document: toml_edit::DocumentMut =
+        original.parse().map_err(|err| format!("{}", err))?;
+    let current = parse_environment(original, EnvironmentFormat::Toml)?;
+    update_toml_table(document.as_table_mut(), environment, &current)?;
+    Ok(document.to_string())
+}
+
+// Values equal to the ones read from the file are left untouched, changed values
+// keep the comments and whitespace around them.
+fn update_toml_table(
+    table: &mut dyn toml_edit::TableLike,
+    environment: &Map<String, Value>,
+    current: &Map<String, Value>,
+) -> Result<(), String> {
+    let removed: Vec<String> = table
+        .iter()
+        .map(|(key, _)| key.to_owned())
+        .filter(|key| !environment.contains_key(key))
+        .collect();
+    for key in removed {
+        table.remove(&key);
+    }
+    for (key, value) in environment {
+        let previous = current.get(key);
+        if previous == Some(value) {
+            continue;
+        }
+        match (table.get_mut(key), value) {
+            (Some(item), Value::Object(object)) if item.is_table_like() => {
+                let empty = Map::new();
+                let previous = match previous {
+                    Some(Value::Object(previous)) => previous,
+                    _ => &empty,
+                };
+                if let Some(nested) = item.as_table_like_mut() {
+                    update_toml_table(nested, object, previous)?;
+                }
+            }
+            (Some(toml_edit::Item::Value(existing)), value) => {
+                let decor = existing.decor().clone();
+                *existing = toml_value(value)?;
+                *existing.decor_mut() = decor;
+            }
+            (_, Value::Object(object)) => {
+                let mut nested = toml_edit::Table::new();
+                update_toml_table(&mut nested, object, &Map::new())?;
+                table.insert(key, toml_edit::Item::Table(nested));
+            }
+            (_, value) => {
+                table.insert(key, toml_edit::Item::Value(toml_value(value)?));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn toml_value(value: &Value) -> Result<toml_edit::Value, String> {
+    Ok(match value {
+        Value::Null => return Err("TOML has no null values, remove the variable".to_owned()),
+        Value::Bool(value) => (*value).into(),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => integer.into(),
+            None => number.as_f64().unwrap_or_default().into(),
+        },
+        Value::String(text) => text.as_str().into(),
+        Value::Array(items) => {
+            let mut array = toml_edit::Array::new();
+            for item in items {
+                array.push(toml_value(item)?);
+            }
+            array.into()
+        }
+        Value::Object(object) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (key, item) in object {
+                table.insert(key, toml_value(item)?);
+            }
+            table.into()
+        }
+    })
+}
+
+// Returns the indentation unit used by the file, or None if the object was written
+// on a single line. New files default to two spaces.
+fn detect_indent(contents: &str) -> Option<String> {
+    if contents.trim().is_empty() || contents.trim() == "{}" {
+        return Some("  ".to_owned());
+    }
+    for line in contents.trim().lines().skip(1) {
+        let indent: String = line
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        if !indent.is_empty() {
+            return Some(indent);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn edit_toml(original: &str, edit: impl FnOnce(&mut Map<String, Value>)) -> String {
+        let mut environment = parse_environment(original, EnvironmentFormat::Toml).unwrap();
+        edit(&mut environment);
+        serialize_toml(&environment, original).unwrap()
+    }
+
+    #[test]
+    fn toml_keeps_comments_and_quoting() {
+        let original = "# api settings\nurl = 'https://example.com' # literal\n\n[db]\nhost = \"localhost\"\nport = 5432\n";
+        let saved = edit_toml(original, |environment| {
+            environment["db"]["port"] = json!(6543);
+        });
+        assert_eq!(saved, original.replace("5432", "6543"));
+    }
+
+    #[test]
+    fn toml_adds_and_removes_keys() {
+        let original = "a = 1 # one\nb = 2\n";
+        let saved = edit_toml(original, |environment| {
+            environment.remove("b");
+            environment.insert("c".to_owned(), json!("three"));
+        });
+        assert_eq!(saved, "a = 1 # one\nc = \"three\"\n");
+    }
+
+    #[test]
+    fn toml_unchanged_file_is_identical() {
+        let original = "title  =  \"x\"   # spaced\n[nested]\nlist = [1, 2]\n";
+        assert_eq!(edit_toml(original, |_| ()), original);
+    }
+
+    #[test]
+    fn yaml_with_comments_is_not_rewritten() {
+        let original = "# comment\nurl: https://example.com\n";
+        let environment = parse_environment(original, EnvironmentFormat::Yaml).unwrap();
+        assert!(serialize_yaml(&environment, original).is_err());
+
+        let plain = serde_yaml::to_string(&environment).unwrap();
+        let mut changed = environment.clone();
+        changed.insert("url".to_owned(), json!("https://other.com"));
+        let saved = serialize_yaml(&changed, &plain).unwrap();
+        assert_eq!(saved, "url: https://other.com\n");
+    }
+
+    #[test]
+    fn json_keeps_indentation() {
+        let original = "{\n    \"a\": 1\n}\n";
+        let environment = parse_environment(original, EnvironmentFormat::Json).unwrap();
+        assert_eq!(serialize_json(&environment, original).unwrap(), original);
+    }
+}
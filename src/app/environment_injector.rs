@@ -6,11 +6,15 @@ pub fn inject_environment(
 ) -> (String, Option<String>) {
     let mut new_str = str.clone();
     let mut err: Option<String> = None;
-    for (k, v) in environment {
-        let val = v.as_str();
+    for (k, v) in flatten_environment(environment) {
+        let val = match &v {
+            Value::String(value) => Some(value.clone()),
+            Value::Number(_) | Value::Bool(_) => Some(v.to_string()),
+            _ => None,
+        };
         match val {
             Some(value) => {
-                new_str = new_str.replace(&format!("{{{}}}", k), value);
+                new_str = new_str.replace(&format!("{{{}}}", k), &value);
             }
             None => {
                 new_str = str.clone();
@@ -20,3 +24,58 @@ pub fn inject_environment(
     }
     (new_str, err)
 }
+
+// Names of the `{name}` references in a text.
+pub fn referenced_variables(text: &str) -> Vec<String> {
+    let mut names = vec![];
+    let mut start = None;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '{' => start = Some(idx + 1),
+            '}' => {
+                if let Some(start) = start.take() {
+                    if idx > start {
+                        names.push(text[start..idx].to_owned());
+                    }
+                }
+            }
+            c if c.is_whitespace() => start = None,
+            _ => (),
+        }
+    }
+    names
+}
+
+// True when the text is empty or only made of `{name}` references.
+pub fn only_references(text: &str) -> bool {
+    let mut rest = text.to_owned();
+    for name in referenced_variables(text) {
+        rest = rest.replace(&format!("{{{}}}", name), "");
+    }
+    rest.trim().is_empty()
+}
+
+// Nested values are addressed with dots, `{database.host}` or `{hosts.0}`.
+pub fn flatten_environment(environment: &Map<String, Value>) -> Vec<(String, Value)> {
+    let mut flattened = vec![];
+    for (k, v) in environment {
+        flatten_value(k.clone(), v, &mut flattened);
+    }
+    flattened
+}
+
+fn flatten_value(key: String, value: &Value, flattened: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                flatten_value(format!("{}.{}", key, k), v, flattened);
+            }
+        }
+        Value::Array(items) => {
+            for (idx, v) in items.iter().enumerate() {
+                flatten_value(format!("{}.{}", key, idx), v, flattened);
+            }
+        }
+        _ => flattened.push((key, value.clone())),
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde_json::{Map, Value};
+
+use super::environment_injector::flatten_environment;
+
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Polls the modification time of the loaded environment files. Polling once a
+// second is cheap enough for the handful of files we care about and avoids
+// running a separate watcher thread.
+#[derive(Default)]
+pub struct EnvironmentWatcher {
+    modified: HashMap<PathBuf, SystemTime>,
+    last_check: Option<Instant>,
+}
+
+impl EnvironmentWatcher {
+    // Returns the files that changed since the previous check. Files seen for the
+    // first time are only remembered, they were just loaded by the user.
+    pub fn changed_files(&mut self, paths: &BTreeSet<PathBuf>) -> Vec<PathBuf> {
+        if let Some(last_check) = self.last_check {
+            if last_check.elapsed() < CHECK_INTERVAL {
+                return vec![];
+            }
+        }
+        self.last_check = Some(Instant::now());
+
+        self.modified.retain(|path, _| paths.contains(path));
+
+        let mut changed = vec![];
+        for path in paths {
+            let modified = match modified_time(path) {
+                Some(modified) => modified,
+                None => continue,
+            };
+            match self.modified.insert(path.clone(), modified) {
+                Some(previous) if previous != modified => changed.push(path.clone()),
+                _ => (),
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[derive(Default, Debug)]
+pub struct EnvironmentChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl EnvironmentChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = vec![];
+        if !self.added.is_empty() {
+            parts.push(format!("added {}", self.added.join(", ")));
+        }
+        if !self.removed.is_empty() {
+            parts.push(format!("removed {}", self.removed.join(", ")));
+        }
+        if !self.changed.is_empty() {
+            parts.push(format!("changed {}", self.changed.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+// Compares the flattened keys so a change deep inside an object is reported by
+// the name used to reference it.
+pub fn diff_environments(old: &Map<String, Value>, new: &Map<String, Value>) -> EnvironmentChanges {
+    let old: HashMap<String, Value> = flatten_environment(old).into_iter().collect();
+    let new_flat = flatten_environment(new);
+    let mut changes = EnvironmentChanges::default();
+
+    for (k, v) in &new_flat {
+        match old.get(k) {
+            Some(previous) if previous != v => changes.changed.push(k.clone()),
+            Some(_) => (),
+            None => changes.added.push(k.clone()),
+        }
+    }
+    let new: HashMap<String, Value> = new_flat.into_iter().collect();
+    let mut removed: Vec<String> = old
+        .keys()
+        .filter(|k| !new.contains_key(*k))
+        .cloned()
+        .collect();
+    removed.sort();
+    changes.removed = removed;
+
+    changes
+}
@@ -0,0 +1,348 @@
+use std::fmt;
+use std::fs;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{SecondsFormat, Utc};
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use url::Url;
+
+use super::auth::set_header;
+use super::environment_injector::{flatten_environment, inject_environment};
+
+// Parts of the request that can be used in the template, `<header:Name>` is
+// the value of a request header.
+pub const PLACEHOLDERS: [&str; 12] = [
+    "<method>",
+    "<url>",
+    "<host>",
+    "<path>",
+    "<query>",
+    "<body>",
+    "<body_sha256>",
+    "<body_md5>",
+    "<timestamp>",
+    "<timestamp_ms>",
+    "<date>",
+    "<nonce>",
+];
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum HmacAlgorithm {
+    Sha1,
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+impl HmacAlgorithm {
+    pub const ALL: [HmacAlgorithm; 3] = [
+        HmacAlgorithm::Sha1,
+        HmacAlgorithm::Sha256,
+        HmacAlgorithm::Sha512,
+    ];
+
+    fn sign(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            HmacAlgorithm::Sha1 => mac::<Hmac<Sha1>>(key, data),
+            HmacAlgorithm::Sha256 => mac::<Hmac<Sha256>>(key, data),
+            HmacAlgorithm::Sha512 => mac::<Hmac<Sha512>>(key, data),
+        }
+    }
+}
+
+pub fn mac<M: Mac + KeyInit>(key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut mac = <M as KeyInit>::new_from_slice(key)
+        .map_err(|err| format!("The HMAC key can't be used: {}", err))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+impl fmt::Display for HmacAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HmacAlgorithm::Sha1 => write!(f, "HMAC-SHA1"),
+            HmacAlgorithm::Sha256 => write!(f, "HMAC-SHA256"),
+            HmacAlgorithm::Sha512 => write!(f, "HMAC-SHA512"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum KeySource {
+    #[default]
+    Text,
+    Base64,
+    Hex,
+    File,
+}
+
+impl KeySource {
+    pub const ALL: [KeySource; 4] = [
+        KeySource::Text,
+        KeySource::Base64,
+        KeySource::Hex,
+        KeySource::File,
+    ];
+}
+
+impl fmt::Display for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeySource::Text => write!(f, "Text"),
+            KeySource::Base64 => write!(f, "Base64 text"),
+            KeySource::Hex => write!(f, "Hex text"),
+            KeySource::File => write!(f, "File path"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+impl fmt::Display for SignatureEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureEncoding::Hex => write!(f, "Hex"),
+            SignatureEncoding::Base64 => write!(f, "Base64"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HmacSigning {
+    pub algorithm: HmacAlgorithm,
+    pub key: String,
+    pub key_source: KeySource,
+    /// The text that is signed, with `<part>` placeholders.
+    pub template: String,
+    pub header: String,
+    /// Value of the header, `<signature>` is replaced by the signature.
+    pub header_value: String,
+    pub encoding: SignatureEncoding,
+    /// Headers that carry the generated `<timestamp>` and `<nonce>`, if any.
+    pub timestamp_header: String,
+    pub nonce_header: String,
+}
+
+impl Default for HmacSigning {
+    fn default() -> Self {
+        Self {
+            algorithm: HmacAlgorithm::Sha256,
+            key: "".to_owned(),
+            key_source: KeySource::Text,
+            template: "<method>\n<path>\n<timestamp>\n<body_sha256>".to_owned(),
+            header: "X-Signature".to_owned(),
+            header_value: "<signature>".to_owned(),
+            encoding: SignatureEncoding::Hex,
+            timestamp_header: "X-Timestamp".to_owned(),
+            nonce_header: "".to_owned(),
+        }
+    }
+}
+
+impl HmacSigning {
+    fn key(&self, variables: &Map<String, Value>) -> Result<Vec<u8>, String> {
+        let text = inject_environment(&self.key, variables).0;
+        let key = match self.key_source {
+            KeySource::Text => text.into_bytes(),
+            KeySource::Base64 => STANDARD
+                .decode(text.trim())
+                .map_err(|err| format!("The HMAC key isn't valid base64: {}", err))?,
+            KeySource::Hex => {
+                decode_hex(text.trim()).ok_or("The HMAC key isn't valid hex".to_owned())?
+            }
+            KeySource::File => fs::read(text.trim())
+                .map_err(|err| format!("Unable to read the HMAC key file: {}", err))?,
+        };
+        if key.is_empty() {
+            return Err("The HMAC key is empty".to_owned());
+        }
+        Ok(key)
+    }
+}
+
+// Signs the request after the variables were injected, so the signed text sees
+// the values that are sent. Returns the text that was signed.
+pub fn sign_hmac(
+    request: &mut ehttp::Request,
+    signing: &HmacSigning,
+    variables: &Map<String, Value>,
+) -> Result<String, String> {
+    let key = signing.key(variables)?;
+    let now = Utc::now();
+    let timestamp = now.timestamp().to_string();
+    let nonce = Alphanumeric.sample_string(&mut rand::thread_rng(), 32);
+
+    // Added first so `<header:...>` can refer to them
+    let timestamp_header = inject_environment(&signing.timestamp_header, variables).0;
+    if !timestamp_header.is_empty() {
+        set_header(&mut request.headers, timestamp_header, timestamp.clone());
+    }
+    let nonce_header = inject_environment(&signing.nonce_header, variables).0;
+    if !nonce_header.is_empty() {
+        set_header(&mut request.headers, nonce_header, nonce.clone());
+    }
+
+    let url = Url::parse(&request.url).map_err(|err| err.to_string())?;
+    let parts = [
+        ("<method>", request.method.clone()),
+        ("<url>", request.url.clone()),
+        ("<host>", url.host_str().unwrap_or_default().to_owned()),
+        ("<path>", url.path().to_owned()),
+        ("<query>", url.query().unwrap_or_default().to_owned()),
+        ("<body>", String::from_utf8_lossy(&request.body).to_string()),
+        ("<body_sha256>", hex(&Sha256::digest(&request.body))),
+        ("<body_md5>", hex(&Md5::digest(&request.body))),
+        ("<timestamp>", timestamp),
+        ("<timestamp_ms>", now.timestamp_millis().to_string()),
+        ("<date>", now.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        ("<nonce>", nonce),
+    ];
+    let text = fill_template(&signing.template, &parts, &request.headers, variables);
+
+    let signature = signing.algorithm.sign(&key, text.as_bytes())?;
+    let signature = match signing.encoding {
+        SignatureEncoding::Hex => hex(&signature),
+        SignatureEncoding::Base64 => STANDARD.encode(signature),
+    };
+    let header = inject_environment(&signing.header, variables).0;
+    if header.is_empty() {
+        return Err("The HMAC signature header isn't set".to_owned());
+    }
+    let value = inject_environment(&signing.header_value, variables)
+        .0
+        .replace("<signature>", &signature);
+    set_header(&mut request.headers, header, value);
+    Ok(text)
+}
+
+// Replaces the placeholders and `{variables}` in one pass, so placeholders or
+// variables that appear in the values themselves, in the body for example, are
+// left alone.
+fn fill_template(
+    template: &str,
+    parts: &[(&str, String)],
+    headers: &ehttp::Headers,
+    variables: &Map<String, Value>,
+) -> String {
+    let variables = flatten_environment(variables);
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['<', '{']) {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let close = if rest.starts_with('<') { '>' } else { '}' };
+        let value = rest.find(close).and_then(|end| {
+            let token = &rest[..=end];
+            let value = if close == '}' {
+                variables
+                    .iter()
+                    .find(|(name, _)| *name == token[1..end])
+                    .and_then(|(_, value)| match value {
+                        Value::String(value) => Some(value.clone()),
+                        Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+                        _ => None,
+                    })
+            } else if let Some(name) = token.strip_prefix("<header:") {
+                headers.get(&name[..name.len() - 1]).map(|v| v.to_owned())
+            } else {
+                parts
+                    .iter()
+                    .find(|(part, _)| *part == token)
+                    .map(|(_, value)| value.clone())
+            };
+            value.map(|value| (value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                text.push_str(&value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                text.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [high, low] => u8::from_str_radix(std::str::from_utf8(&[*high, *low]).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn signs_the_rfc_test_vectors() {
+        let key = b"Jefe";
+        let data = b"what do ya want for nothing?";
+        let sign = |algorithm: HmacAlgorithm| hex(&algorithm.sign(key, data).unwrap());
+        assert_eq!(
+            sign(HmacAlgorithm::Sha1),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+        assert_eq!(
+            sign(HmacAlgorithm::Sha256),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            sign(HmacAlgorithm::Sha512),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+    }
+
+    #[test]
+    fn fills_placeholders_and_variables_in_one_pass() {
+        let parts = [
+            ("<method>", "POST".to_owned()),
+            ("<body>", "{\"id\": \"{client}\"} <method>".to_owned()),
+        ];
+        let headers = ehttp::Headers::new(&[("X-Id", "7")]);
+        let variables = json!({"client": "<body>", "api": {"version": 2}});
+        let text = fill_template(
+            "<method> {client} {api.version} <header:X-Id> <body> <unknown> {missing} <",
+            &parts,
+            &headers,
+            variables.as_object().unwrap(),
+        );
+        assert_eq!(
+            text,
+            "POST <body> 2 7 {\"id\": \"{client}\"} <method> <unknown> {missing} <"
+        );
+    }
+
+    #[test]
+    fn decodes_hex_keys() {
+        assert_eq!(decode_hex("00ff7a"), Some(vec![0, 255, 122]));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+}
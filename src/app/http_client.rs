@@ -0,0 +1,190 @@
+use std::io::Read;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use serde::{Deserialize, Serialize};
+
+// Connection settings applied to every request of a project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestOptions {
+    /// Timeout of the whole request, 0 waits forever.
+    pub timeout_seconds: u64,
+    pub follow_redirects: bool,
+    /// Skips certificate validation, for local servers with self-signed certificates.
+    /// Taken from the local settings of the app, never from the shared project file.
+    #[serde(skip)]
+    pub accept_invalid_certs: bool,
+}
+
+impl Default for RequestOptions {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: 0,
+            follow_redirects: true,
+            accept_invalid_certs: false,
+        }
+    }
+}
+
+impl RequestOptions {
+    fn agent(&self) -> Result<ureq::Agent, String> {
+        let mut builder = ureq::AgentBuilder::new();
+        if self.timeout_seconds > 0 {
+            builder = builder.timeout(Duration::from_secs(self.timeout_seconds));
+        }
+        if !self.follow_redirects {
+            builder = builder.redirects(0);
+        }
+        if self.accept_invalid_certs {
+            builder = builder.tls_config(Arc::new(accept_any_certificate()?));
+        }
+        Ok(builder.build())
+    }
+}
+
+// Same as `ehttp::fetch`, with the options of the project.
+pub fn fetch(
+    request: ehttp::Request,
+    options: RequestOptions,
+    on_done: impl 'static + Send + FnOnce(ehttp::Result<ehttp::Response>),
+) {
+    thread::spawn(move || on_done(fetch_blocking(&request, &options)));
+}
+
+// Follows `ehttp::fetch_blocking`, which always uses the default agent.
+pub fn fetch_blocking(
+    request: &ehttp::Request,
+    options: &RequestOptions,
+) -> ehttp::Result<ehttp::Response> {
+    let mut req = options.agent()?.request(&request.method, &request.url);
+    for (k, v) in &request.headers {
+        req = req.set(k, v);
+    }
+
+    let resp = if request.body.is_empty() {
+        req.call()
+    } else {
+        req.send_bytes(&request.body)
+    };
+
+    let (ok, resp) = match resp {
+        Ok(resp) => (true, resp),
+        Err(ureq::Error::Status(_, resp)) => (false, resp), // Still read the body on e.g. 404
+        Err(ureq::Error::Transport(err)) => return Err(err.to_string()),
+    };
+
+    let url = resp.get_url().to_owned();
+    let status = resp.status();
+    let status_text = resp.status_text().to_owned();
+    let mut headers = ehttp::Headers::default();
+    for key in &resp.headers_names() {
+        if let Some(value) = resp.header(key) {
+            headers.insert(key, value.to_owned());
+        }
+    }
+    headers.sort();
+
+    let mut bytes = vec![];
+    if let Err(err) = resp.into_reader().read_to_end(&mut bytes) {
+        // No body is expected for HEAD requests
+        if request.method != "HEAD" || err.kind() != std::io::ErrorKind::UnexpectedEof {
+            return Err(format!("Failed to read response body: {}", err));
+        }
+    }
+
+    Ok(ehttp::Response {
+        url,
+        ok,
+        status,
+        status_text,
+        headers,
+        bytes,
+    })
+}
+
+fn accept_any_certificate() -> Result<ClientConfig, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|err| err.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+        .with_no_client_auth();
+    Ok(config)
+}
+
+// Accepts any certificate but still checks the handshake signatures, so the
+// connection is encrypted, just not authenticated.
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_files_cant_disable_certificate_checks() {
+        let options: RequestOptions =
+            toml::from_str("timeout_seconds = 5\naccept_invalid_certs = true\n").unwrap();
+        assert_eq!(options.timeout_seconds, 5);
+        assert!(!options.accept_invalid_certs);
+
+        let options = RequestOptions {
+            accept_invalid_certs: true,
+            ..Default::default()
+        };
+        let written = toml::to_string(&options).unwrap();
+        assert!(!written.contains("accept_invalid_certs"), "{}", written);
+    }
+}
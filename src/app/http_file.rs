@@ -0,0 +1,558 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+
+use super::auth::{Auth, AuthMode};
+use super::collections::{Folder, SavedRequest};
+use super::digest_auth::DigestAlgorithm;
+use super::dynamic_variables::{CMD_PREFIX, ENV_PREFIX};
+use super::environment_injector::{flatten_environment, only_references};
+use super::import::ImportedRequest;
+use super::response_capture::CaptureRule;
+
+// Settings other tools don't have are kept in comments, which they ignore.
+const AUTH_TAG: &str = "@requestor-auth";
+const CAPTURE_TAG: &str = "@requestor-capture";
+
+// The requests and `@name = value` variables of a `.http` file, as used by the
+// JetBrains HTTP Client and the VS Code REST Client.
+#[derive(Debug, Default)]
+pub struct HttpFile {
+    pub variables: Map<String, Value>,
+    pub requests: Vec<SavedRequest>,
+    /// What couldn't be converted, one line each.
+    pub report: Vec<String>,
+}
+
+pub fn read_http_file(path: &Path) -> Result<HttpFile, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let file = parse_http_file(&contents);
+    if file.requests.is_empty() {
+        return Err("The file has no requests".to_owned());
+    }
+    Ok(file)
+}
+
+// Returns the auth fields that were written as a variable instead of their value.
+pub fn write_http_file(
+    path: &Path,
+    requests: &[SavedRequest],
+    variables: &Map<String, Value>,
+) -> Result<BTreeSet<&'static str>, String> {
+    fs::write(path, serialize_http_file(requests, variables)).map_err(|err| err.to_string())?;
+    Ok(requests
+        .iter()
+        .flat_map(|request| exported_auth(&request.auth).1)
+        .collect())
+}
+
+// The toast text of an export, naming the variables the secrets were written as.
+pub fn exported_message(name: &str, replaced: &BTreeSet<&str>) -> String {
+    match replaced.is_empty() {
+        true => format!("Exported {}", name),
+        false => format!(
+            "Exported {}, secrets were written as variables to define: {}",
+            name,
+            replaced
+                .iter()
+                .map(|field| format!("{{{{{}}}}}", field))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+#[derive(PartialEq)]
+enum Section {
+    RequestLine,
+    Headers,
+    Body,
+}
+
+pub fn parse_http_file(contents: &str) -> HttpFile {
+    let mut file = HttpFile::default();
+    // Requests are separated by `###`, which can be followed by the name
+    let mut blocks: Vec<(String, Vec<&str>)> = vec![("".to_owned(), vec![])];
+    for line in contents.lines() {
+        match line.strip_prefix("###") {
+            Some(title) => blocks.push((title.trim().to_owned(), vec![])),
+            None => {
+                if let Some((_, lines)) = blocks.last_mut() {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+    for (title, lines) in blocks {
+        let context = match title.as_str() {
+            "" => format!("Request {}", file.requests.len() + 1),
+            title => title.to_owned(),
+        };
+        if let Some(request) = parse_request(&mut file, title, &lines, &context) {
+            file.requests
+                .push(request.into_saved(&context, &mut file.report));
+        }
+    }
+    file
+}
+
+fn parse_request(
+    file: &mut HttpFile,
+    title: String,
+    lines: &[&str],
+    context: &str,
+) -> Option<ImportedRequest> {
+    let mut request = ImportedRequest {
+        name: title,
+        ..Default::default()
+    };
+    let mut section = Section::RequestLine;
+    let mut auth: Option<Auth> = None;
+    let mut captures = vec![];
+    let mut body = vec![];
+    for line in lines {
+        let trimmed = line.trim();
+        match section {
+            Section::RequestLine | Section::Headers if trimmed.is_empty() => {
+                if section == Section::Headers {
+                    section = Section::Body;
+                }
+            }
+            Section::RequestLine | Section::Headers if comment(trimmed).is_some() => {
+                let comment = comment(trimmed).unwrap_or_default();
+                let Some((tag, value)) = metadata(comment) else {
+                    continue;
+                };
+                match tag {
+                    "@name" if request.name.is_empty() => request.name = value.to_owned(),
+                    "@name" => (),
+                    AUTH_TAG => match serde_json::from_str(value) {
+                        Ok(parsed) => auth = Some(parsed),
+                        Err(err) => file
+                            .report
+                            .push(format!("{}: invalid auth settings: {}", context, err)),
+                    },
+                    CAPTURE_TAG => match serde_json::from_str::<CaptureRule>(value) {
+                        Ok(rule) => captures.push(rule),
+                        Err(err) => file
+                            .report
+                            .push(format!("{}: invalid capture rule: {}", context, err)),
+                    },
+                    tag => file
+                        .report
+                        .push(format!("{}: request setting {} was ignored", context, tag)),
+                }
+            }
+            Section::RequestLine => {
+                if let Some(definition) = trimmed.strip_prefix('@') {
+                    if let Some((name, value)) = definition.split_once('=') {
+                        let value = convert_references(value.trim(), &mut file.report, context);
+                        file.variables
+                            .insert(name.trim().to_owned(), Value::String(value));
+                    }
+                    continue;
+                }
+                // `GET https://example.com HTTP/1.1`, or just the url for a GET
+                let mut parts: Vec<&str> = trimmed.split_whitespace().collect();
+                if parts.last().is_some_and(|part| part.starts_with("HTTP/")) {
+                    parts.pop();
+                }
+                let is_method = parts.len() > 1 && parts[0].chars().all(|c| c.is_ascii_uppercase());
+                if is_method {
+                    request.method = parts.remove(0).to_owned();
+                }
+                request.url = parts.join(" ");
+                section = Section::Headers;
+            }
+            Section::Headers => {
+                // Long query strings can continue on indented lines
+                if line.starts_with(char::is_whitespace)
+                    && (trimmed.starts_with('?') || trimmed.starts_with('&'))
+                {
+                    request.url.push_str(trimmed);
+                    continue;
+                }
+                match trimmed.split_once(':') {
+                    Some((name, value)) => request
+                        .headers
+                        .push((name.trim().to_owned(), value.trim().to_owned())),
+                    None => file.report.push(format!(
+                        "{}: invalid header {} was skipped",
+                        context, trimmed
+                    )),
+                }
+            }
+            Section::Body => body.push(*line),
+        }
+    }
+    if request.url.is_empty() {
+        return None;
+    }
+
+    request.url = convert_references(&request.url, &mut file.report, context);
+    request.headers = request
+        .headers
+        .into_iter()
+        .map(|(name, value)| {
+            (
+                convert_references(&name, &mut file.report, context),
+                convert_references(&value, &mut file.report, context),
+            )
+        })
+        .collect();
+    let body = request_body(&body, &mut file.report, context);
+    request.body = convert_references(&body, &mut file.report, context);
+    request.auth = match auth {
+        Some(auth) => auth,
+        None => authorization_header(&mut request.headers),
+    };
+    if request.name.is_empty() {
+        request.name = context.to_owned();
+    }
+    request.captures = captures;
+    Some(request)
+}
+
+// The text of a `#` or `//` comment line.
+fn comment(line: &str) -> Option<&str> {
+    line.strip_prefix('#')
+        .or_else(|| line.strip_prefix("//"))
+        .map(str::trim)
+}
+
+// `@name value` in a comment.
+fn metadata(comment: &str) -> Option<(&str, &str)> {
+    if !comment.starts_with('@') {
+        return None;
+    }
+    let (tag, value) = comment
+        .split_once(char::is_whitespace)
+        .unwrap_or((comment, ""));
+    Some((tag, value.trim()))
+}
+
+// Response handlers and file references of the body aren't supported.
+fn request_body(lines: &[&str], report: &mut Vec<String>, context: &str) -> String {
+    let mut body: Vec<&str> = vec![];
+    let mut in_script = false;
+    for line in lines {
+        if in_script {
+            in_script = !line.contains("%}");
+            continue;
+        }
+        if line.starts_with("> {%") {
+            report.push(format!("{}: response handler script was skipped", context));
+            in_script = !line.contains("%}");
+        } else if line.starts_with("> ") || line.starts_with(">> ") || line.starts_with(">>! ") {
+            report.push(format!(
+                "{}: response handler {} was skipped",
+                context, line
+            ));
+        } else if line.starts_with("<> ") {
+            continue;
+        } else if line.starts_with("< ") && body.iter().all(|line| line.trim().is_empty()) {
+            report.push(format!(
+                "{}: body from file {} was skipped, file bodies aren't supported",
+                context,
+                line[2..].trim()
+            ));
+        } else {
+            body.push(line);
+        }
+    }
+    while body.last().is_some_and(|line| line.trim().is_empty()) {
+        body.pop();
+    }
+    body.join("\n")
+}
+
+// `Authorization: Basic user password` and the like become the auth of the request.
+fn authorization_header(headers: &mut Vec<(String, String)>) -> Auth {
+    let mut auth = Auth::default();
+    let Some(index) = headers
+        .iter()
+        .position(|(name, _)| name.eq_ignore_ascii_case("Authorization"))
+    else {
+        return auth;
+    };
+    let value = headers[index].1.clone();
+    let Some((scheme, credentials)) = value.split_once(' ') else {
+        return auth;
+    };
+    let credentials = credentials.trim();
+    let user_password = credentials
+        .split_once(' ')
+        .or_else(|| credentials.split_once(':'))
+        .map(|(user, password)| (user.to_owned(), password.trim().to_owned()));
+    match (scheme.to_lowercase().as_str(), user_password) {
+        ("bearer", _) => {
+            auth.mode = AuthMode::Bearer;
+            auth.bearer.token = credentials.to_owned();
+        }
+        // A single token is already encoded, it stays a header
+        ("basic", Some((username, password))) => {
+            auth.mode = AuthMode::Basic;
+            auth.basic.username = username;
+            auth.basic.password = password;
+        }
+        ("digest", Some((username, password))) => {
+            auth.mode = AuthMode::Digest;
+            auth.digest.username = username;
+            auth.digest.password = password;
+        }
+        _ => return auth,
+    }
+    headers.remove(index);
+    auth
+}
+
+// `{{name}}` becomes `{name}`, `{{$processEnv NAME}}` the `{$env.NAME}` variable.
+fn convert_references(text: &str, report: &mut Vec<String>, context: &str) -> String {
+    static REFERENCE: OnceLock<Regex> = OnceLock::new();
+    REFERENCE
+        .get_or_init(|| Regex::new(r"\{\{\s*([^{}]+?)\s*\}\}").unwrap())
+        .replace_all(text, |caps: &Captures<'_>| {
+            let name = &caps[1];
+            if let Some(variable) = name.strip_prefix("$processEnv ") {
+                return format!("{{{}{}}}", ENV_PREFIX, variable.trim());
+            }
+            if name.starts_with('$') && !name.starts_with(ENV_PREFIX) && !name.starts_with(CMD_PREFIX)
+            {
+                report.push(format!(
+                    "{}: dynamic variable {{{{{}}}}} has no equivalent, define it in an environment",
+                    context, name
+                ));
+            }
+            format!("{{{}}}", name)
+        })
+        .into_owned()
+}
+
+// `{name}` becomes `{{name}}`, braces that aren't variables, as in json, are kept.
+fn write_references(text: &str) -> String {
+    static REFERENCE: OnceLock<Regex> = OnceLock::new();
+    REFERENCE
+        .get_or_init(|| Regex::new(r"\{([A-Za-z_$][\w.$-]*)\}").unwrap())
+        .replace_all(text, |caps: &Captures<'_>| {
+            let name = &caps[1];
+            match name.strip_prefix(ENV_PREFIX) {
+                Some(variable) => format!("{{{{$processEnv {}}}}}", variable),
+                None => format!("{{{{{}}}}}", name),
+            }
+        })
+        .into_owned()
+}
+
+pub fn serialize_http_file(requests: &[SavedRequest], variables: &Map<String, Value>) -> String {
+    let mut contents = String::new();
+    for (name, value) in flatten_environment(variables) {
+        let value = match value {
+            Value::String(value) => value,
+            value => value.to_string(),
+        };
+        contents.push_str(&format!("@{} = {}\n", name, write_references(&value)));
+    }
+    for request in requests {
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        contents.push_str(&format!("### {}\n", request.name));
+        serialize_request(request, &mut contents);
+    }
+    contents
+}
+
+fn serialize_request(request: &SavedRequest, contents: &mut String) {
+    for rule in &request.capture_rules {
+        if let Ok(rule) = serde_json::to_string(rule) {
+            contents.push_str(&format!("# {} {}\n", CAPTURE_TAG, rule));
+        }
+    }
+    let headers: Vec<(&String, &String)> = request
+        .request_header_keys
+        .iter()
+        .zip(&request.request_header_values)
+        .filter(|(name, value)| !name.is_empty() || !value.is_empty())
+        .collect();
+    let (auth, _) = exported_auth(&request.auth);
+    let authorization = authorization_value(&auth).filter(|_| {
+        !headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("Authorization"))
+    });
+    if auth.mode != AuthMode::None && authorization.is_none() {
+        if let Ok(auth) = active_auth(&auth) {
+            contents.push_str(&format!("# {} {}\n", AUTH_TAG, auth));
+        }
+    }
+
+    contents.push_str(&format!(
+        "{} {}\n",
+        request.method,
+        write_references(&request.url)
+    ));
+    if let Some(authorization) = authorization {
+        contents.push_str(&format!(
+            "Authorization: {}\n",
+            write_references(&authorization)
+        ));
+    }
+    for (name, value) in headers {
+        contents.push_str(&format!(
+            "{}: {}\n",
+            write_references(name),
+            write_references(value)
+        ));
+    }
+    if !request.request_body.is_empty() {
+        contents.push_str(&format!("\n{}\n", write_references(&request.request_body)));
+    }
+}
+
+// Credentials are only written as `{variables}`, a value typed in the auth is
+// replaced by a variable named after its field.
+fn exported_auth(auth: &Auth) -> (Auth, Vec<&'static str>) {
+    let mut auth = auth.clone();
+    let mut replaced = vec![];
+    for (name, value) in auth.secret_fields() {
+        if !only_references(value) {
+            *value = format!("{{{}}}", name);
+            replaced.push(name);
+        }
+    }
+    (auth, replaced)
+}
+
+// The header other tools build the same auth from, when there is one.
+fn authorization_value(auth: &Auth) -> Option<String> {
+    let plain = |text: &str| !text.is_empty() && !text.contains(char::is_whitespace);
+    match auth.mode {
+        AuthMode::Bearer if plain(&auth.bearer.token) => {
+            Some(format!("Bearer {}", auth.bearer.token))
+        }
+        AuthMode::Basic if plain(&auth.basic.username) => Some(format!(
+            "Basic {} {}",
+            auth.basic.username, auth.basic.password
+        )),
+        AuthMode::Digest
+            if plain(&auth.digest.username) && auth.digest.algorithm == DigestAlgorithm::Md5 =>
+        {
+            Some(format!(
+                "Digest {} {}",
+                auth.digest.username, auth.digest.password
+            ))
+        }
+        _ => None,
+    }
+}
+
+// Only the settings of the selected mode, on a single line.
+fn active_auth(auth: &Auth) -> Result<String, String> {
+    let active = auth.mode.settings_key().unwrap_or_default();
+    match serde_json::to_value(auth).map_err(|err| err.to_string())? {
+        Value::Object(mut settings) => {
+            settings.retain(|key, _| key == "mode" || key == active);
+            Ok(Value::Object(settings).to_string())
+        }
+        _ => Err("Invalid auth settings".to_owned()),
+    }
+}
+
+// The requests of a folder and its sub folders, named after their path.
+pub fn folder_requests(folder: &Folder) -> Vec<SavedRequest> {
+    let mut requests = folder.requests.clone();
+    for child in &folder.folders {
+        for mut request in folder_requests(child) {
+            request.name = format!("{} / {}", child.name, request.name);
+            requests.push(request);
+        }
+    }
+    requests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tab_state::TabState;
+
+    fn request(auth: Auth) -> SavedRequest {
+        let state = TabState {
+            url: "https://example.com/{path}".to_owned(),
+            auth,
+            ..Default::default()
+        };
+        SavedRequest::new("Request".to_owned(), &state)
+    }
+
+    #[test]
+    fn secrets_are_written_as_variables() {
+        let mut auth = Auth {
+            mode: AuthMode::Basic,
+            ..Default::default()
+        };
+        auth.basic.username = "alice".to_owned();
+        auth.basic.password = "hunter2".to_owned();
+        let contents = serialize_http_file(&[request(auth)], &Map::new());
+        assert!(contents.contains("Authorization: Basic alice {{password}}\n"));
+        assert!(!contents.contains("hunter2"));
+
+        let mut auth = Auth {
+            mode: AuthMode::AwsSigV4,
+            ..Default::default()
+        };
+        auth.aws.access_key = "AKIDEXAMPLE".to_owned();
+        auth.aws.secret_key = "wJalrXUtnFEMI".to_owned();
+        auth.aws.session_token = "{session}".to_owned();
+        let contents = serialize_http_file(&[request(auth)], &Map::new());
+        assert!(!contents.contains("wJalrXUtnFEMI"));
+        let file = parse_http_file(&contents);
+        let aws = &file.requests[0].auth.aws;
+        assert_eq!(aws.access_key, "AKIDEXAMPLE");
+        assert_eq!(aws.secret_key, "{secret_key}");
+        assert_eq!(aws.session_token, "{session}");
+    }
+
+    #[test]
+    fn references_are_kept() {
+        let mut auth = Auth {
+            mode: AuthMode::Bearer,
+            ..Default::default()
+        };
+        auth.bearer.token = "{token}".to_owned();
+        let requests = [request(auth)];
+        let contents = serialize_http_file(&requests, &Map::new());
+        assert!(contents
+            .contains("GET https://example.com/{{path}}\nAuthorization: Bearer {{token}}\n"));
+        assert_eq!(exported_auth(&requests[0].auth).1, Vec::<&str>::new());
+        assert_eq!(
+            exported_message("Request", &BTreeSet::from(["password", "key"])),
+            "Exported Request, secrets were written as variables to define: {{key}}, {{password}}"
+        );
+    }
+
+    #[test]
+    fn converts_references() {
+        let mut report = vec![];
+        assert_eq!(
+            convert_references(
+                "{{ host }}/{{$processEnv HOME}}/{{$uuid}}",
+                &mut report,
+                "Request"
+            ),
+            "{host}/{$env.HOME}/{$uuid}"
+        );
+        assert_eq!(
+            report,
+            vec!["Request: dynamic variable {{$uuid}} has no equivalent, define it in an environment"]
+        );
+        // Json braces aren't variables
+        assert_eq!(
+            write_references("{\"id\": {id}, \"home\": \"{$env.HOME}\"}"),
+            "{\"id\": {{id}}, \"home\": \"{{$processEnv HOME}}\"}"
+        );
+    }
+}
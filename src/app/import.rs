@@ -0,0 +1,207 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::{Map, Value};
+
+use super::auth::Auth;
+use super::collections::{Folder, SavedRequest};
+use super::environment_file::{save_environment, EnvironmentFormat};
+use super::insomnia_import::{import_insomnia, is_insomnia_export};
+use super::openapi_import::{import_openapi, is_openapi_spec};
+use super::postman_import::import_postman;
+use super::project::unique_slug;
+use super::request_method::RequestMethod;
+use super::response_capture::CaptureRule;
+use super::tab_state::TabState;
+
+pub const MULTIPART_BOUNDARY: &str = "----RequestorFormBoundary";
+
+// Braces are kept so `{variables}` in form fields still get injected.
+const FORM_VALUE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'{')
+    .remove(b'}');
+
+// Everything read from a file of another tool.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub collections: Vec<Folder>,
+    pub environments: Vec<ImportedEnvironment>,
+    /// Variables marked as secret in the source.
+    pub secret_names: BTreeSet<String>,
+    /// What couldn't be converted, one line each.
+    pub report: Vec<String>,
+}
+
+impl Imported {
+    pub fn request_count(&self) -> usize {
+        fn count(folder: &Folder) -> usize {
+            folder.requests.len() + folder.folders.iter().map(count).sum::<usize>()
+        }
+        self.collections.iter().map(count).sum()
+    }
+}
+
+#[derive(Debug)]
+pub struct ImportedEnvironment {
+    pub name: String,
+    pub variables: Map<String, Value>,
+}
+
+// A request as described by another tool, before it becomes a saved request.
+#[derive(Debug, Default)]
+pub struct ImportedRequest {
+    pub name: String,
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub auth: Auth,
+    pub captures: Vec<CaptureRule>,
+}
+
+impl ImportedRequest {
+    pub fn has_header(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(name))
+    }
+
+    // Headers set explicitly win over the type implied by the body.
+    pub fn set_content_type(&mut self, content_type: &str) {
+        if !self.has_header("Content-Type") {
+            self.headers
+                .push(("Content-Type".to_owned(), content_type.to_owned()));
+        }
+    }
+
+    // Methods the app doesn't have are sent as GET and reported.
+    pub fn into_saved(self, context: &str, report: &mut Vec<String>) -> SavedRequest {
+        let method = match self.method.to_uppercase().as_str() {
+            "GET" | "" => RequestMethod::GET,
+            "POST" => RequestMethod::POST,
+            "PUT" => RequestMethod::PUT,
+            "PATCH" => RequestMethod::PATCH,
+            "DELETE" => RequestMethod::DELETE,
+            other => {
+                report.push(format!(
+                    "{}: method {} isn't supported, imported as GET",
+                    context, other
+                ));
+                RequestMethod::GET
+            }
+        };
+        let (mut keys, mut values): (Vec<String>, Vec<String>) = self.headers.into_iter().unzip();
+        keys.push("".to_owned());
+        values.push("".to_owned());
+        let state = TabState {
+            method,
+            url: self.url,
+            request_header_keys: keys,
+            request_header_values: values,
+            request_body: self.body,
+            auth: self.auth,
+            capture_rules: self.captures,
+            ..Default::default()
+        };
+        SavedRequest::new(self.name, &state)
+    }
+}
+
+// Reads a collection or environment exported by another tool, the format is
+// detected from the contents.
+pub fn import_file(path: &Path) -> Result<Imported, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    // Insomnia also exports yaml, json parses as yaml too but with worse errors
+    let value: Value = match serde_json::from_str(&contents) {
+        Ok(value) => value,
+        Err(json_err) => serde_yaml::from_str(&contents)
+            .map_err(|_| format!("Not a supported export: {}", json_err))?,
+    };
+    if is_insomnia_export(&value) {
+        return import_insomnia(&value);
+    }
+    if is_openapi_spec(&value) {
+        return import_openapi(&value);
+    }
+    import_postman(&value)
+}
+
+// Writes each environment to a new json file in the directory.
+pub fn write_environments(
+    directory: &Path,
+    environments: &[ImportedEnvironment],
+) -> Result<Vec<PathBuf>, String> {
+    fs::create_dir_all(directory).map_err(|err| err.to_string())?;
+    // Existing files are never overwritten
+    let mut used: BTreeSet<String> = fs::read_dir(directory)
+        .map_err(|err| err.to_string())?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .collect();
+    let mut written = vec![];
+    for environment in environments {
+        let slug = unique_slug(&environment.name, "environment", &mut used);
+        let path = directory.join(format!("{}.json", slug));
+        save_environment(&path, &environment.variables, Some(EnvironmentFormat::Json))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+pub fn form_urlencoded(fields: &[(String, String)]) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                utf8_percent_encode(key, FORM_VALUE),
+                utf8_percent_encode(value, FORM_VALUE)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// Text parts of a multipart form, as (name, content type, value).
+pub fn multipart_body(parts: &[(String, String, String)]) -> String {
+    let mut body = String::new();
+    for (name, content_type, value) in parts {
+        body.push_str(&format!("--{}\r\n", MULTIPART_BOUNDARY));
+        body.push_str(&format!(
+            "Content-Disposition: form-data; name=\"{}\"\r\n",
+            name.replace('"', "%22")
+        ));
+        if !content_type.is_empty() {
+            body.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        body.push_str(&format!("\r\n{}\r\n", value));
+    }
+    body.push_str(&format!("--{}--\r\n", MULTIPART_BOUNDARY));
+    body
+}
+
+pub fn multipart_content_type() -> String {
+    format!("multipart/form-data; boundary={}", MULTIPART_BOUNDARY)
+}
+
+// The non-empty headers of an imported request, for the importer tests.
+#[cfg(test)]
+pub fn headers(request: &SavedRequest) -> Vec<(&str, &str)> {
+    request
+        .request_header_keys
+        .iter()
+        .zip(&request.request_header_values)
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect()
+}
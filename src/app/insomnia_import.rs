@@ -0,0 +1,727 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::OnceLock;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+
+use super::auth::{ApiKeyLocation, Auth, AuthMode};
+use super::collections::Folder;
+use super::import::{
+    form_urlencoded, multipart_body, multipart_content_type, Imported, ImportedEnvironment,
+    ImportedRequest,
+};
+use super::oauth2::{ClientAuthentication, OAuth2Grant};
+use super::response_capture::{CaptureRule, CaptureSource};
+
+pub fn is_insomnia_export(value: &Value) -> bool {
+    value.get("_type").and_then(Value::as_str) == Some("export") && value.get("resources").is_some()
+}
+
+// Imports an Insomnia export (format 4, json or yaml). Every workspace becomes a
+// collection and every sub environment an environment merged over its base.
+pub fn import_insomnia(value: &Value) -> Result<Imported, String> {
+    let format = value.get("__export_format").and_then(Value::as_u64);
+    if format != Some(4) {
+        return Err("Only Insomnia exports of format 4 can be imported".to_owned());
+    }
+    let resources: Vec<&Value> = match value.get("resources") {
+        Some(Value::Array(resources)) => resources.iter().collect(),
+        _ => vec![],
+    };
+
+    let mut import = InsomniaImport {
+        names: resources
+            .iter()
+            .filter_map(|resource| Some((id(resource)?, text(resource.get("name"))?)))
+            .collect(),
+        ..Default::default()
+    };
+    for resource in &resources {
+        let parent = text(resource.get("parentId")).unwrap_or_default();
+        import.children.entry(parent).or_default().push(resource);
+    }
+    for children in import.children.values_mut() {
+        children.sort_by(|a, b| sort_key(a).total_cmp(&sort_key(b)));
+    }
+
+    let mut imported = Imported::default();
+    let workspaces: Vec<&Value> = resources
+        .iter()
+        .filter(|resource| kind(resource) == "workspace")
+        .copied()
+        .collect();
+    for workspace in workspaces {
+        let name = text(workspace.get("name")).unwrap_or_else(|| "Insomnia".to_owned());
+        let workspace_id = id(workspace).unwrap_or_default();
+        imported.environments.extend(import.environments(
+            &workspace_id,
+            &name,
+            &mut imported.secret_names,
+        ));
+        let mut collection = Folder::new(&name);
+        import.folder(&workspace_id, &name, &Auth::default(), &mut collection);
+        imported.collections.push(collection);
+    }
+    import.add_captures(&mut imported.collections);
+    imported.report = import.finish();
+    Ok(imported)
+}
+
+#[derive(Default)]
+struct InsomniaImport<'a> {
+    /// Resources by the id of their parent, in the order Insomnia shows them.
+    children: BTreeMap<String, Vec<&'a Value>>,
+    names: BTreeMap<String, String>,
+    report: Vec<String>,
+    /// Rules added to a request because another one uses its response.
+    captures: BTreeMap<String, Vec<CaptureRule>>,
+    /// Saved request ids by Insomnia request id.
+    saved_ids: BTreeMap<String, String>,
+    unsupported_tags: BTreeSet<String>,
+}
+
+impl<'a> InsomniaImport<'a> {
+    fn finish(mut self) -> Vec<String> {
+        if !self.unsupported_tags.is_empty() {
+            self.report.push(format!(
+                "Template tags without an equivalent were replaced by a variable of the same name: {}",
+                self.unsupported_tags.into_iter().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        self.report
+    }
+
+    fn children(&self, parent: &str) -> Vec<&'a Value> {
+        self.children.get(parent).cloned().unwrap_or_default()
+    }
+
+    // `{{ _.name }}` becomes `{name}`, response tags become capture rules of the
+    // request they read from and other tags a variable to fill in.
+    fn convert(&mut self, text: &str, context: &str) -> String {
+        static VARIABLE: OnceLock<Regex> = OnceLock::new();
+        static TAG: OnceLock<Regex> = OnceLock::new();
+        let variable =
+            VARIABLE.get_or_init(|| Regex::new(r"\{\{\s*(?:_\.)?([^{}\s]+)\s*\}\}").unwrap());
+        let converted = variable.replace_all(text, "{$1}").into_owned();
+        let tag = TAG.get_or_init(|| Regex::new(r"\{%\s*(\w+)\s*(.*?)\s*%\}").unwrap());
+        tag.replace_all(&converted, |caps: &Captures<'_>| {
+            let name = &caps[1];
+            if name == "response" {
+                if let Some(variable) = self.response_tag(&caps[2], context) {
+                    return format!("{{{}}}", variable);
+                }
+            }
+            self.unsupported_tags.insert(caps[0].to_owned());
+            format!("{{{}}}", name)
+        })
+        .into_owned()
+    }
+
+    // `{% response 'body', 'req_1', 'b64::JC50b2tlbg==::46b', 'never', 60 %}`
+    fn response_tag(&mut self, args: &str, context: &str) -> Option<String> {
+        let args = tag_arguments(args);
+        let attribute = args.first()?;
+        let request_id = args.get(1)?;
+        let filter = args.get(2).map(|filter| decode_filter(filter))?;
+        let (source, variable) = match attribute.as_str() {
+            "body" if filter.starts_with('$') => {
+                let last = filter.rsplit(['.', '[']).next().unwrap_or("");
+                (CaptureSource::JsonPath, variable_name(last))
+            }
+            "header" if !filter.is_empty() => (CaptureSource::Header, variable_name(&filter)),
+            _ => return None,
+        };
+        let variable = match variable.as_str() {
+            "" => "response_value".to_owned(),
+            _ => variable,
+        };
+        let rule = CaptureRule {
+            variable: variable.clone(),
+            source,
+            expression: filter,
+        };
+        let rules = self.captures.entry(request_id.clone()).or_default();
+        if !rules
+            .iter()
+            .any(|existing| existing.variable == rule.variable)
+        {
+            rules.push(rule);
+        }
+        let source_name = self.names.get(request_id).cloned().unwrap_or_default();
+        self.report.push(format!(
+            "{}: a response tag is now {{{}}}, captured from \"{}\", send it first with the same environment",
+            context, variable, source_name
+        ));
+        Some(variable)
+    }
+
+    fn add_captures(&mut self, collections: &mut [Folder]) {
+        fn add(folder: &mut Folder, rules: &BTreeMap<&String, &Vec<CaptureRule>>) {
+            for request in &mut folder.requests {
+                if let Some(rules) = rules.get(&request.id) {
+                    request.capture_rules.extend(rules.iter().cloned());
+                }
+            }
+            for child in &mut folder.folders {
+                add(child, rules);
+            }
+        }
+        let mut rules = BTreeMap::new();
+        for (insomnia_id, captures) in &self.captures {
+            match self.saved_ids.get(insomnia_id) {
+                Some(saved_id) => {
+                    rules.insert(saved_id, captures);
+                }
+                None => self.report.push(format!(
+                    "A response tag reads from request {} which isn't in the export",
+                    insomnia_id
+                )),
+            }
+        }
+        for collection in collections {
+            add(collection, &rules);
+        }
+    }
+
+    fn folder(&mut self, parent: &str, context: &str, auth: &Auth, folder: &mut Folder) {
+        for resource in self.children(parent) {
+            let name = text(resource.get("name")).unwrap_or_default();
+            let item_context = format!("{} / {}", context, name);
+            let resource_id = id(resource).unwrap_or_default();
+            match kind(resource) {
+                "request_group" => {
+                    let environment = object(resource.get("environment"));
+                    if !environment.is_empty() {
+                        self.report.push(format!(
+                            "{}: folder environment variables aren't supported, add them to an environment: {}",
+                            item_context,
+                            environment.keys().cloned().collect::<Vec<_>>().join(", ")
+                        ));
+                    }
+                    let group_auth = self
+                        .auth(resource.get("authentication"), &item_context)
+                        .unwrap_or_else(|| auth.clone());
+                    let mut child = Folder::new(&name);
+                    self.folder(&resource_id, &item_context, &group_auth, &mut child);
+                    folder.folders.push(child);
+                }
+                "request" => {
+                    let request_auth = self
+                        .auth(resource.get("authentication"), &item_context)
+                        .unwrap_or_else(|| auth.clone());
+                    let request = self.request(resource, name, &item_context, request_auth);
+                    let saved = request.into_saved(&item_context, &mut self.report);
+                    self.saved_ids.insert(resource_id, saved.id.clone());
+                    folder.requests.push(saved);
+                }
+                "grpc_request" | "websocket_request" => self.report.push(format!(
+                    "{}: {} requests aren't supported",
+                    item_context,
+                    match kind(resource) {
+                        "grpc_request" => "gRPC",
+                        _ => "WebSocket",
+                    }
+                )),
+                _ => (),
+            }
+        }
+    }
+
+    fn request(
+        &mut self,
+        resource: &Value,
+        name: String,
+        context: &str,
+        auth: Auth,
+    ) -> ImportedRequest {
+        let mut request = ImportedRequest {
+            name,
+            auth,
+            method: text(resource.get("method")).unwrap_or_else(|| "GET".to_owned()),
+            ..Default::default()
+        };
+        // Query parameters are kept apart from the url in Insomnia
+        let mut url = text(resource.get("url")).unwrap_or_default();
+        let query = self.pairs(resource.get("parameters"), context, "query parameter");
+        if !query.is_empty() {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            let query: Vec<String> = query
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            url = format!("{}{}{}", url, separator, query.join("&"));
+        }
+        request.url = self.convert(&url, context);
+        request.headers = self
+            .pairs(resource.get("headers"), context, "header")
+            .into_iter()
+            .map(|(key, value)| (self.convert(&key, context), self.convert(&value, context)))
+            .collect();
+        if let Some(body) = resource.get("body") {
+            self.body(body, &mut request, context);
+        }
+        request
+    }
+
+    // Enabled name/value pairs, disabled ones are reported.
+    fn pairs(&mut self, pairs: Option<&Value>, context: &str, kind: &str) -> Vec<(String, String)> {
+        let mut enabled = vec![];
+        for pair in array(pairs) {
+            let name = text(pair.get("name")).unwrap_or_default();
+            if pair.get("disabled") == Some(&Value::Bool(true)) {
+                self.report.push(format!(
+                    "{}: disabled {} {} was skipped",
+                    context, kind, name
+                ));
+                continue;
+            }
+            enabled.push((name, text(pair.get("value")).unwrap_or_default()));
+        }
+        enabled
+    }
+
+    fn body(&mut self, body: &Value, request: &mut ImportedRequest, context: &str) {
+        let mime_type = text(body.get("mimeType")).unwrap_or_default();
+        match mime_type.as_str() {
+            "application/x-www-form-urlencoded" => {
+                let fields: Vec<(String, String)> = self
+                    .pairs(body.get("params"), context, "form field")
+                    .into_iter()
+                    .map(|(key, value)| {
+                        (self.convert(&key, context), self.convert(&value, context))
+                    })
+                    .collect();
+                request.body = form_urlencoded(&fields);
+                request.set_content_type(&mime_type);
+            }
+            "multipart/form-data" => {
+                let mut parts = vec![];
+                for param in array(body.get("params")) {
+                    let name = text(param.get("name")).unwrap_or_default();
+                    if param.get("disabled") == Some(&Value::Bool(true)) {
+                        self.report.push(format!(
+                            "{}: disabled form field {} was skipped",
+                            context, name
+                        ));
+                        continue;
+                    }
+                    if param.get("type").and_then(Value::as_str) == Some("file") {
+                        self.report.push(format!(
+                            "{}: file field {} of the form was skipped, file uploads aren't supported",
+                            context, name
+                        ));
+                        continue;
+                    }
+                    let value = text(param.get("value")).unwrap_or_default();
+                    parts.push((
+                        self.convert(&name, context),
+                        "".to_owned(),
+                        self.convert(&value, context),
+                    ));
+                }
+                request.body = multipart_body(&parts);
+                // The boundary Insomnia generates isn't in the export
+                request
+                    .headers
+                    .retain(|(key, _)| !key.eq_ignore_ascii_case("Content-Type"));
+                request.set_content_type(&multipart_content_type());
+            }
+            _ if body.get("fileName").is_some() => {
+                let file_name = text(body.get("fileName")).unwrap_or_default();
+                self.report.push(format!(
+                    "{}: file body {} was skipped, file uploads aren't supported",
+                    context, file_name
+                ));
+            }
+            _ => {
+                request.body = self.convert(&text(body.get("text")).unwrap_or_default(), context);
+                let content_type = match mime_type.as_str() {
+                    // Sent as a json body with the query and variables
+                    "application/graphql" => "application/json",
+                    mime_type => mime_type,
+                };
+                if !request.body.is_empty() && !content_type.is_empty() {
+                    request.set_content_type(content_type);
+                }
+            }
+        }
+    }
+
+    // None when the auth is inherited from the folder.
+    fn auth(&mut self, auth: Option<&Value>, context: &str) -> Option<Auth> {
+        let auth = auth.filter(|auth| auth.as_object().is_some_and(|auth| !auth.is_empty()))?;
+        let auth_type = auth.get("type").and_then(Value::as_str).unwrap_or("none");
+        if auth_type == "inherit" {
+            return None;
+        }
+        let mut converted = Auth::default();
+        if auth.get("disabled") == Some(&Value::Bool(true)) {
+            return Some(converted);
+        }
+        let mut param = |key: &str| {
+            let value = text(auth.get(key)).unwrap_or_default();
+            self.convert(&value, context)
+        };
+        let mut unsupported = None;
+        match auth_type {
+            "none" => (),
+            "basic" => {
+                converted.mode = AuthMode::Basic;
+                converted.basic.username = param("username");
+                converted.basic.password = param("password");
+            }
+            "bearer" => {
+                converted.mode = AuthMode::Bearer;
+                converted.bearer.token = param("token");
+                let prefix = param("prefix");
+                if !prefix.is_empty() && prefix != "Bearer" {
+                    unsupported = Some(format!(
+                        "bearer prefix {} isn't supported, Bearer is used",
+                        prefix
+                    ));
+                }
+            }
+            "apikey" => {
+                converted.mode = AuthMode::ApiKey;
+                converted.api_key.name = param("key");
+                converted.api_key.value = param("value");
+                match param("addTo").as_str() {
+                    "queryParams" => converted.api_key.location = ApiKeyLocation::QueryParam,
+                    "cookie" => {
+                        unsupported = Some("API keys in a cookie are sent as a header".to_owned())
+                    }
+                    _ => (),
+                }
+            }
+            "digest" => {
+                converted.mode = AuthMode::Digest;
+                converted.digest.username = param("username");
+                converted.digest.password = param("password");
+            }
+            "iam" => {
+                converted.mode = AuthMode::AwsSigV4;
+                converted.aws.access_key = param("accessKeyId");
+                converted.aws.secret_key = param("secretAccessKey");
+                converted.aws.session_token = param("sessionToken");
+                converted.aws.region = param("region");
+                converted.aws.service = param("service");
+            }
+            "oauth2" => {
+                converted.mode = AuthMode::OAuth2;
+                let oauth2 = &mut converted.oauth2;
+                oauth2.grant = match param("grantType").as_str() {
+                    "client_credentials" => OAuth2Grant::ClientCredentials,
+                    "password" => OAuth2Grant::Password,
+                    "refresh_token" => OAuth2Grant::RefreshToken,
+                    "authorization_code" | "" => OAuth2Grant::AuthorizationCode,
+                    other => {
+                        unsupported = Some(format!(
+                            "OAuth 2.0 grant {} isn't supported, imported as authorization code",
+                            other
+                        ));
+                        OAuth2Grant::AuthorizationCode
+                    }
+                };
+                oauth2.token_url = param("accessTokenUrl");
+                oauth2.authorization_url = param("authorizationUrl");
+                oauth2.client_id = param("clientId");
+                oauth2.client_secret = param("clientSecret");
+                oauth2.scope = param("scope");
+                oauth2.username = param("username");
+                oauth2.password = param("password");
+                oauth2.refresh_token = param("refreshToken");
+                if param("credentialsInBody") == "true" {
+                    oauth2.client_authentication = ClientAuthentication::RequestBody;
+                }
+                let redirect = param("redirectUrl");
+                let port = url::Url::parse(&redirect)
+                    .ok()
+                    .filter(|url| matches!(url.host_str(), Some("localhost" | "127.0.0.1")))
+                    .and_then(|url| url.port());
+                match port {
+                    Some(port) => oauth2.redirect_port = port,
+                    None if oauth2.grant == OAuth2Grant::AuthorizationCode
+                        && !redirect.is_empty() =>
+                    {
+                        unsupported = Some(format!(
+                            "redirect url {} is replaced by a local one, register it with the provider",
+                            redirect
+                        ));
+                    }
+                    None => (),
+                }
+            }
+            other => {
+                unsupported = Some(format!(
+                    "{} auth isn't supported, the request has no auth",
+                    other
+                ));
+            }
+        }
+        if let Some(unsupported) = unsupported {
+            self.report.push(format!("{}: {}", context, unsupported));
+        }
+        Some(converted)
+    }
+
+    fn environments(
+        &mut self,
+        workspace_id: &str,
+        workspace_name: &str,
+        secret_names: &mut BTreeSet<String>,
+    ) -> Vec<ImportedEnvironment> {
+        let mut environments = vec![];
+        for base in self.children(workspace_id) {
+            if kind(base) != "environment" {
+                continue;
+            }
+            let base_name = text(base.get("name")).unwrap_or_default();
+            let context = format!("{} / {}", workspace_name, base_name);
+            let base_data = self.environment_data(base, &context);
+            let sub_environments: Vec<&Value> = self
+                .children(&id(base).unwrap_or_default())
+                .into_iter()
+                .filter(|resource| kind(resource) == "environment")
+                .collect();
+            if sub_environments.is_empty() {
+                if !base_data.is_empty() {
+                    environments.push(ImportedEnvironment {
+                        name: context,
+                        variables: base_data,
+                    });
+                }
+                continue;
+            }
+            // Tabs have one environment, so the base is merged into each sub environment
+            for sub in sub_environments {
+                let name = text(sub.get("name")).unwrap_or_default();
+                let context = format!("{} / {}", workspace_name, name);
+                let mut variables = base_data.clone();
+                let data = self.environment_data(sub, &context);
+                if sub.get("isPrivate") == Some(&Value::Bool(true)) {
+                    secret_names.extend(data.keys().cloned());
+                    self.report.push(format!(
+                        "{}: values of the private environment were written to the environment file and marked as secrets",
+                        context
+                    ));
+                }
+                variables.extend(data);
+                environments.push(ImportedEnvironment {
+                    name: context,
+                    variables,
+                });
+            }
+        }
+        environments
+    }
+
+    fn environment_data(&mut self, environment: &Value, context: &str) -> Map<String, Value> {
+        object(environment.get("data"))
+            .into_iter()
+            .map(|(key, value)| (key, self.convert_value(value, context)))
+            .collect()
+    }
+
+    fn convert_value(&mut self, value: Value, context: &str) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.convert(&text, context)),
+            Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(|item| self.convert_value(item, context))
+                    .collect(),
+            ),
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, self.convert_value(value, context)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+}
+
+// Arguments of a template tag, quoted or not, separated by commas.
+fn tag_arguments(args: &str) -> Vec<String> {
+    static ARGUMENT: OnceLock<Regex> = OnceLock::new();
+    ARGUMENT
+        .get_or_init(|| Regex::new(r#"'((?:[^'\\]|\\.)*)'|"((?:[^"\\]|\\.)*)"|([^,\s]+)"#).unwrap())
+        .captures_iter(args)
+        .filter_map(|caps| {
+            caps.get(1)
+                .or_else(|| caps.get(2))
+                .or_else(|| caps.get(3))
+                .map(|arg| arg.as_str().to_owned())
+        })
+        .collect()
+}
+
+// Filters can be stored as `b64::<base64>::46b`.
+fn decode_filter(filter: &str) -> String {
+    filter
+        .strip_prefix("b64::")
+        .and_then(|encoded| encoded.strip_suffix("::46b"))
+        .and_then(|encoded| STANDARD.decode(encoded).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .unwrap_or_else(|| filter.to_owned())
+}
+
+fn variable_name(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() => c.to_ascii_lowercase(),
+            _ => '_',
+        })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_owned()
+}
+
+fn id(resource: &Value) -> Option<String> {
+    text(resource.get("_id"))
+}
+
+fn kind(resource: &Value) -> &str {
+    resource.get("_type").and_then(Value::as_str).unwrap_or("")
+}
+
+fn sort_key(resource: &Value) -> f64 {
+    resource
+        .get("metaSortKey")
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0)
+}
+
+fn array(value: Option<&Value>) -> &[Value] {
+    match value {
+        Some(Value::Array(values)) => values,
+        _ => &[],
+    }
+}
+
+fn object(value: Option<&Value>) -> Map<String, Value> {
+    match value {
+        Some(Value::Object(map)) => map.clone(),
+        _ => Map::new(),
+    }
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(text) => Some(text.clone()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::import::headers;
+    use crate::app::request_method::RequestMethod;
+
+    fn fixture() -> Imported {
+        let contents = include_str!("../../tests/fixtures/insomnia_export.json");
+        import_insomnia(&serde_json::from_str(contents).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn imports_workspaces_requests_and_environments() {
+        let imported = fixture();
+        assert_eq!(imported.request_count(), 3);
+        let collection = &imported.collections[0];
+        assert_eq!(collection.name, "Billing API");
+        assert_eq!(collection.folders[0].name, "Invoices");
+
+        let login = &collection.requests[0];
+        assert_eq!(login.name, "Login");
+        assert_eq!(login.method, RequestMethod::POST);
+        assert_eq!(login.url, "{base_url}/login");
+        assert_eq!(login.request_body, "{\"user\": \"{user}\"}");
+        assert_eq!(login.auth.mode, AuthMode::Basic);
+        assert_eq!(login.auth.basic.username, "{user}");
+        assert_eq!(login.auth.basic.password, "{password}");
+
+        // The base environment is merged into its sub environment
+        assert_eq!(imported.environments.len(), 1);
+        let environment = &imported.environments[0];
+        assert_eq!(environment.name, "Billing API / Production");
+        assert_eq!(
+            Value::Object(environment.variables.clone()),
+            serde_json::json!({
+                "base_url": "https://billing.example.com",
+                "api_version": "v2",
+                "token": "prod-token"
+            })
+        );
+        assert_eq!(imported.secret_names, BTreeSet::from(["token".to_owned()]));
+    }
+
+    #[test]
+    fn converts_requests_and_inherits_folder_auth() {
+        let imported = fixture();
+        let invoices = &imported.collections[0].folders[0];
+        // Sorted by metaSortKey, not by their order in the export
+        let list = &invoices.requests[0];
+        assert_eq!(list.name, "List invoices");
+        assert_eq!(list.url, "{base_url}/{api_version}/invoices?page=1");
+        assert_eq!(list.auth.mode, AuthMode::Bearer);
+        assert_eq!(list.auth.bearer.token, "{token}");
+
+        let create = &invoices.requests[1];
+        assert_eq!(create.auth.mode, AuthMode::ApiKey);
+        assert_eq!(create.auth.api_key.name, "X-Api-Key");
+        assert_eq!(create.auth.api_key.value, "{api_key}");
+        assert_eq!(create.auth.api_key.location, ApiKeyLocation::QueryParam);
+        assert!(create
+            .request_body
+            .contains("name=\"note\"\r\n\r\n{faker}\r\n"));
+        assert!(!create.request_body.contains("invoice.pdf"));
+        // The boundary of the export is replaced by the one of the body
+        let content_type = headers(create);
+        assert_eq!(content_type.len(), 1);
+        assert_eq!(content_type[0].1, multipart_content_type());
+    }
+
+    #[test]
+    fn response_tags_become_captures() {
+        let imported = fixture();
+        let collection = &imported.collections[0];
+        let list = &collection.folders[0].requests[0];
+        assert_eq!(headers(list), vec![("X-Session", "{access_token}")]);
+
+        let login = &collection.requests[0];
+        assert_eq!(login.capture_rules.len(), 1);
+        let rule = &login.capture_rules[0];
+        assert_eq!(rule.variable, "access_token");
+        assert_eq!(rule.source, CaptureSource::JsonPath);
+        assert_eq!(rule.expression, "$.access_token");
+    }
+
+    #[test]
+    fn reports_what_wasnt_imported() {
+        assert_eq!(
+            fixture().report,
+            vec![
+                "Billing API / Production: values of the private environment were written to the environment file and marked as secrets",
+                "Billing API / Invoices: folder environment variables aren't supported, add them to an environment: page_size",
+                "Billing API / Invoices / List invoices: disabled query parameter debug was skipped",
+                "Billing API / Invoices / List invoices: a response tag is now {access_token}, captured from \"Login\", send it first with the same environment",
+                "Billing API / Invoices / Create invoice: file field pdf of the form was skipped, file uploads aren't supported",
+                "Billing API / Stream invoices: gRPC requests aren't supported",
+                "Template tags without an equivalent were replaced by a variable of the same name: {% faker 'randomWord' %}",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_other_export_formats() {
+        let v3 = serde_json::json!({"_type": "export", "__export_format": 3, "resources": []});
+        assert_eq!(
+            import_insomnia(&v3).unwrap_err(),
+            "Only Insomnia exports of format 4 can be imported"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use std::fmt;
+use std::fs;
+use std::sync::OnceLock;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::environment_injector::{flatten_environment, inject_environment};
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum JwtAlgorithm {
+    #[default]
+    Hs256,
+    Rs256,
+    Es256,
+}
+
+impl JwtAlgorithm {
+    pub const ALL: [JwtAlgorithm; 3] = [
+        JwtAlgorithm::Hs256,
+        JwtAlgorithm::Rs256,
+        JwtAlgorithm::Es256,
+    ];
+}
+
+impl fmt::Display for JwtAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwtAlgorithm::Hs256 => write!(f, "HS256"),
+            JwtAlgorithm::Rs256 => write!(f, "RS256"),
+            JwtAlgorithm::Es256 => write!(f, "ES256"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum JwtKeySource {
+    #[default]
+    Text,
+    File,
+}
+
+impl fmt::Display for JwtKeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwtKeySource::Text => write!(f, "Text"),
+            JwtKeySource::File => write!(f, "File path"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JwtAuth {
+    pub algorithm: JwtAlgorithm,
+    /// The HMAC secret, or a PEM private key for RS256 and ES256.
+    pub key: String,
+    pub key_source: JwtKeySource,
+    pub key_id: String,
+    /// Json object with the claims, string values can use variables.
+    pub claims: String,
+    pub add_issued_at: bool,
+    pub add_expiry: bool,
+    pub lifetime_seconds: u64,
+}
+
+impl Default for JwtAuth {
+    fn default() -> Self {
+        Self {
+            algorithm: JwtAlgorithm::Hs256,
+            key: "".to_owned(),
+            key_source: JwtKeySource::Text,
+            key_id: "".to_owned(),
+            claims: "{\n  \"sub\": \"\"\n}".to_owned(),
+            add_issued_at: true,
+            add_expiry: true,
+            lifetime_seconds: 3600,
+        }
+    }
+}
+
+// Builds and signs a token, called when the request is sent so `iat` and `exp`
+// follow the clock. Claims set in the editor win.
+pub fn generate_jwt(config: &JwtAuth, variables: &Map<String, Value>) -> Result<String, String> {
+    let inject = |text: &String| inject_environment(text, variables).0;
+
+    // Parsed before the variables are injected, so a value with quotes can't
+    // change the claims
+    let mut claims: Map<String, Value> = match serde_json::from_str(&config.claims) {
+        Ok(Value::Object(claims)) => claims,
+        Ok(_) => return Err("The JWT claims must be a json object".to_owned()),
+        Err(err) => return Err(format!("Invalid JWT claims: {}", err)),
+    };
+    let flattened = flatten_environment(variables);
+    for value in claims.values_mut() {
+        inject_claim(value, &flattened, variables);
+    }
+    let now = Utc::now().timestamp();
+    if config.add_issued_at && !claims.contains_key("iat") {
+        claims.insert("iat".to_owned(), now.into());
+    }
+    if config.add_expiry && !claims.contains_key("exp") {
+        let exp = now + config.lifetime_seconds as i64;
+        claims.insert("exp".to_owned(), exp.into());
+    }
+
+    let key = match config.key_source {
+        JwtKeySource::Text => inject(&config.key).into_bytes(),
+        JwtKeySource::File => fs::read(inject(&config.key).trim())
+            .map_err(|err| format!("Unable to read the JWT key file: {}", err))?,
+    };
+    if key.is_empty() {
+        return Err("The JWT key is empty".to_owned());
+    }
+    let (algorithm, key) = match config.algorithm {
+        JwtAlgorithm::Hs256 => (Algorithm::HS256, EncodingKey::from_secret(&key)),
+        JwtAlgorithm::Rs256 => (
+            Algorithm::RS256,
+            EncodingKey::from_rsa_pem(&key).map_err(key_error)?,
+        ),
+        JwtAlgorithm::Es256 => (
+            Algorithm::ES256,
+            EncodingKey::from_ec_pem(&key).map_err(key_error)?,
+        ),
+    };
+
+    let mut header = Header::new(algorithm);
+    let key_id = inject(&config.key_id);
+    if !key_id.is_empty() {
+        header.kid = Some(key_id);
+    }
+    jsonwebtoken::encode(&header, &claims, &key).map_err(|err| err.to_string())
+}
+
+// Injects the variables into the string values. A value that is only a
+// reference to a number or boolean variable takes its type, `"{user_id}"`.
+fn inject_claim(value: &mut Value, flattened: &[(String, Value)], variables: &Map<String, Value>) {
+    match value {
+        Value::String(text) => {
+            let name = text.strip_prefix('{').and_then(|t| t.strip_suffix('}'));
+            let typed = flattened
+                .iter()
+                .find(|(key, _)| Some(key.as_str()) == name)
+                .filter(|(_, variable)| variable.is_number() || variable.is_boolean());
+            *value = match typed {
+                Some((_, variable)) => variable.clone(),
+                None => Value::String(inject_environment(text, variables).0),
+            };
+        }
+        Value::Array(items) => {
+            for item in items {
+                inject_claim(item, flattened, variables);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                inject_claim(item, flattened, variables);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn key_error(error: jsonwebtoken::errors::Error) -> String {
+    format!("The JWT key isn't a valid PEM private key: {}", error)
+}
+
+// A token found in a response, decoded without checking the signature.
+#[derive(Debug, Clone)]
+pub struct DecodedJwt {
+    /// Where the token was found, a header name or the body.
+    pub source: String,
+    pub header: Value,
+    pub claims: Value,
+}
+
+impl DecodedJwt {
+    pub fn time_claim(&self, name: &str) -> Option<DateTime<Utc>> {
+        let seconds = self.claims.get(name)?.as_i64()?;
+        DateTime::from_timestamp(seconds, 0)
+    }
+}
+
+// Finds the tokens in the headers (cookies included) and the body of a response.
+pub fn find_jwts(response: &ehttp::Response, body: Option<&str>) -> Vec<DecodedJwt> {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = REGEX.get_or_init(|| {
+        Regex::new(r"eyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*").unwrap()
+    });
+    let mut sources: Vec<(String, &str)> = response
+        .headers
+        .headers
+        .iter()
+        .map(|(k, v)| (k.clone(), v.as_str()))
+        .collect();
+    if let Some(body) = body {
+        sources.push(("body".to_owned(), body));
+    }
+
+    let mut found: Vec<DecodedJwt> = vec![];
+    let mut seen = vec![];
+    for (source, text) in sources {
+        for token in regex.find_iter(text) {
+            if seen.contains(&token.as_str()) {
+                continue;
+            }
+            seen.push(token.as_str());
+            if let Some((header, claims)) = decode_jwt(token.as_str()) {
+                found.push(DecodedJwt {
+                    source: source.clone(),
+                    header,
+                    claims,
+                });
+            }
+        }
+    }
+    found
+}
+
+fn decode_jwt(token: &str) -> Option<(Value, Value)> {
+    let mut parts = token.split('.');
+    let decode = |part: &str| -> Option<Value> {
+        let bytes = URL_SAFE_NO_PAD.decode(part).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    };
+    let header = decode(parts.next()?)?;
+    let claims = decode(parts.next()?)?;
+    Some((header, claims))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn claims(token: &str) -> Value {
+        decode_jwt(token).unwrap().1
+    }
+
+    #[test]
+    fn injects_variables_after_parsing_the_claims() {
+        let config = JwtAuth {
+            key: "{secret}".to_owned(),
+            claims: r#"{"sub": "{name}", "id": "{id}", "admin": "{admin}", "scope": ["read:{name}"], "org": {"id": "org-{id}"}}"#.to_owned(),
+            add_issued_at: false,
+            add_expiry: false,
+            ..Default::default()
+        };
+        let variables = json!({"secret": "key", "name": "a\", \"admin\": true, \"x\": \"", "id": 7, "admin": false});
+        let token = generate_jwt(&config, variables.as_object().unwrap()).unwrap();
+        assert_eq!(
+            claims(&token),
+            json!({
+                "sub": "a\", \"admin\": true, \"x\": \"",
+                "id": 7,
+                "admin": false,
+                "scope": ["read:a\", \"admin\": true, \"x\": \""],
+                "org": {"id": "org-7"}
+            })
+        );
+    }
+
+    #[test]
+    fn adds_iat_and_exp_unless_set() {
+        let config = JwtAuth {
+            key: "key".to_owned(),
+            claims: r#"{"exp": 1}"#.to_owned(),
+            lifetime_seconds: 60,
+            ..Default::default()
+        };
+        let claims = claims(&generate_jwt(&config, &Map::new()).unwrap());
+        assert_eq!(claims["exp"], json!(1));
+        assert!(claims["iat"].as_i64().unwrap() > 0);
+    }
+
+    #[test]
+    fn reports_invalid_claims_and_keys() {
+        let mut config = JwtAuth {
+            key: "key".to_owned(),
+            claims: "[1]".to_owned(),
+            ..Default::default()
+        };
+        let error = generate_jwt(&config, &Map::new()).unwrap_err();
+        assert_eq!(error, "The JWT claims must be a json object");
+        config.claims = "{}".to_owned();
+        config.algorithm = JwtAlgorithm::Rs256;
+        let error = generate_jwt(&config, &Map::new()).unwrap_err();
+        assert!(error.starts_with("The JWT key isn't a valid PEM private key"));
+    }
+
+    #[test]
+    fn finds_tokens_in_headers_and_body_once() {
+        let config = JwtAuth {
+            key: "key".to_owned(),
+            claims: r#"{"sub": "1"}"#.to_owned(),
+            add_issued_at: false,
+            add_expiry: false,
+            ..Default::default()
+        };
+        let token = generate_jwt(&config, &Map::new()).unwrap();
+        let response = ehttp::Response {
+            url: "https://example.com".to_owned(),
+            ok: true,
+            status: 200,
+            status_text: "OK".to_owned(),
+            headers: ehttp::Headers::new(&[("Set-Cookie", &format!("session={}; Path=/", token))]),
+            bytes: vec![],
+        };
+        let body = format!(r#"{{"token": "{}", "again": "{}"}}"#, token, token);
+        let found = find_jwts(&response, Some(&body));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].source, "Set-Cookie");
+        assert_eq!(found[0].header["alg"], json!("HS256"));
+        assert_eq!(found[0].claims, json!({"sub": "1"}));
+    }
+}
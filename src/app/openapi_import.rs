@@ -0,0 +1,788 @@
+use std::collections::BTreeSet;
+
+use serde_json::{json, Map, Value};
+
+use super::auth::{ApiKeyLocation, Auth, AuthMode};
+use super::collections::Folder;
+use super::import::{
+    form_urlencoded, multipart_body, multipart_content_type, Imported, ImportedEnvironment,
+    ImportedRequest,
+};
+use super::oauth2::OAuth2Grant;
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+// Deep enough for real schemas, and a stop for recursive ones.
+const MAX_EXAMPLE_DEPTH: usize = 8;
+
+pub fn is_openapi_spec(value: &Value) -> bool {
+    value.get("openapi").is_some() || value.get("swagger").is_some()
+}
+
+// Imports an OpenAPI 3 or Swagger 2 spec as a collection with a folder per tag.
+// The servers become environments with a `base_url` variable.
+pub fn import_openapi(spec: &Value) -> Result<Imported, String> {
+    let swagger = match (text(spec.get("openapi")), text(spec.get("swagger"))) {
+        (Some(version), _) if version.starts_with('3') => false,
+        (_, Some(version)) if version.starts_with('2') => true,
+        _ => return Err("Only OpenAPI 3 and Swagger 2 specs can be imported".to_owned()),
+    };
+    let title = text(spec.pointer("/info/title")).unwrap_or_else(|| "OpenAPI".to_owned());
+    let mut import = OpenApiImport {
+        spec,
+        swagger,
+        report: vec![],
+        variables: Map::new(),
+        external_refs: BTreeSet::new(),
+        schema_refs: vec![],
+    };
+
+    let mut collection = Folder::new(&title);
+    // Folders follow the order of the tags in the spec
+    for tag in array(spec.get("tags")) {
+        if let Some(name) = text(tag.get("name")) {
+            collection.folders.push(Folder::new(&name));
+        }
+    }
+    let paths = spec.get("paths").and_then(Value::as_object);
+    for (path, item) in paths.into_iter().flatten() {
+        let item = import.resolve(item);
+        for method in METHODS {
+            let Some(operation) = item.get(method) else {
+                continue;
+            };
+            let request = import.operation(path, method, &item, operation);
+            let context = format!("{} / {}", title, request.name);
+            let saved = request.into_saved(&context, &mut import.report);
+            match text(array(operation.get("tags")).first()) {
+                Some(tag) => {
+                    let index = match collection.folders.iter().position(|f| f.name == tag) {
+                        Some(index) => index,
+                        None => {
+                            collection.folders.push(Folder::new(&tag));
+                            collection.folders.len() - 1
+                        }
+                    };
+                    collection.folders[index].requests.push(saved);
+                }
+                None => collection.requests.push(saved),
+            }
+        }
+    }
+    collection
+        .folders
+        .retain(|folder| !folder.requests.is_empty());
+
+    Ok(Imported {
+        environments: import.environments(&title),
+        collections: vec![collection],
+        secret_names: BTreeSet::new(),
+        report: import.finish(),
+    })
+}
+
+struct OpenApiImport<'a> {
+    spec: &'a Value,
+    swagger: bool,
+    report: Vec<String>,
+    /// Path parameters and credentials, added to every environment.
+    variables: Map<String, Value>,
+    external_refs: BTreeSet<String>,
+    /// Schemas the example being generated is nested in.
+    schema_refs: Vec<String>,
+}
+
+impl<'a> OpenApiImport<'a> {
+    fn finish(mut self) -> Vec<String> {
+        if !self.external_refs.is_empty() {
+            self.report.push(format!(
+                "References to other files were left out: {}",
+                self.external_refs
+                    .into_iter()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        self.report
+    }
+
+    // Follows `$ref`s within the spec, references to other files resolve to null.
+    fn resolve(&mut self, value: &Value) -> Value {
+        let mut value = value.clone();
+        // A chain of references ends within a few steps, unless it's a cycle
+        for _ in 0..MAX_EXAMPLE_DEPTH {
+            let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+                return value;
+            };
+            match reference.strip_prefix('#') {
+                Some(pointer) => {
+                    value = self.spec.pointer(pointer).cloned().unwrap_or(Value::Null);
+                }
+                None => {
+                    self.external_refs.insert(reference.to_owned());
+                    return Value::Null;
+                }
+            }
+        }
+        Value::Null
+    }
+
+    fn operation(
+        &mut self,
+        path: &str,
+        method: &str,
+        item: &Value,
+        operation: &Value,
+    ) -> ImportedRequest {
+        let name = text(operation.get("summary"))
+            .or_else(|| text(operation.get("operationId")))
+            .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path));
+        let context = format!("{} {}", method.to_uppercase(), path);
+        let mut request = ImportedRequest {
+            name,
+            method: method.to_owned(),
+            url: format!("{{base_url}}{}", path),
+            ..Default::default()
+        };
+
+        // Operation parameters override the ones of the path with the same name
+        let mut parameters: Vec<Value> = vec![];
+        for parameter in array(item.get("parameters"))
+            .iter()
+            .chain(array(operation.get("parameters")))
+        {
+            let parameter = self.resolve(parameter);
+            let key = (text(parameter.get("name")), text(parameter.get("in")));
+            parameters
+                .retain(|existing| (text(existing.get("name")), text(existing.get("in"))) != key);
+            parameters.push(parameter);
+        }
+
+        let mut query = vec![];
+        let mut form = Map::new();
+        for parameter in &parameters {
+            let name = text(parameter.get("name")).unwrap_or_default();
+            let value = self.parameter_example(parameter);
+            let required = parameter.get("required") == Some(&Value::Bool(true));
+            match text(parameter.get("in")).unwrap_or_default().as_str() {
+                "path" => {
+                    self.variables
+                        .entry(name)
+                        .or_insert(Value::String(value.unwrap_or_default()));
+                }
+                "query" if required || value.is_some() => {
+                    query.push((name, value.unwrap_or_default()));
+                }
+                "header" => request.headers.push((name, value.unwrap_or_default())),
+                "cookie" => self.report.push(format!(
+                    "{}: cookie parameter {} was skipped",
+                    context, name
+                )),
+                "body" => {
+                    let schema = parameter.get("schema").cloned().unwrap_or(Value::Null);
+                    let example = self.example(&schema, 0);
+                    request.body = serde_json::to_string_pretty(&example).unwrap_or_default();
+                    request
+                        .set_content_type(&self.swagger_media_type(operation, "application/json"));
+                }
+                "formData" => {
+                    if text(parameter.get("type")).as_deref() == Some("file") {
+                        self.report
+                            .push(format!("{}: file field {} was skipped", context, name));
+                    } else if required || value.is_some() {
+                        form.insert(name, Value::String(value.unwrap_or_default()));
+                    }
+                }
+                _ => (),
+            }
+        }
+        if !query.is_empty() {
+            let query: Vec<String> = query
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            request.url = format!("{}?{}", request.url, query.join("&"));
+        }
+        if !form.is_empty() {
+            let media_type =
+                self.swagger_media_type(operation, "application/x-www-form-urlencoded");
+            self.form_body(&Value::Object(form), &media_type, &mut request);
+        }
+        if let Some(body) = operation.get("requestBody") {
+            let body = self.resolve(body);
+            self.request_body(&body, &mut request, &context);
+        }
+        request.auth = self.auth(operation, &context);
+        request
+    }
+
+    fn swagger_media_type(&self, operation: &Value, fallback: &str) -> String {
+        let consumes = operation
+            .get("consumes")
+            .or_else(|| self.spec.get("consumes"));
+        let consumes: Vec<String> = array(consumes)
+            .iter()
+            .filter_map(|media| text(Some(media)))
+            .collect();
+        let preferred = match fallback {
+            "application/json" => ["application/json"].as_slice(),
+            _ => ["application/x-www-form-urlencoded", "multipart/form-data"].as_slice(),
+        };
+        preferred
+            .iter()
+            .find(|media| consumes.iter().any(|consumed| consumed == *media))
+            .map(|media| media.to_string())
+            .or_else(|| consumes.first().cloned())
+            .unwrap_or_else(|| fallback.to_owned())
+    }
+
+    fn parameter_example(&mut self, parameter: &Value) -> Option<String> {
+        let schema = match self.swagger {
+            true => parameter.clone(),
+            false => parameter.get("schema").cloned().unwrap_or(Value::Null),
+        };
+        let example = parameter
+            .get("example")
+            .cloned()
+            .or_else(|| {
+                let example = self.resolve(&first_example(parameter.get("examples"))?);
+                example.get("value").cloned()
+            })
+            .or_else(|| {
+                let schema = self.resolve(&schema);
+                schema
+                    .get("example")
+                    .or_else(|| schema.get("default"))
+                    .or_else(|| array(schema.get("enum")).first())
+                    .cloned()
+            })?;
+        match example {
+            Value::String(text) => Some(text),
+            Value::Null => None,
+            example => Some(example.to_string()),
+        }
+    }
+
+    // OpenAPI 3 bodies, json is preferred when the operation accepts several types.
+    fn request_body(&mut self, body: &Value, request: &mut ImportedRequest, context: &str) {
+        let Some(content) = body.get("content").and_then(Value::as_object) else {
+            return;
+        };
+        let media_type = content
+            .keys()
+            .find(|media| *media == "application/json")
+            .or_else(|| content.keys().find(|media| media.ends_with("+json")))
+            .or_else(|| content.keys().next())
+            .cloned();
+        let Some(media_type) = media_type else {
+            return;
+        };
+        let media = &content[&media_type];
+        let schema = media.get("schema").cloned().unwrap_or(Value::Null);
+        let example = media
+            .get("example")
+            .cloned()
+            .or_else(|| first_example(media.get("examples")).map(|example| self.resolve(&example)))
+            .map(|example| example.get("value").cloned().unwrap_or(example))
+            .unwrap_or_else(|| self.example(&schema, 0));
+
+        if media_type.contains("json") {
+            request.body = serde_json::to_string_pretty(&example).unwrap_or_default();
+            request.set_content_type(&media_type);
+        } else if media_type == "application/x-www-form-urlencoded"
+            || media_type == "multipart/form-data"
+        {
+            self.form_body(&example, &media_type, request);
+        } else if let Value::String(example) = example {
+            request.body = example;
+            request.set_content_type(&media_type);
+        } else {
+            self.report.push(format!(
+                "{}: no example body could be made for {}",
+                context, media_type
+            ));
+            request.set_content_type(&media_type);
+        }
+    }
+
+    fn form_body(&mut self, example: &Value, media_type: &str, request: &mut ImportedRequest) {
+        let fields: Vec<(String, String)> = example
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::String(text) => text.clone(),
+                    value => value.to_string(),
+                };
+                (key.clone(), value)
+            })
+            .collect();
+        if media_type == "multipart/form-data" {
+            let parts: Vec<(String, String, String)> = fields
+                .into_iter()
+                .map(|(key, value)| (key, "".to_owned(), value))
+                .collect();
+            request.body = multipart_body(&parts);
+            request.set_content_type(&multipart_content_type());
+        } else {
+            request.body = form_urlencoded(&fields);
+            request.set_content_type(media_type);
+        }
+    }
+
+    // An example value for a schema: its example, default or first enum value,
+    // otherwise a placeholder of the right type.
+    fn example(&mut self, schema: &Value, depth: usize) -> Value {
+        if depth > MAX_EXAMPLE_DEPTH {
+            return Value::Null;
+        }
+        // Recursive schemas stop where they refer to themselves
+        let reference = text(schema.get("$ref"));
+        if let Some(reference) = &reference {
+            if self.schema_refs.contains(reference) {
+                return Value::Null;
+            }
+            self.schema_refs.push(reference.clone());
+        }
+        let example = self.schema_example(schema, depth);
+        if reference.is_some() {
+            self.schema_refs.pop();
+        }
+        example
+    }
+
+    fn schema_example(&mut self, schema: &Value, depth: usize) -> Value {
+        let schema = self.resolve(schema);
+        for key in ["example", "default", "const"] {
+            if let Some(example) = schema.get(key) {
+                return example.clone();
+            }
+        }
+        if let Some(first) = array(schema.get("enum")).first() {
+            return first.clone();
+        }
+        if let Some(all_of) = schema.get("allOf") {
+            let mut merged = Map::new();
+            for part in array(Some(all_of)) {
+                if let Value::Object(part) = self.example(part, depth + 1) {
+                    merged.extend(part);
+                }
+            }
+            return Value::Object(merged);
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(first) = array(schema.get(key)).first() {
+                return self.example(first, depth + 1);
+            }
+        }
+
+        let schema_type = match schema.get("type") {
+            // 3.1 allows a list of types, such as ["string", "null"]
+            Some(Value::Array(types)) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|schema_type| *schema_type != "null")
+                .unwrap_or("null")
+                .to_owned(),
+            schema_type => {
+                text(schema_type).unwrap_or_else(|| match schema.get("properties").is_some() {
+                    true => "object".to_owned(),
+                    false => "".to_owned(),
+                })
+            }
+        };
+        match schema_type.as_str() {
+            "object" => {
+                let mut object = Map::new();
+                for (name, property) in schema
+                    .get("properties")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flatten()
+                {
+                    let resolved = self.resolve(property);
+                    if resolved.get("readOnly") == Some(&Value::Bool(true)) {
+                        continue;
+                    }
+                    let example = self.example(property, depth + 1);
+                    let required = array(schema.get("required"))
+                        .iter()
+                        .any(|required| required.as_str() == Some(name));
+                    if !example.is_null() || required {
+                        object.insert(name.clone(), example);
+                    }
+                }
+                Value::Object(object)
+            }
+            "array" => {
+                let items = schema.get("items").cloned().unwrap_or(Value::Null);
+                json!([self.example(&items, depth + 1)])
+            }
+            "integer" | "number" => json!(0),
+            "boolean" => json!(true),
+            "string" => {
+                let example = match text(schema.get("format")).unwrap_or_default().as_str() {
+                    "date-time" => "2024-01-01T00:00:00Z",
+                    "date" => "2024-01-01",
+                    "email" => "user@example.com",
+                    "uuid" => "00000000-0000-0000-0000-000000000000",
+                    "uri" | "url" => "https://example.com",
+                    _ => "string",
+                };
+                json!(example)
+            }
+            _ => Value::Null,
+        }
+    }
+
+    // The first scheme of the first security requirement, credentials are left as
+    // variables of the environments.
+    fn auth(&mut self, operation: &Value, context: &str) -> Auth {
+        let mut auth = Auth::default();
+        let requirements = operation
+            .get("security")
+            .or_else(|| self.spec.get("security"));
+        let Some(scheme_name) = array(requirements)
+            .first()
+            .and_then(Value::as_object)
+            .and_then(|requirement| requirement.keys().next().cloned())
+        else {
+            return auth;
+        };
+        let schemes = match self.swagger {
+            true => "/securityDefinitions",
+            false => "/components/securitySchemes",
+        };
+        let pointer = format!(
+            "{}/{}",
+            schemes,
+            scheme_name.replace('~', "~0").replace('/', "~1")
+        );
+        let scheme = self.spec.pointer(&pointer).cloned().unwrap_or(Value::Null);
+        let scheme = self.resolve(&scheme);
+        let scheme_type = text(scheme.get("type")).unwrap_or_default();
+        let http_scheme = text(scheme.get("scheme"))
+            .unwrap_or_default()
+            .to_lowercase();
+        match (scheme_type.as_str(), http_scheme.as_str()) {
+            ("basic", _) | ("http", "basic") => {
+                auth.mode = AuthMode::Basic;
+                auth.basic.username = self.credential("username");
+                auth.basic.password = self.credential("password");
+            }
+            ("http", "bearer") => {
+                auth.mode = AuthMode::Bearer;
+                auth.bearer.token = self.credential("token");
+            }
+            ("http", "digest") => {
+                auth.mode = AuthMode::Digest;
+                auth.digest.username = self.credential("username");
+                auth.digest.password = self.credential("password");
+            }
+            ("apiKey", _) => {
+                auth.mode = AuthMode::ApiKey;
+                auth.api_key.name = text(scheme.get("name")).unwrap_or_default();
+                auth.api_key.value = self.credential("api_key");
+                match text(scheme.get("in")).unwrap_or_default().as_str() {
+                    "query" => auth.api_key.location = ApiKeyLocation::QueryParam,
+                    "cookie" => self.report.push(format!(
+                        "{}: API key cookie {} is sent as a header",
+                        context, auth.api_key.name
+                    )),
+                    _ => (),
+                }
+            }
+            ("oauth2", _) => {
+                auth.mode = AuthMode::OAuth2;
+                let oauth2 = &mut auth.oauth2;
+                // Swagger 2 has a single flow, OpenAPI 3 a map of them
+                let (flow_name, flow) = match self.swagger {
+                    true => (text(scheme.get("flow")).unwrap_or_default(), scheme.clone()),
+                    false => scheme
+                        .get("flows")
+                        .and_then(Value::as_object)
+                        .and_then(|flows| flows.iter().next())
+                        .map(|(name, flow)| (name.clone(), flow.clone()))
+                        .unwrap_or_default(),
+                };
+                oauth2.grant = match flow_name.as_str() {
+                    "clientCredentials" | "application" => OAuth2Grant::ClientCredentials,
+                    "password" => OAuth2Grant::Password,
+                    "authorizationCode" | "accessCode" => OAuth2Grant::AuthorizationCode,
+                    other => {
+                        self.report.push(format!(
+                            "{}: OAuth 2.0 flow {} isn't supported, imported as authorization code",
+                            context, other
+                        ));
+                        OAuth2Grant::AuthorizationCode
+                    }
+                };
+                oauth2.token_url = text(flow.get("tokenUrl")).unwrap_or_default();
+                oauth2.authorization_url = text(flow.get("authorizationUrl")).unwrap_or_default();
+                let scopes: Vec<String> = array(requirements)
+                    .first()
+                    .and_then(|requirement| requirement.get(&scheme_name))
+                    .map(|scopes| {
+                        array(Some(scopes))
+                            .iter()
+                            .filter_map(|s| text(Some(s)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                oauth2.scope = scopes.join(" ");
+                oauth2.client_id = self.credential("client_id");
+                oauth2.client_secret = self.credential("client_secret");
+            }
+            _ => self.report.push(format!(
+                "{}: security scheme {} isn't supported, the request has no auth",
+                context, scheme_name
+            )),
+        }
+        auth
+    }
+
+    fn credential(&mut self, name: &str) -> String {
+        self.variables
+            .entry(name.to_owned())
+            .or_insert(Value::String("".to_owned()));
+        format!("{{{}}}", name)
+    }
+
+    // An environment per server, with the server variables at their default.
+    fn environments(&mut self, title: &str) -> Vec<ImportedEnvironment> {
+        let mut servers: Vec<(String, Map<String, Value>)> = vec![];
+        if self.swagger {
+            if let Some(host) = text(self.spec.get("host")) {
+                let scheme = text(array(self.spec.get("schemes")).first())
+                    .unwrap_or_else(|| "https".to_owned());
+                let base_path = text(self.spec.get("basePath")).unwrap_or_default();
+                let url = format!("{}://{}{}", scheme, host, base_path);
+                servers.push((url, Map::new()));
+            }
+        } else {
+            for server in array(self.spec.get("servers")) {
+                let url = text(server.get("url")).unwrap_or_default();
+                let description = text(server.get("description")).unwrap_or(url.clone());
+                let mut variables = Map::new();
+                variables.insert("base_url".to_owned(), Value::String(url));
+                for (name, variable) in server
+                    .get("variables")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flatten()
+                {
+                    let default = text(variable.get("default")).unwrap_or_default();
+                    variables.insert(name.clone(), Value::String(default));
+                }
+                servers.push((description, variables));
+            }
+        }
+        if servers.is_empty() {
+            self.report
+                .push("The spec has no servers, set base_url in the environment".to_owned());
+            servers.push(("".to_owned(), Map::new()));
+        }
+
+        let single = servers.len() == 1;
+        servers
+            .into_iter()
+            .map(|(name, mut variables)| {
+                if self.swagger {
+                    variables.insert("base_url".to_owned(), Value::String(name.clone()));
+                }
+                variables
+                    .entry("base_url".to_owned())
+                    .or_insert(Value::String("".to_owned()));
+                for (key, value) in &self.variables {
+                    variables.entry(key.clone()).or_insert(value.clone());
+                }
+                let name = match single {
+                    true => title.to_owned(),
+                    false => format!("{} / {}", title, name),
+                };
+                ImportedEnvironment { name, variables }
+            })
+            .collect()
+    }
+}
+
+// The value of the first entry of an `examples` map.
+fn first_example(examples: Option<&Value>) -> Option<Value> {
+    examples?.as_object()?.values().next().cloned()
+}
+
+fn array(value: Option<&Value>) -> &[Value] {
+    match value {
+        Some(Value::Array(values)) => values,
+        _ => &[],
+    }
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(text) => Some(text.clone()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::import::headers;
+    use crate::app::request_method::RequestMethod;
+
+    fn fixture() -> Imported {
+        let contents = include_str!("../../tests/fixtures/openapi_spec.json");
+        import_openapi(&serde_json::from_str(contents).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn imports_a_folder_per_tag() {
+        let imported = fixture();
+        assert_eq!(imported.request_count(), 5);
+        let collection = &imported.collections[0];
+        assert_eq!(collection.name, "Pet Store");
+        // Tags without operations are left out
+        let folders: Vec<&str> = collection.folders.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(folders, vec!["pets", "store"]);
+        let pets: Vec<&str> = collection.folders[0]
+            .requests
+            .iter()
+            .map(|request| request.name.as_str())
+            .collect();
+        assert_eq!(pets, vec!["List pets", "createPet", "Delete pet"]);
+
+        let health = &collection.requests[0];
+        assert_eq!(health.name, "GET /health");
+        assert_eq!(health.auth.mode, AuthMode::None);
+    }
+
+    #[test]
+    fn builds_requests_from_parameters_and_schemas() {
+        let imported = fixture();
+        let pets = &imported.collections[0].folders[0].requests;
+
+        let list = &pets[0];
+        assert_eq!(list.url, "{base_url}/pets?limit=20");
+        assert_eq!(headers(list), vec![("X-Request-Id", "abc")]);
+        assert_eq!(list.auth.mode, AuthMode::Bearer);
+        assert_eq!(list.auth.bearer.token, "{token}");
+
+        // Json is preferred, read only and recursive properties are left out
+        let create = &pets[1];
+        assert_eq!(create.method, RequestMethod::POST);
+        assert_eq!(
+            serde_json::from_str::<Value>(&create.request_body).unwrap(),
+            json!({
+                "name": "string",
+                "tag": "dog",
+                "owner": {"email": "user@example.com", "born": "2024-01-01"}
+            })
+        );
+        assert_eq!(headers(create), vec![("Content-Type", "application/json")]);
+
+        let delete = &pets[2];
+        assert_eq!(delete.method, RequestMethod::DELETE);
+        assert_eq!(delete.url, "{base_url}/pets/{petId}");
+        assert_eq!(delete.auth.mode, AuthMode::ApiKey);
+        assert_eq!(delete.auth.api_key.name, "X-Api-Key");
+        assert_eq!(delete.auth.api_key.value, "{api_key}");
+
+        let upload = &imported.collections[0].folders[1].requests[0];
+        assert_eq!(upload.auth.mode, AuthMode::OAuth2);
+        assert_eq!(upload.auth.oauth2.grant, OAuth2Grant::AuthorizationCode);
+        assert_eq!(
+            upload.auth.oauth2.authorization_url,
+            "https://auth.example.com/authorize"
+        );
+        assert_eq!(upload.auth.oauth2.scope, "write:pets read:pets");
+        assert_eq!(upload.auth.oauth2.client_id, "{client_id}");
+    }
+
+    #[test]
+    fn servers_become_environments() {
+        let imported = fixture();
+        let names: Vec<&str> = imported
+            .environments
+            .iter()
+            .map(|environment| environment.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Pet Store / Production", "Pet Store / Staging"]);
+        assert_eq!(
+            Value::Object(imported.environments[1].variables.clone()),
+            json!({
+                "base_url": "https://{region}.staging.example.com",
+                "region": "eu",
+                "token": "",
+                "petId": "7",
+                "api_key": "",
+                "client_id": "",
+                "client_secret": ""
+            })
+        );
+    }
+
+    #[test]
+    fn reports_what_wasnt_imported() {
+        assert_eq!(
+            fixture().report,
+            vec![
+                "GET /pets: cookie parameter session was skipped",
+                "DELETE /pets/{petId}: API key cookie X-Api-Key is sent as a header",
+                "POST /store/upload: OAuth 2.0 flow implicit isn't supported, imported as authorization code",
+                "References to other files were left out: photos.yaml#/Photo",
+            ]
+        );
+    }
+
+    #[test]
+    fn imports_swagger_2_forms() {
+        let spec = json!({
+            "swagger": "2.0",
+            "info": {"title": "Legacy"},
+            "host": "legacy.example.com",
+            "basePath": "/api",
+            "schemes": ["http"],
+            "consumes": ["application/x-www-form-urlencoded"],
+            "securityDefinitions": {"basic": {"type": "basic"}},
+            "security": [{"basic": []}],
+            "paths": {"/login": {"post": {"parameters": [
+                {"name": "user", "in": "formData", "type": "string", "required": true},
+                {"name": "remember", "in": "formData", "type": "boolean", "default": true},
+                {"name": "avatar", "in": "formData", "type": "file"}
+            ]}}}
+        });
+        let imported = import_openapi(&spec).unwrap();
+        let login = &imported.collections[0].requests[0];
+        assert_eq!(login.name, "POST /login");
+        assert_eq!(login.request_body, "user=&remember=true");
+        assert_eq!(
+            headers(login),
+            vec![("Content-Type", "application/x-www-form-urlencoded")]
+        );
+        assert_eq!(login.auth.mode, AuthMode::Basic);
+        assert_eq!(login.auth.basic.username, "{username}");
+
+        let environment = &imported.environments[0];
+        assert_eq!(environment.name, "Legacy");
+        assert_eq!(
+            environment.variables["base_url"],
+            json!("http://legacy.example.com/api")
+        );
+        assert_eq!(
+            imported.report,
+            vec!["POST /login: file field avatar was skipped"]
+        );
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        assert_eq!(
+            import_openapi(&json!({"swagger": "1.2"})).unwrap_err(),
+            "Only OpenAPI 3 and Swagger 2 specs can be imported"
+        );
+    }
+}
@@ -0,0 +1,702 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+
+use super::auth::{ApiKeyLocation, Auth, AuthMode};
+use super::collections::Folder;
+use super::digest_auth::DigestAlgorithm;
+use super::import::{
+    form_urlencoded, multipart_body, multipart_content_type, Imported, ImportedEnvironment,
+    ImportedRequest,
+};
+use super::jwt::JwtAlgorithm;
+use super::oauth2::{ClientAuthentication, OAuth2Grant};
+
+// Imports a Postman collection (v2.0 or v2.1) or a Postman environment export.
+pub fn import_postman(value: &Value) -> Result<Imported, String> {
+    if value.get("_postman_variable_scope").is_some() || value.get("values").is_some() {
+        return Ok(import_environment(value));
+    }
+    let Some(info) = value.get("info") else {
+        return Err("Not a Postman or Insomnia export or an OpenAPI spec".to_owned());
+    };
+    let schema = info.get("schema").and_then(Value::as_str).unwrap_or("");
+    if value.get("item").is_none() || schema.contains("/v1.") {
+        return Err("Only Postman collections v2.0 and v2.1 can be imported".to_owned());
+    }
+
+    let mut import = PostmanImport::default();
+    let name = text(info.get("name")).unwrap_or_else(|| "Postman collection".to_owned());
+    let auth = import.auth(value.get("auth"), &name).unwrap_or_default();
+    import.scripts(value, &name);
+    let collection = import.folder(value, name.clone(), &auth);
+
+    let mut imported = Imported::default();
+    let variables = import.variables(value.get("variable"));
+    if !variables.is_empty() {
+        imported.environments.push(ImportedEnvironment {
+            name: format!("{} variables", name),
+            variables,
+        });
+    }
+    imported.collections.push(collection);
+    imported.report = import.finish();
+    Ok(imported)
+}
+
+fn import_environment(value: &Value) -> Imported {
+    let mut import = PostmanImport::default();
+    let mut imported = Imported::default();
+    let name = text(value.get("name")).unwrap_or_else(|| "Postman environment".to_owned());
+    let mut variables = Map::new();
+    for entry in array(value.get("values")) {
+        let Some(key) = text(entry.get("key")) else {
+            continue;
+        };
+        if entry.get("enabled") == Some(&Value::Bool(false)) {
+            import
+                .report
+                .push(format!("{}: disabled variable {} was skipped", name, key));
+            continue;
+        }
+        if entry.get("type").and_then(Value::as_str) == Some("secret") {
+            imported.secret_names.insert(key.clone());
+        }
+        let value = import.convert(&text(entry.get("value")).unwrap_or_default());
+        variables.insert(key, Value::String(value));
+    }
+    if !imported.secret_names.is_empty() {
+        import.report.push(format!(
+            "{}: secret values were written to the environment file, move them to the secret vault: {}",
+            name,
+            imported.secret_names.iter().cloned().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    imported
+        .environments
+        .push(ImportedEnvironment { name, variables });
+    imported.report = import.finish();
+    imported
+}
+
+#[derive(Default)]
+struct PostmanImport {
+    report: Vec<String>,
+    /// `{{$guid}}` and friends, which have no equivalent.
+    dynamic_variables: BTreeSet<String>,
+}
+
+impl PostmanImport {
+    fn finish(mut self) -> Vec<String> {
+        if !self.dynamic_variables.is_empty() {
+            self.report.push(format!(
+                "Postman dynamic variables have no equivalent and must be defined in an environment: {}",
+                self.dynamic_variables.into_iter().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        self.report
+    }
+
+    // `{{name}}` becomes `{name}`.
+    fn convert(&mut self, text: &str) -> String {
+        static PATTERN: OnceLock<Regex> = OnceLock::new();
+        PATTERN
+            .get_or_init(|| Regex::new(r"\{\{\s*([^{}]+?)\s*\}\}").unwrap())
+            .replace_all(text, |caps: &Captures<'_>| {
+                let name = &caps[1];
+                if name.starts_with('$') {
+                    self.dynamic_variables.insert(format!("{{{{{}}}}}", name));
+                }
+                format!("{{{}}}", name)
+            })
+            .into_owned()
+    }
+
+    fn variables(&mut self, variables: Option<&Value>) -> Map<String, Value> {
+        let mut map = Map::new();
+        for variable in array(variables) {
+            if variable.get("disabled") == Some(&Value::Bool(true)) {
+                continue;
+            }
+            if let Some(key) = text(variable.get("key")) {
+                let value = self.convert(&text(variable.get("value")).unwrap_or_default());
+                map.insert(key, Value::String(value));
+            }
+        }
+        map
+    }
+
+    fn folder(&mut self, value: &Value, name: String, auth: &Auth) -> Folder {
+        let mut folder = Folder::new(&name);
+        for item in array(value.get("item")) {
+            let item_name = text(item.get("name")).unwrap_or_default();
+            let context = format!("{} / {}", name, item_name);
+            self.scripts(item, &context);
+            let item_auth = self
+                .auth(item_auth(item), &context)
+                .unwrap_or_else(|| auth.clone());
+            if item.get("item").is_some() {
+                let mut child = self.folder(item, context, &item_auth);
+                child.name = item_name;
+                folder.folders.push(child);
+            } else {
+                let request = self.request(item, item_name, &context, item_auth);
+                folder
+                    .requests
+                    .push(request.into_saved(&context, &mut self.report));
+            }
+        }
+        folder
+    }
+
+    fn request(
+        &mut self,
+        item: &Value,
+        name: String,
+        context: &str,
+        auth: Auth,
+    ) -> ImportedRequest {
+        let mut request = ImportedRequest {
+            name,
+            auth,
+            method: "GET".to_owned(),
+            ..Default::default()
+        };
+        let definition = match item.get("request") {
+            // A request can be just its url
+            Some(Value::String(url)) => {
+                request.url = self.convert(url);
+                return request;
+            }
+            Some(definition) => definition,
+            None => return request,
+        };
+        if let Some(method) = text(definition.get("method")) {
+            request.method = method;
+        }
+        request.url = self.url(definition.get("url"), context);
+        request.headers = self.headers(definition.get("header"), context);
+        if let Some(body) = definition.get("body") {
+            self.body(body, &mut request, context);
+        }
+        request
+    }
+
+    fn url(&mut self, url: Option<&Value>, context: &str) -> String {
+        let url = match url {
+            Some(Value::String(raw)) => return self.convert(raw),
+            Some(url) => url,
+            None => return "".to_owned(),
+        };
+        let mut raw = match text(url.get("raw")) {
+            Some(raw) => raw,
+            None => {
+                let protocol = text(url.get("protocol"))
+                    .map(|protocol| format!("{}://", protocol))
+                    .unwrap_or_default();
+                let host = join(url.get("host"), ".");
+                let port = text(url.get("port"))
+                    .map(|port| format!(":{}", port))
+                    .unwrap_or_default();
+                let path = join(url.get("path"), "/");
+                let query = array(url.get("query"))
+                    .iter()
+                    .filter(|param| param.get("disabled") != Some(&Value::Bool(true)))
+                    .map(|param| {
+                        let key = text(param.get("key")).unwrap_or_default();
+                        match text(param.get("value")) {
+                            Some(value) => format!("{}={}", key, value),
+                            None => key,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("&");
+                let mut raw = format!("{}{}{}", protocol, host, port);
+                if !path.is_empty() {
+                    raw = format!("{}/{}", raw, path);
+                }
+                if !query.is_empty() {
+                    raw = format!("{}?{}", raw, query);
+                }
+                raw
+            }
+        };
+        // Path variables like `:id` are filled in, the ones without a value
+        // become variables of the environment
+        let mut replacements = BTreeMap::new();
+        for variable in array(url.get("variable")) {
+            let Some(key) = text(variable.get("key")) else {
+                continue;
+            };
+            let replacement = match text(variable.get("value")) {
+                Some(value) if !value.is_empty() => value,
+                _ => {
+                    self.report.push(format!(
+                        "{}: path variable :{} has no value, it's now {{{}}}",
+                        context, key, key
+                    ));
+                    format!("{{{}}}", key)
+                }
+            };
+            replacements.insert(key, replacement);
+        }
+        if !replacements.is_empty() {
+            static PATH_VARIABLE: OnceLock<Regex> = OnceLock::new();
+            raw = PATH_VARIABLE
+                .get_or_init(|| Regex::new(r":([\w-]+)").unwrap())
+                .replace_all(&raw, |caps: &Captures<'_>| {
+                    match replacements.get(&caps[1]) {
+                        Some(replacement) => replacement.clone(),
+                        None => caps[0].to_owned(),
+                    }
+                })
+                .into_owned();
+        }
+        self.convert(&raw)
+    }
+
+    fn headers(&mut self, headers: Option<&Value>, context: &str) -> Vec<(String, String)> {
+        let mut converted = vec![];
+        match headers {
+            // v2.0 also allows the raw header block
+            Some(Value::String(block)) => {
+                for line in block.lines() {
+                    if let Some((key, value)) = line.split_once(':') {
+                        converted.push((self.convert(key.trim()), self.convert(value.trim())));
+                    }
+                }
+            }
+            headers => {
+                for header in array(headers) {
+                    let key = text(header.get("key")).unwrap_or_default();
+                    if header.get("disabled") == Some(&Value::Bool(true)) {
+                        self.report
+                            .push(format!("{}: disabled header {} was skipped", context, key));
+                        continue;
+                    }
+                    let value = text(header.get("value")).unwrap_or_default();
+                    converted.push((self.convert(&key), self.convert(&value)));
+                }
+            }
+        }
+        converted
+    }
+
+    fn body(&mut self, body: &Value, request: &mut ImportedRequest, context: &str) {
+        let mode = body.get("mode").and_then(Value::as_str).unwrap_or("");
+        match mode {
+            "raw" => {
+                request.body = self.convert(&text(body.get("raw")).unwrap_or_default());
+                let language = body
+                    .pointer("/options/raw/language")
+                    .and_then(Value::as_str)
+                    .unwrap_or("text");
+                let content_type = match language {
+                    "json" => "application/json",
+                    "xml" => "application/xml",
+                    "html" => "text/html",
+                    "javascript" => "application/javascript",
+                    _ => "text/plain",
+                };
+                if !request.body.is_empty() {
+                    request.set_content_type(content_type);
+                }
+            }
+            "urlencoded" => {
+                let fields = self.form_fields(body.get("urlencoded"), context);
+                request.body = form_urlencoded(&fields);
+                request.set_content_type("application/x-www-form-urlencoded");
+            }
+            "formdata" => {
+                let mut parts = vec![];
+                for field in array(body.get("formdata")) {
+                    let key = text(field.get("key")).unwrap_or_default();
+                    if field.get("disabled") == Some(&Value::Bool(true)) {
+                        self.report.push(format!(
+                            "{}: disabled form field {} was skipped",
+                            context, key
+                        ));
+                        continue;
+                    }
+                    if field.get("type").and_then(Value::as_str) == Some("file") {
+                        self.report.push(format!(
+                            "{}: file field {} of the form was skipped, file uploads aren't supported",
+                            context, key
+                        ));
+                        continue;
+                    }
+                    let content_type = text(field.get("contentType")).unwrap_or_default();
+                    let value = text(field.get("value")).unwrap_or_default();
+                    parts.push((self.convert(&key), content_type, self.convert(&value)));
+                }
+                request.body = multipart_body(&parts);
+                request.set_content_type(&multipart_content_type());
+            }
+            "graphql" => {
+                let query = self.convert(
+                    body.pointer("/graphql/query")
+                        .and_then(Value::as_str)
+                        .unwrap_or(""),
+                );
+                let variables = body
+                    .pointer("/graphql/variables")
+                    .and_then(Value::as_str)
+                    .filter(|variables| !variables.trim().is_empty())
+                    .and_then(|variables| serde_json::from_str(variables).ok())
+                    .unwrap_or(Value::Object(Map::new()));
+                let graphql = serde_json::json!({ "query": query, "variables": variables });
+                request.body =
+                    self.convert(&serde_json::to_string_pretty(&graphql).unwrap_or_default());
+                request.set_content_type("application/json");
+            }
+            "file" => {
+                let source = text(body.pointer("/file/src")).unwrap_or_default();
+                self.report.push(format!(
+                    "{}: file body {} was skipped, file uploads aren't supported",
+                    context, source
+                ));
+            }
+            "" => (),
+            other => self
+                .report
+                .push(format!("{}: body mode {} isn't supported", context, other)),
+        }
+    }
+
+    fn form_fields(&mut self, fields: Option<&Value>, context: &str) -> Vec<(String, String)> {
+        let mut converted = vec![];
+        for field in array(fields) {
+            let key = text(field.get("key")).unwrap_or_default();
+            if field.get("disabled") == Some(&Value::Bool(true)) {
+                self.report.push(format!(
+                    "{}: disabled form field {} was skipped",
+                    context, key
+                ));
+                continue;
+            }
+            let value = text(field.get("value")).unwrap_or_default();
+            converted.push((self.convert(&key), self.convert(&value)));
+        }
+        converted
+    }
+
+    fn scripts(&mut self, item: &Value, context: &str) {
+        for event in array(item.get("event")) {
+            let exec = match event.pointer("/script/exec") {
+                Some(Value::Array(lines)) => lines
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                Some(Value::String(script)) => script.clone(),
+                _ => continue,
+            };
+            if exec.trim().is_empty() {
+                continue;
+            }
+            let kind = match event.get("listen").and_then(Value::as_str) {
+                Some("prerequest") => "pre-request script",
+                Some("test") => "test script",
+                _ => "script",
+            };
+            self.report
+                .push(format!("{}: {} wasn't imported", context, kind));
+        }
+    }
+
+    // None when the auth is inherited from the parent.
+    fn auth(&mut self, auth: Option<&Value>, context: &str) -> Option<Auth> {
+        let auth = auth.filter(|auth| !auth.is_null())?;
+        let kind = auth.get("type").and_then(Value::as_str).unwrap_or("noauth");
+        let params: Map<String, Value> = auth_params(auth, kind)
+            .into_iter()
+            .map(|(key, value)| {
+                let value = self.convert(&text(Some(&value)).unwrap_or_default());
+                (key, Value::String(value))
+            })
+            .collect();
+        let param = |key: &str| text(params.get(key)).unwrap_or_default();
+        let mut converted = Auth::default();
+        match kind {
+            "inherit" => return None,
+            "noauth" => (),
+            "basic" => {
+                converted.mode = AuthMode::Basic;
+                converted.basic.username = param("username");
+                converted.basic.password = param("password");
+            }
+            "bearer" => {
+                converted.mode = AuthMode::Bearer;
+                converted.bearer.token = param("token");
+            }
+            "apikey" => {
+                converted.mode = AuthMode::ApiKey;
+                converted.api_key.name = param("key");
+                converted.api_key.value = param("value");
+                if param("in") == "query" {
+                    converted.api_key.location = ApiKeyLocation::QueryParam;
+                }
+            }
+            "digest" => {
+                converted.mode = AuthMode::Digest;
+                converted.digest.username = param("username");
+                converted.digest.password = param("password");
+                let algorithm = param("algorithm").to_uppercase();
+                if algorithm.starts_with("SHA-256") {
+                    converted.digest.algorithm = DigestAlgorithm::Sha256;
+                } else if !algorithm.is_empty() && !algorithm.starts_with("MD5") {
+                    self.report.push(format!(
+                        "{}: digest algorithm {} isn't supported, MD5 is used",
+                        context, algorithm
+                    ));
+                }
+            }
+            "awsv4" => {
+                converted.mode = AuthMode::AwsSigV4;
+                converted.aws.access_key = param("accessKey");
+                converted.aws.secret_key = param("secretKey");
+                converted.aws.session_token = param("sessionToken");
+                converted.aws.region = param("region");
+                converted.aws.service = param("service");
+            }
+            "jwt" => {
+                converted.mode = AuthMode::Jwt;
+                converted.jwt.algorithm = match param("algorithm").as_str() {
+                    "HS256" | "" => JwtAlgorithm::Hs256,
+                    "RS256" => JwtAlgorithm::Rs256,
+                    "ES256" => JwtAlgorithm::Es256,
+                    other => {
+                        self.report.push(format!(
+                            "{}: JWT algorithm {} isn't supported, HS256 is used",
+                            context, other
+                        ));
+                        JwtAlgorithm::Hs256
+                    }
+                };
+                converted.jwt.key = match converted.jwt.algorithm {
+                    JwtAlgorithm::Hs256 => param("secret"),
+                    _ => param("privateKey"),
+                };
+                let payload = param("payload");
+                if !payload.trim().is_empty() {
+                    converted.jwt.claims = payload;
+                }
+                // Postman signs the payload as it is
+                converted.jwt.add_issued_at = false;
+                converted.jwt.add_expiry = false;
+                if param("isSecretBase64Encoded") == "true" {
+                    self.report.push(format!(
+                        "{}: the JWT secret is base64 encoded, decode it before sending",
+                        context
+                    ));
+                }
+                if param("addTokenTo") == "queryParam" {
+                    self.report.push(format!(
+                        "{}: the JWT is sent in the Authorization header instead of a query param",
+                        context
+                    ));
+                }
+            }
+            "oauth2" => {
+                converted.mode = AuthMode::OAuth2;
+                let oauth2 = &mut converted.oauth2;
+                oauth2.grant = match param("grant_type").as_str() {
+                    "client_credentials" => OAuth2Grant::ClientCredentials,
+                    "password_credentials" => OAuth2Grant::Password,
+                    "authorization_code" | "authorization_code_with_pkce" | "" => {
+                        OAuth2Grant::AuthorizationCode
+                    }
+                    other => {
+                        self.report.push(format!(
+                            "{}: OAuth 2.0 grant {} isn't supported, imported as authorization code",
+                            context, other
+                        ));
+                        OAuth2Grant::AuthorizationCode
+                    }
+                };
+                oauth2.token_url = param("accessTokenUrl");
+                oauth2.authorization_url = param("authUrl");
+                oauth2.client_id = param("clientId");
+                oauth2.client_secret = param("clientSecret");
+                oauth2.scope = param("scope");
+                oauth2.username = param("username");
+                oauth2.password = param("password");
+                if param("client_authentication") == "body" {
+                    oauth2.client_authentication = ClientAuthentication::RequestBody;
+                }
+                let redirect = param("redirect_uri");
+                let port = url::Url::parse(&redirect)
+                    .ok()
+                    .filter(|url| matches!(url.host_str(), Some("localhost" | "127.0.0.1")))
+                    .and_then(|url| url.port());
+                match port {
+                    Some(port) => oauth2.redirect_port = port,
+                    None if oauth2.grant == OAuth2Grant::AuthorizationCode
+                        && !redirect.is_empty() =>
+                    {
+                        self.report.push(format!(
+                            "{}: redirect uri {} is replaced by a local one, register it with the provider",
+                            context, redirect
+                        ));
+                    }
+                    None => (),
+                }
+            }
+            other => {
+                self.report.push(format!(
+                    "{}: {} auth isn't supported, the request has no auth",
+                    context, other
+                ));
+            }
+        }
+        Some(converted)
+    }
+}
+
+fn item_auth(item: &Value) -> Option<&Value> {
+    item.pointer("/request/auth").or_else(|| item.get("auth"))
+}
+
+// v2.1 lists the parameters as key/value pairs, v2.0 as an object.
+fn auth_params(auth: &Value, kind: &str) -> Map<String, Value> {
+    match auth.get(kind) {
+        Some(Value::Array(params)) => params
+            .iter()
+            .filter_map(|param| {
+                let key = param.get("key")?.as_str()?.to_owned();
+                Some((key, param.get("value").cloned().unwrap_or(Value::Null)))
+            })
+            .collect(),
+        Some(Value::Object(params)) => params.clone(),
+        _ => Map::new(),
+    }
+}
+
+fn array(value: Option<&Value>) -> &[Value] {
+    match value {
+        Some(Value::Array(values)) => values,
+        _ => &[],
+    }
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(text) => Some(text.clone()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+fn join(parts: Option<&Value>, separator: &str) -> String {
+    match parts {
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| text(Some(part)))
+            .collect::<Vec<_>>()
+            .join(separator),
+        part => text(part).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::import::headers;
+    use crate::app::request_method::RequestMethod;
+
+    fn fixture() -> Imported {
+        let contents = include_str!("../../tests/fixtures/postman_collection.json");
+        import_postman(&serde_json::from_str(contents).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn imports_folders_requests_and_variables() {
+        let imported = fixture();
+        assert_eq!(imported.request_count(), 4);
+        let collection = &imported.collections[0];
+        assert_eq!(collection.name, "Shop API");
+        assert_eq!(collection.folders[0].name, "Users");
+
+        let get_user = &collection.folders[0].requests[0];
+        assert_eq!(get_user.name, "Get user");
+        // Values of path variables aren't replaced again
+        assert_eq!(get_user.url, "{baseUrl}/users/42/orders/:id");
+        assert_eq!(headers(get_user), vec![("Accept", "application/json")]);
+        assert_eq!(get_user.auth.mode, AuthMode::Bearer);
+        assert_eq!(get_user.auth.bearer.token, "{token}");
+
+        let create_user = &collection.folders[0].requests[1];
+        assert_eq!(create_user.method, RequestMethod::POST);
+        assert_eq!(create_user.auth.mode, AuthMode::Basic);
+        assert_eq!(create_user.auth.basic.username, "{user}");
+        assert_eq!(create_user.auth.basic.password, "{password}");
+        assert_eq!(
+            create_user.request_body,
+            "{\"id\": \"{$guid}\", \"name\": \"{name}\"}"
+        );
+        assert_eq!(
+            headers(create_user),
+            vec![("Content-Type", "application/json")]
+        );
+
+        let environment = &imported.environments[0];
+        assert_eq!(environment.name, "Shop API variables");
+        assert_eq!(
+            Value::Object(environment.variables.clone()),
+            serde_json::json!({"baseUrl": "https://shop.example.com"})
+        );
+    }
+
+    #[test]
+    fn builds_urls_and_bodies() {
+        let imported = fixture();
+        let collection = &imported.collections[0];
+        let search = &collection.requests[0];
+        assert_eq!(
+            search.url,
+            "https://api.example.com:8443/search/{term}?sort=price"
+        );
+        assert_eq!(search.request_body, "q=red%20shoes");
+        assert_eq!(
+            headers(search),
+            vec![("Content-Type", "application/x-www-form-urlencoded")]
+        );
+
+        let upload = &collection.requests[1];
+        assert_eq!(upload.method, RequestMethod::PUT);
+        assert_eq!(upload.auth.mode, AuthMode::None);
+        assert!(upload
+            .request_body
+            .contains("name=\"title\"\r\n\r\nAvatar\r\n"));
+        assert!(!upload.request_body.contains("avatar.png"));
+    }
+
+    #[test]
+    fn reports_what_wasnt_imported() {
+        assert_eq!(
+            fixture().report,
+            vec![
+                "Shop API / Users / Get user: pre-request script wasn't imported",
+                "Shop API / Users / Get user: disabled header X-Debug was skipped",
+                "Shop API / Search: path variable :term has no value, it's now {term}",
+                "Shop API / Search: disabled form field page was skipped",
+                "Shop API / Upload: file field file of the form was skipped, file uploads aren't supported",
+                "Postman dynamic variables have no equivalent and must be defined in an environment: {{$guid}}",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_v1_collections() {
+        let v1 = serde_json::json!({
+            "info": {"schema": "https://schema.getpostman.com/json/collection/v1.0.0/collection.json"},
+            "item": []
+        });
+        assert_eq!(
+            import_postman(&v1).unwrap_err(),
+            "Only Postman collections v2.0 and v2.1 can be imported"
+        );
+    }
+}
@@ -0,0 +1,598 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::auth::Auth;
+use super::collections::{Folder, SavedRequest};
+use super::environment_injector::only_references;
+use super::environment_watcher::CHECK_INTERVAL;
+use super::http_client::RequestOptions;
+use super::request_method::RequestMethod;
+use super::response_capture::CaptureRule;
+
+// A project is a directory that can live in a repository:
+//
+//   requestor.toml          project name and the defaults of every request
+//   environments/           environment files in any supported format
+//   collections/<name>/     a directory per collection and folder, each with a
+//                           folder.toml and a <name>.toml per request
+pub const PROJECT_FILE: &str = "requestor.toml";
+const FOLDER_FILE: &str = "folder.toml";
+const COLLECTIONS_DIR: &str = "collections";
+pub const ENVIRONMENTS_DIR: &str = "environments";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectFile {
+    pub name: String,
+    /// Relative request urls are appended to it, can use variables.
+    pub base_url: String,
+    /// Environment loaded into new tabs, relative to the project directory.
+    pub environment: String,
+    /// Sent with every request, unless the request sets the same header.
+    pub headers: Vec<Pair>,
+    pub request: RequestOptions,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FolderFile {
+    name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pair {
+    pub name: String,
+    pub value: String,
+}
+
+// Only what differs from an empty request is written, and only the settings of
+// the selected auth mode, so files stay short and diffs small.
+#[derive(Serialize, Deserialize)]
+struct RequestFile {
+    id: String,
+    name: String,
+    method: RequestMethod,
+    url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    query: Vec<Pair>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<Pair>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auth: Option<toml::Table>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    captures: Vec<CaptureRule>,
+}
+
+impl RequestFile {
+    fn from_request(request: &SavedRequest) -> Result<Self, String> {
+        Ok(Self {
+            id: request.id.clone(),
+            name: request.name.clone(),
+            method: request.method,
+            url: request.url.clone(),
+            query: pairs(&request.query_param_keys, &request.query_param_values),
+            headers: pairs(&request.request_header_keys, &request.request_header_values),
+            body: request.request_body.clone(),
+            auth: auth_table(&request.auth).map_err(|err| format!("{}: {}", request.name, err))?,
+            captures: request.capture_rules.clone(),
+        })
+    }
+
+    fn into_request(self) -> Result<SavedRequest, String> {
+        let auth = match self.auth {
+            Some(table) => toml::Value::Table(table)
+                .try_into()
+                .map_err(|err: toml::de::Error| err.to_string())?,
+            None => Auth::default(),
+        };
+        let (query_param_keys, query_param_values) = unzip_pairs(self.query);
+        let (request_header_keys, request_header_values) = unzip_pairs(self.headers);
+        Ok(SavedRequest {
+            id: self.id,
+            name: self.name,
+            method: self.method,
+            url: self.url,
+            request_header_keys,
+            request_header_values,
+            query_param_keys,
+            query_param_values,
+            request_body: self.body,
+            auth,
+            capture_rules: self.captures,
+        })
+    }
+}
+
+fn pairs(keys: &[String], values: &[String]) -> Vec<Pair> {
+    keys.iter()
+        .zip(values)
+        .filter(|(key, value)| !key.is_empty() || !value.is_empty())
+        .map(|(key, value)| Pair {
+            name: key.clone(),
+            value: value.clone(),
+        })
+        .collect()
+}
+
+// The editors always end with an empty row to type into.
+fn unzip_pairs(pairs: Vec<Pair>) -> (Vec<String>, Vec<String>) {
+    let (mut keys, mut values): (Vec<String>, Vec<String>) = pairs
+        .into_iter()
+        .map(|pair| (pair.name, pair.value))
+        .unzip();
+    keys.push("".to_owned());
+    values.push("".to_owned());
+    (keys, values)
+}
+
+// Project files are shared, so the secret fields have to be variables that are
+// filled from the vault or a local environment.
+fn auth_table(auth: &Auth) -> Result<Option<toml::Table>, String> {
+    let Some(active) = auth.mode.settings_key() else {
+        return Ok(None);
+    };
+    let mut checked = auth.clone();
+    if let Some((field, _)) = checked
+        .secret_fields()
+        .into_iter()
+        .find(|(_, value)| !only_references(value))
+    {
+        return Err(format!(
+            "the {} of the {} auth isn't a {{variable}}, secrets aren't written to the project",
+            field, auth.mode
+        ));
+    }
+    match toml::Value::try_from(auth).map_err(|err| err.to_string())? {
+        toml::Value::Table(mut table) => {
+            table.retain(|key, _| key == "mode" || key == active);
+            Ok(Some(table))
+        }
+        _ => Ok(None),
+    }
+}
+
+// Projects without a name are named after their directory.
+pub fn load_project_file(path: &Path) -> Result<ProjectFile, String> {
+    let mut project = read_toml::<ProjectFile>(&path.join(PROJECT_FILE))?;
+    if project.name.is_empty() {
+        project.name = directory_name(path);
+    }
+    Ok(project)
+}
+
+pub fn save_project_file(path: &Path, project: &ProjectFile) -> Result<(), String> {
+    let contents = toml::to_string_pretty(project).map_err(|err| err.to_string())?;
+    fs::write(path.join(PROJECT_FILE), contents).map_err(|err| err.to_string())
+}
+
+// The default environment of the project, if it has one.
+pub fn project_environment(path: &Path, project: &ProjectFile) -> Option<PathBuf> {
+    if path.as_os_str().is_empty() || project.environment.is_empty() {
+        return None;
+    }
+    Some(path.join(&project.environment))
+}
+
+fn directory_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// Creates the project file and directories, existing files are left alone.
+pub fn create_project(path: &Path) -> Result<(), String> {
+    fs::create_dir_all(path.join(COLLECTIONS_DIR)).map_err(|err| err.to_string())?;
+    fs::create_dir_all(path.join(ENVIRONMENTS_DIR)).map_err(|err| err.to_string())?;
+    let file = path.join(PROJECT_FILE);
+    if !file.exists() {
+        let project = ProjectFile {
+            name: directory_name(path),
+            ..Default::default()
+        };
+        save_project_file(path, &project)?;
+    }
+    Ok(())
+}
+
+// Folders and requests are read in file name order, so every checkout of the
+// project shows them the same way.
+pub fn load_project(path: &Path) -> Result<Vec<Folder>, String> {
+    if !path.join(PROJECT_FILE).exists() {
+        return Err(format!("{} has no {}", path.display(), PROJECT_FILE));
+    }
+    let directory = path.join(COLLECTIONS_DIR);
+    if !directory.exists() {
+        return Ok(vec![]);
+    }
+    let (collections, _) = load_directory(&directory)?;
+    Ok(collections)
+}
+
+fn load_directory(directory: &Path) -> Result<(Vec<Folder>, Vec<SavedRequest>), String> {
+    let mut folders = vec![];
+    let mut requests = vec![];
+    for entry in sorted_entries(directory)? {
+        if entry.is_dir() {
+            let name = read_toml::<FolderFile>(&entry.join(FOLDER_FILE))
+                .map(|folder| folder.name)
+                .unwrap_or_else(|_| directory_name(&entry));
+            let (children, child_requests) = load_directory(&entry)?;
+            folders.push(Folder {
+                name,
+                folders: children,
+                requests: child_requests,
+            });
+        } else if is_request_file(&entry) {
+            let request = read_toml::<RequestFile>(&entry)?.into_request()?;
+            requests.push(request);
+        }
+    }
+    Ok((folders, requests))
+}
+
+fn read_toml<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    toml::from_str(&contents).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn is_request_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "toml")
+        && path.file_name().is_some_and(|name| name != FOLDER_FILE)
+}
+
+fn sorted_entries(directory: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(directory)
+        .map_err(|err| err.to_string())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    // By name without the extension, so `name.toml` comes before `name-2.toml`
+    entries.sort_by_key(|entry| entry.file_stem().map(|stem| stem.to_os_string()));
+    Ok(entries)
+}
+
+// Writes the collections to the project and returns the files that make it up.
+// Files whose contents didn't change aren't touched. Of the `known` files, the
+// ones the app loaded or wrote before, those that no longer exist in the app are
+// removed, every other file is left alone.
+pub fn save_project(
+    path: &Path,
+    collections: &[Folder],
+    known: &BTreeSet<PathBuf>,
+) -> Result<BTreeSet<PathBuf>, String> {
+    create_project(path)?;
+    let directory = path.join(COLLECTIONS_DIR);
+    let mut files = vec![];
+    folder_files(&directory, collections, &[], &mut files)?;
+
+    let written: BTreeSet<PathBuf> = files.iter().map(|(file, _)| file.clone()).collect();
+    for (file, contents) in &files {
+        if fs::read_to_string(file).ok().as_ref() == Some(contents) {
+            continue;
+        }
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        fs::write(file, contents).map_err(|err| err.to_string())?;
+    }
+    let stale: BTreeSet<PathBuf> = known.difference(&written).cloned().collect();
+    remove_stale(&directory, &stale)?;
+    Ok(written)
+}
+
+fn folder_files(
+    directory: &Path,
+    folders: &[Folder],
+    requests: &[SavedRequest],
+    files: &mut Vec<(PathBuf, String)>,
+) -> Result<(), String> {
+    let mut used = BTreeSet::new();
+    for folder in folders {
+        let folder_directory = directory.join(unique_slug(&folder.name, "folder", &mut used));
+        let metadata = FolderFile {
+            name: folder.name.clone(),
+        };
+        let contents = toml::to_string_pretty(&metadata).map_err(|err| err.to_string())?;
+        files.push((folder_directory.join(FOLDER_FILE), contents));
+        folder_files(&folder_directory, &folder.folders, &folder.requests, files)?;
+    }
+    for request in requests {
+        let file_name = format!("{}.toml", unique_slug(&request.name, "request", &mut used));
+        let file = RequestFile::from_request(request)?;
+        let contents = toml::to_string_pretty(&file).map_err(|err| err.to_string())?;
+        files.push((directory.join(file_name), contents));
+    }
+    Ok(())
+}
+
+// File names are lowercase with dashes, duplicates get a number.
+pub fn unique_slug(name: &str, fallback: &str, used: &mut BTreeSet<String>) -> String {
+    let mut slug = String::new();
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = match slug.trim_end_matches('-') {
+        "" => fallback.to_owned(),
+        // Reserved for the folder metadata
+        "folder" if fallback == "request" => "folder-request".to_owned(),
+        slug => slug.to_owned(),
+    };
+    let mut unique = slug.clone();
+    let mut index = 2;
+    while !used.insert(unique.clone()) {
+        unique = format!("{}-{}", slug, index);
+        index += 1;
+    }
+    unique
+}
+
+fn remove_stale(directory: &Path, stale: &BTreeSet<PathBuf>) -> Result<(), String> {
+    for entry in sorted_entries(directory)? {
+        if entry.is_dir() {
+            remove_stale(&entry, stale)?;
+            // Only removed once nothing is left in it
+            let empty = fs::read_dir(&entry)
+                .map(|mut entries| entries.next().is_none())
+                .unwrap_or(false);
+            if empty {
+                fs::remove_dir(&entry).map_err(|err| err.to_string())?;
+            }
+        } else if stale.contains(&entry) {
+            fs::remove_file(&entry).map_err(|err| err.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+// Environment files shipped with the project.
+pub fn project_environments(path: &Path) -> Vec<PathBuf> {
+    sorted_entries(&path.join(ENVIRONMENTS_DIR))
+        .map(|entries| {
+            entries
+                .into_iter()
+                .filter(|entry| entry.is_file())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub enum ProjectChange {
+    Saved,
+    Reloaded(ProjectFile, Vec<Folder>),
+    /// The collections changed in the app and on disk since the last sync.
+    Conflict,
+    Failed(String),
+}
+
+// Keeps the project directory and the collections in the app in sync. Edits
+// made in the app are written out, and files changed on disk, by a git pull
+// for example, are loaded back. Polled like the environment files.
+#[derive(Default)]
+pub struct ProjectSync {
+    last_check: Option<Instant>,
+    synced: Value,
+    modified: Vec<(PathBuf, Option<SystemTime>)>,
+    /// Files of the collections the app loaded or wrote.
+    files: BTreeSet<PathBuf>,
+    conflict: bool,
+}
+
+impl ProjectSync {
+    // Remembers what was just loaded so it isn't written straight back.
+    pub fn loaded(&mut self, path: &Path, collections: &[Folder]) {
+        self.synced = serde_json::to_value(collections).unwrap_or_default();
+        self.modified = modified_times(path);
+        self.files = collection_files(path, &self.modified);
+        self.conflict = false;
+    }
+
+    // Takes in a change made by the app itself, so it isn't reported as a reload.
+    pub fn refresh(&mut self, path: &Path) {
+        self.modified = modified_times(path);
+    }
+
+    pub fn has_conflict(&self) -> bool {
+        self.conflict
+    }
+
+    // Resolves a conflict by writing the collections of the app over the files.
+    pub fn overwrite(&mut self, path: &Path, collections: &[Folder]) -> Result<(), String> {
+        let written = save_project(path, collections, &self.files)?;
+        self.loaded(path, collections);
+        self.files = written;
+        Ok(())
+    }
+
+    // Disk changes are looked at first, the app only writes over files that are
+    // as it last saw them.
+    pub fn check(&mut self, path: &Path, collections: &[Folder]) -> Option<ProjectChange> {
+        if self.conflict {
+            return None;
+        }
+        if let Some(last_check) = self.last_check {
+            if last_check.elapsed() < CHECK_INTERVAL {
+                return None;
+            }
+        }
+        self.last_check = Some(Instant::now());
+
+        let current = serde_json::to_value(collections).unwrap_or_default();
+        let modified = modified_times(path);
+        let app_changed = current != self.synced;
+        let disk_changed = modified != self.modified;
+
+        if app_changed && disk_changed {
+            self.conflict = true;
+            return Some(ProjectChange::Conflict);
+        }
+        if app_changed {
+            self.synced = current;
+            let saved = save_project(path, collections, &self.files);
+            self.modified = modified_times(path);
+            return Some(match saved {
+                Ok(written) => {
+                    self.files = written;
+                    ProjectChange::Saved
+                }
+                Err(error) => ProjectChange::Failed(error),
+            });
+        }
+        if !disk_changed {
+            return None;
+        }
+
+        self.modified = modified;
+        let loaded = load_project_file(path).and_then(|project| Ok((project, load_project(path)?)));
+        match loaded {
+            Ok((project, collections)) => {
+                self.synced = serde_json::to_value(&collections).unwrap_or_default();
+                self.files = collection_files(path, &self.modified);
+                Some(ProjectChange::Reloaded(project, collections))
+            }
+            Err(error) => Some(ProjectChange::Failed(error)),
+        }
+    }
+}
+
+// The toml files under the collections directory.
+fn collection_files(path: &Path, modified: &[(PathBuf, Option<SystemTime>)]) -> BTreeSet<PathBuf> {
+    let directory = path.join(COLLECTIONS_DIR);
+    modified
+        .iter()
+        .map(|(file, _)| file.clone())
+        .filter(|file| {
+            file.starts_with(&directory)
+                && file
+                    .extension()
+                    .is_some_and(|extension| extension == "toml")
+        })
+        .collect()
+}
+
+fn modified_times(path: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let file = path.join(PROJECT_FILE);
+    let modified = fs::metadata(&file).and_then(|meta| meta.modified()).ok();
+    let mut times = vec![(file, modified)];
+    collect_modified(&path.join(COLLECTIONS_DIR), &mut times);
+    times
+}
+
+fn collect_modified(directory: &Path, times: &mut Vec<(PathBuf, Option<SystemTime>)>) {
+    for entry in sorted_entries(directory).unwrap_or_default() {
+        if entry.is_dir() {
+            collect_modified(&entry, times);
+        } else {
+            let modified = fs::metadata(&entry).and_then(|meta| meta.modified()).ok();
+            times.push((entry, modified));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::auth::AuthMode;
+    use crate::app::tab_state::TabState;
+
+    fn project_dir(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("requestor-project-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        create_project(&path).unwrap();
+        path
+    }
+
+    fn collection(names: &[&str]) -> Vec<Folder> {
+        let mut folder = Folder::new("API");
+        for name in names {
+            folder
+                .requests
+                .push(SavedRequest::new(name.to_string(), &TabState::default()));
+        }
+        vec![folder]
+    }
+
+    #[test]
+    fn secrets_must_be_variables() {
+        let mut auth = Auth {
+            mode: AuthMode::Bearer,
+            ..Default::default()
+        };
+        auth.bearer.token = "{token}".to_owned();
+        let table = auth_table(&auth).unwrap().unwrap();
+        assert_eq!(table["bearer"]["token"].as_str(), Some("{token}"));
+        assert!(!table.contains_key("basic"));
+
+        auth.bearer.token = "abc{token}".to_owned();
+        let error = auth_table(&auth).unwrap_err();
+        assert!(error.starts_with("the token of the Bearer token auth isn't a {variable}"));
+
+        // The path of a key file isn't a secret
+        auth.mode = AuthMode::Jwt;
+        auth.jwt.key = "keys/private.pem".to_owned();
+        auth.jwt.key_source = crate::app::jwt::JwtKeySource::File;
+        assert!(auth_table(&auth).is_ok());
+    }
+
+    #[test]
+    fn only_removes_files_the_app_knows() {
+        let path = project_dir("stale");
+        let written = save_project(&path, &collection(&["One", "Two"]), &BTreeSet::new()).unwrap();
+        let directory = path.join(COLLECTIONS_DIR).join("api");
+        assert!(directory.join("two.toml").exists());
+        let unknown = directory.join("added-by-git.toml");
+        fs::write(&unknown, "not = 'loaded'").unwrap();
+
+        save_project(&path, &collection(&["One"]), &written).unwrap();
+        assert!(directory.join("one.toml").exists());
+        assert!(!directory.join("two.toml").exists());
+        assert!(unknown.exists());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn edits_on_both_sides_are_a_conflict() {
+        let path = project_dir("conflict");
+        let collections = collection(&["One"]);
+        save_project(&path, &collections, &BTreeSet::new()).unwrap();
+        let mut sync = ProjectSync::default();
+        sync.loaded(&path, &collections);
+
+        let file = path.join(COLLECTIONS_DIR).join("api").join("one.toml");
+        let contents = fs::read_to_string(&file).unwrap();
+        fs::write(&file, contents.replace("One", "Renamed on disk")).unwrap();
+        // Filesystems with coarse mtimes could keep the time of the first write
+        fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(60))
+            .unwrap();
+        let edited = collection(&["One", "Two"]);
+        assert!(matches!(
+            sync.check(&path, &edited),
+            Some(ProjectChange::Conflict)
+        ));
+        assert!(sync.has_conflict());
+        assert!(sync.check(&path, &edited).is_none());
+        // Nothing was written over the change on disk
+        assert!(fs::read_to_string(&file)
+            .unwrap()
+            .contains("Renamed on disk"));
+
+        sync.overwrite(&path, &edited).unwrap();
+        assert!(!sync.has_conflict());
+        assert!(fs::read_to_string(&file).unwrap().contains("\"One\""));
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
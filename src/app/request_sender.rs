@@ -1,25 +1,37 @@
 use std::cell::RefCell;
+use std::thread;
 use std::time::Instant;
 
 use egui_toast::Toasts;
 use poll_promise::Promise;
+use serde_json::{Map, Value};
 use url::Url;
 
 use crate::history_item::history_item::HistoryItem;
 
+use super::auth::{apply_auth, set_header, AuthMode};
+use super::digest_auth::fetch_with_digest;
 use super::environment_injector::inject_environment;
+use super::http_client::fetch;
+use super::jwt::generate_jwt;
 use super::request_method::RequestMethod;
 use super::resource::Resource;
+use super::secret_vault::redact_secrets;
 use super::tab_state::TabState;
 
 pub fn send_request(
     ui: &mut egui::Ui,
     state: &mut TabState,
+    variables: &Map<String, Value>,
+    secrets: &[String],
     toasts: &mut Toasts,
     active_request: &mut Option<HistoryItem>,
     next_id: usize,
 ) {
-    let (url, error) = inject_environment(&state.url, &state.environment);
+    let (url, error) = match request_url(state, variables) {
+        Ok(url) => (url, None),
+        Err(error) => ("".to_owned(), Some(error)),
+    };
     if error.is_some() {
         toasts.add(egui_toast::Toast {
             text: error.unwrap().into(),
@@ -47,15 +59,13 @@ pub fn send_request(
                     Some(v) => v,
                     None => break,
                 };
-                let (injected_key, _err) =
-                    inject_environment(&val.0.to_string(), &state.environment);
+                let (injected_key, _err) = inject_environment(&val.0.to_string(), variables);
                 if state.query_param_keys.len() == x {
                     state.query_param_keys.insert(x, injected_key)
                 } else {
                     state.query_param_keys[x] = injected_key;
                 }
-                let (injected_val, _err) =
-                    inject_environment(&val.0.to_string(), &state.environment);
+                let (injected_val, _err) = inject_environment(&val.0.to_string(), variables);
                 if state.query_param_values.len() == x {
                     state.query_param_values.insert(x, injected_val)
                 } else {
@@ -80,53 +90,159 @@ pub fn send_request(
         }
     }
 
+    // Signed here rather than when resolving, the preview shows the last token
+    if state.auth.mode == AuthMode::Jwt {
+        match generate_jwt(&state.auth.jwt, variables) {
+            Ok(token) => state.jwt_token = Some(token),
+            Err(error) => {
+                toasts.add(egui_toast::Toast {
+                    text: error.into(),
+                    kind: egui_toast::ToastKind::Error,
+                    options: egui_toast::ToastOptions::default()
+                        .duration_in_seconds(3.0)
+                        .show_progress(true)
+                        .show_icon(true),
+                });
+                return;
+            }
+        }
+    }
+
+    let request = match resolve_request(state, variables) {
+        Ok((request, _)) => request,
+        Err(error) => {
+            toasts.add(egui_toast::Toast {
+                text: error.into(),
+                kind: egui_toast::ToastKind::Error,
+                options: egui_toast::ToastOptions::default()
+                    .duration_in_seconds(3.0)
+                    .show_progress(true)
+                    .show_icon(true),
+            });
+            return;
+        }
+    };
+
     let (sender, promise) = Promise::new();
 
     let ctx = ui.ctx().clone();
 
+    let start = Instant::now();
+    if state.auth.mode == AuthMode::Digest {
+        // Both legs of the challenge run on one thread
+        let digest = state.auth.digest.resolved(variables);
+        let session = state.digest_session.clone();
+        let options = state.project.request.clone();
+        thread::spawn(move || {
+            let result = fetch_with_digest(&request, &digest, &session, &options);
+            let elapsed = start.elapsed();
+            ctx.request_repaint(); // wake up UI thread
+            let resource = result.map(|(response, legs)| {
+                let mut resource = Resource::from_response(&ctx, response, elapsed);
+                resource.digest_legs = legs;
+                resource
+            });
+            sender.send(resource);
+        });
+    } else {
+        fetch(request, state.project.request.clone(), move |response| {
+            let elapsed = start.elapsed();
+            //ctx.forget_image(&prev_url);
+            ctx.request_repaint(); // wake up UI thread
+            let resource =
+                response.map(|response| Resource::from_response(&ctx, response, elapsed));
+            sender.send(resource);
+        });
+    }
+
+    // Secret values never end up in the persisted history
+    let redact = |text: &String| redact_secrets(text, secrets);
+    *active_request = Some(HistoryItem {
+        id: next_id.to_string(),
+        url: redact(&url),
+        original_url: redact(&state.url),
+        method: state.method.clone(),
+        request_body: redact(&state.request_body),
+        request_header_keys: state.request_header_keys.iter().map(redact).collect(),
+        request_header_values: state.request_header_values.iter().map(redact).collect(),
+        query_param_keys: state.query_param_keys.iter().map(redact).collect(),
+        query_param_values: state.query_param_values.iter().map(redact).collect(),
+    });
+
+    state.promise = Some(promise);
+    state.capture_pending = true;
+}
+
+// The url with the variables injected. Relative urls are appended to the base
+// url of the project.
+pub fn request_url(state: &TabState, variables: &Map<String, Value>) -> Result<String, String> {
+    let (url, error) = inject_environment(&state.url, variables);
+    if let Some(error) = error {
+        return Err(error);
+    }
+    if state.project.base_url.is_empty() || url.contains("://") {
+        return Ok(url);
+    }
+    let (base_url, error) = inject_environment(&state.project.base_url, variables);
+    if let Some(error) = error {
+        return Err(error);
+    }
+    if url.is_empty() {
+        return Ok(base_url);
+    }
+    Ok(format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        url.trim_start_matches('/')
+    ))
+}
+
+// Builds the request exactly as it will be sent, with the variables injected into
+// the url, headers and body. Used both for sending and for the preview. Also
+// returns the text a signing auth mode signed.
+pub fn resolve_request(
+    state: &TabState,
+    variables: &Map<String, Value>,
+) -> Result<(ehttp::Request, Option<String>), String> {
+    let url = request_url(state, variables)?;
+
     let mut request = match state.method {
         RequestMethod::GET => ehttp::Request::get(&url),
-        RequestMethod::POST => ehttp::Request::post(&url, Vec::new()),
-        RequestMethod::PUT => ehttp::Request::post(&url, Vec::new()),
-        RequestMethod::PATCH => ehttp::Request::post(&url, Vec::new()),
-        RequestMethod::DELETE => ehttp::Request::post(&url, Vec::new()),
+        _ => ehttp::Request::post(&url, Vec::new()),
     };
-    for idx in 0..state.request_header_keys.len() {
-        if state.request_header_keys[idx].len() == 0 {
+    request.method = state.method.to_string();
+
+    // Added first so the headers of the request replace them
+    for header in &state.project.headers {
+        if header.name.is_empty() {
             continue;
         }
-        let (h_k, _err) = inject_environment(&state.request_header_keys[idx], &state.environment);
-        let (h_v, _err) = inject_environment(&state.request_header_values[idx], &state.environment);
-        request.headers.insert(&h_k, &h_v);
+        let (h_k, _err) = inject_environment(&header.name, variables);
+        let (h_v, _err) = inject_environment(&header.value, variables);
+        set_header(&mut request.headers, h_k, h_v);
     }
 
-    if (request.method != RequestMethod::GET.to_string()
-        || request.method != RequestMethod::DELETE.to_string())
-        && state.request_body.len() > 0
-    {
-        request.body = Vec::from(state.request_body.clone());
+    for idx in 0..state.request_header_keys.len() {
+        if state.request_header_keys[idx].is_empty() {
+            continue;
+        }
+        let (h_k, _err) = inject_environment(&state.request_header_keys[idx], variables);
+        let (h_v, _err) = inject_environment(&state.request_header_values[idx], variables);
+        set_header(&mut request.headers, h_k, h_v);
     }
 
-    let start = Instant::now();
-    ehttp::fetch(request, move |response| {
-        let elapsed = start.elapsed();
-        //ctx.forget_image(&prev_url);
-        ctx.request_repaint(); // wake up UI thread
-        let resource = response.map(|response| Resource::from_response(&ctx, response, elapsed));
-        sender.send(resource);
-    });
+    if !state.request_body.is_empty() {
+        let (body, _err) = inject_environment(&state.request_body, variables);
+        request.body = Vec::from(body);
+    }
 
-    *active_request = Some(HistoryItem {
-        id: next_id.to_string(),
-        url: url.clone(),
-        original_url: state.url.clone(),
-        method: state.method.clone(),
-        request_body: state.request_body.clone(),
-        request_header_keys: state.request_header_keys.clone(),
-        request_header_values: state.request_header_values.clone(),
-        query_param_keys: state.query_param_keys.clone(),
-        query_param_values: state.query_param_values.clone(),
-    });
+    let signed = apply_auth(
+        &mut request,
+        &state.auth,
+        variables,
+        state.oauth2_token.as_deref(),
+        state.jwt_token.as_deref(),
+    )?;
 
-    state.promise = Some(promise);
+    Ok((request, signed))
 }
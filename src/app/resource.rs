@@ -2,6 +2,9 @@ use std::time::Duration;
 
 use egui::Image;
 
+use super::digest_auth::DigestLeg;
+use super::jwt::{find_jwts, DecodedJwt};
+
 #[derive(Clone, Debug)]
 pub struct Resource {
     /// HTTP response
@@ -14,6 +17,12 @@ pub struct Resource {
 
     /// If set, the response was text with some supported syntax highlighting (e.g. ".rs" or ".md").
     pub colored_text: Option<ColoredText>,
+
+    /// The requests of the Digest auth exchange, the last one is this response.
+    pub digest_legs: Vec<DigestLeg>,
+
+    /// Tokens found in the headers and the body.
+    pub jwts: Vec<DecodedJwt>,
 }
 
 impl Resource {
@@ -26,6 +35,7 @@ impl Resource {
         if content_type.starts_with("image/") {
             ctx.include_bytes(response.url.clone(), response.bytes.clone());
             let image = Image::from_uri(response.url.clone());
+            let jwts = find_jwts(&response, None);
 
             Self {
                 response,
@@ -34,11 +44,14 @@ impl Resource {
                 raw_text: None,
                 colored_text: None,
                 image: Some(image),
+                digest_legs: vec![],
+                jwts,
             }
         } else {
             let raw_text =
                 unsafe { Some(std::str::from_utf8_unchecked(response.bytes.as_ref()).to_owned()) };
             let text = response.text();
+            let jwts = find_jwts(&response, text);
             let colored_text = text.and_then(|text| syntax_highlighting(ctx, &response, text));
             let text = text.map(|text| text.to_owned());
 
@@ -49,6 +62,8 @@ impl Resource {
                 raw_text,
                 colored_text,
                 image: None,
+                digest_legs: vec![],
+                jwts,
             }
         }
     }
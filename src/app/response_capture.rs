@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum CaptureSource {
+    JsonPath,
+    Header,
+    Cookie,
+    Regex,
+}
+
+impl CaptureSource {
+    pub const ALL: [CaptureSource; 4] = [
+        CaptureSource::JsonPath,
+        CaptureSource::Header,
+        CaptureSource::Cookie,
+        CaptureSource::Regex,
+    ];
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            CaptureSource::JsonPath => "$.data.token",
+            CaptureSource::Header => "Location",
+            CaptureSource::Cookie => "session",
+            CaptureSource::Regex => "token=(\\w+)",
+        }
+    }
+}
+
+impl fmt::Display for CaptureSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureSource::JsonPath => write!(f, "JSONPath"),
+            CaptureSource::Header => write!(f, "Header"),
+            CaptureSource::Cookie => write!(f, "Cookie"),
+            CaptureSource::Regex => write!(f, "Regex"),
+        }
+    }
+}
+
+// Writes a value taken from every response into a variable of the tab.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRule {
+    pub variable: String,
+    pub source: CaptureSource,
+    pub expression: String,
+}
+
+impl Default for CaptureRule {
+    fn default() -> Self {
+        Self {
+            variable: "".to_owned(),
+            source: CaptureSource::JsonPath,
+            expression: "".to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for CaptureRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.source, self.expression)
+    }
+}
+
+impl CaptureRule {
+    pub fn capture(&self, response: &ehttp::Response) -> Result<Value, String> {
+        match self.source {
+            CaptureSource::JsonPath => {
+                let text = response.text().ok_or("The body isn't text")?;
+                let body: Value = serde_json::from_str(text).map_err(|err| err.to_string())?;
+                json_path(&body, &self.expression).cloned()
+            }
+            CaptureSource::Header => response
+                .headers
+                .get(&self.expression)
+                .map(|value| Value::String(value.to_owned()))
+                .ok_or(format!("No {} header", self.expression)),
+            CaptureSource::Cookie => response
+                .headers
+                .get_all("set-cookie")
+                .find_map(|cookie| cookie_value(cookie, &self.expression))
+                .map(Value::String)
+                .ok_or(format!("No {} cookie", self.expression)),
+            CaptureSource::Regex => {
+                let text = response.text().ok_or("The body isn't text")?;
+                let regex = Regex::new(&self.expression).map_err(|err| err.to_string())?;
+                let captures = regex.captures(text).ok_or("The regex didn't match")?;
+                // The first group if there is one, otherwise the whole match
+                let found = captures.get(1).or(captures.get(0)).unwrap();
+                Ok(Value::String(found.as_str().to_owned()))
+            }
+        }
+    }
+}
+
+// Runs every rule against the response and stores the values. Returns the rules
+// that failed.
+pub fn apply_captures(
+    rules: &[CaptureRule],
+    response: &ehttp::Response,
+    values: &mut Map<String, Value>,
+    captured: &mut BTreeMap<String, String>,
+) -> Vec<String> {
+    let mut errors = vec![];
+    for rule in rules {
+        if rule.variable.is_empty() {
+            continue;
+        }
+        match rule.capture(response) {
+            Ok(value) => {
+                values.insert(rule.variable.clone(), value);
+                captured.insert(rule.variable.clone(), rule.to_string());
+            }
+            Err(error) => errors.push(format!("{} not captured: {}", rule.variable, error)),
+        }
+    }
+    errors
+}
+
+// Supports the common subset of JSONPath: `$`, `.key`, `['key']` and `[index]`.
+fn json_path<'a>(value: &'a Value, path: &str) -> Result<&'a Value, String> {
+    let mut rest = path.trim().strip_prefix('$').unwrap_or(path.trim());
+    let mut current = value;
+
+    while !rest.is_empty() {
+        let (segment, remaining) = if let Some(after) = rest.strip_prefix("['") {
+            let end = after.find("']").ok_or("Missing closing ']")?;
+            (&after[..end], &after[end + 2..])
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or("Missing closing ]")?;
+            (&after[..end], &after[end + 1..])
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        } else {
+            return Err(format!("Unexpected {} in path", rest));
+        };
+
+        current = match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|idx| items.get(idx)),
+            _ => None,
+        }
+        .ok_or(format!("Nothing found at {}", segment))?;
+        rest = remaining;
+    }
+
+    Ok(current)
+}
+
+fn cookie_value(set_cookie: &str, name: &str) -> Option<String> {
+    let pair = set_cookie.split(';').next()?;
+    let (key, value) = pair.split_once('=')?;
+    (key.trim() == name).then(|| value.trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response(headers: &[(&str, &str)], body: &str) -> ehttp::Response {
+        ehttp::Response {
+            url: "https://example.com".to_owned(),
+            ok: true,
+            status: 200,
+            status_text: "OK".to_owned(),
+            headers: ehttp::Headers::new(headers),
+            bytes: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn follows_json_paths() {
+        let body = json!({
+            "data": {"token": "abc", "user.name": "alice"},
+            "items": [{"id": 1}, {"id": 2, "tags": ["x", "y"]}]
+        });
+        assert_eq!(json_path(&body, "$.data.token"), Ok(&json!("abc")));
+        assert_eq!(json_path(&body, " $.items[1].id "), Ok(&json!(2)));
+        assert_eq!(json_path(&body, "$.items[1].tags[0]"), Ok(&json!("x")));
+        assert_eq!(
+            json_path(&body, "$['data']['user.name']"),
+            Ok(&json!("alice"))
+        );
+        assert_eq!(json_path(&body, "$"), Ok(&body));
+        // The leading $ is optional
+        assert_eq!(json_path(&body, ".data.token"), Ok(&json!("abc")));
+    }
+
+    #[test]
+    fn reports_missing_json_paths() {
+        let body = json!({"items": [{"id": 1}]});
+        assert_eq!(
+            json_path(&body, "$.data.token"),
+            Err("Nothing found at data".to_owned())
+        );
+        assert_eq!(
+            json_path(&body, "$.items[3]"),
+            Err("Nothing found at 3".to_owned())
+        );
+        assert_eq!(
+            json_path(&body, "$.items.id"),
+            Err("Nothing found at id".to_owned())
+        );
+        assert_eq!(
+            json_path(&body, "$['items'"),
+            Err("Missing closing ']".to_owned())
+        );
+        assert_eq!(
+            json_path(&body, "$items"),
+            Err("Unexpected items in path".to_owned())
+        );
+    }
+
+    #[test]
+    fn reads_cookies() {
+        assert_eq!(
+            cookie_value("session=abc123; Path=/; HttpOnly", "session"),
+            Some("abc123".to_owned())
+        );
+        assert_eq!(cookie_value("session=abc123; Path=/", "Path"), None);
+        assert_eq!(cookie_value("flag", "flag"), None);
+
+        let response = response(
+            &[
+                ("Set-Cookie", "theme=dark; Path=/"),
+                ("Set-Cookie", "session=abc123; Secure"),
+            ],
+            "",
+        );
+        let rule = CaptureRule {
+            variable: "session".to_owned(),
+            source: CaptureSource::Cookie,
+            expression: "session".to_owned(),
+        };
+        assert_eq!(rule.capture(&response), Ok(json!("abc123")));
+        let rule = CaptureRule {
+            expression: "missing".to_owned(),
+            ..rule
+        };
+        assert_eq!(rule.capture(&response), Err("No missing cookie".to_owned()));
+    }
+
+    #[test]
+    fn stores_captured_values() {
+        let response = response(&[("Location", "/users/7")], "{\"token\": \"abc\"}");
+        let rules = [
+            CaptureRule {
+                variable: "token".to_owned(),
+                source: CaptureSource::JsonPath,
+                expression: "$.token".to_owned(),
+            },
+            CaptureRule {
+                variable: "user_id".to_owned(),
+                source: CaptureSource::Regex,
+                expression: "token\": \"(\\d+)".to_owned(),
+            },
+            CaptureRule {
+                variable: "location".to_owned(),
+                source: CaptureSource::Header,
+                expression: "location".to_owned(),
+            },
+        ];
+        let mut values = Map::new();
+        let mut captured = BTreeMap::new();
+        let errors = apply_captures(&rules, &response, &mut values, &mut captured);
+        assert_eq!(errors, vec!["user_id not captured: The regex didn't match"]);
+        assert_eq!(
+            Value::Object(values),
+            json!({"token": "abc", "location": "/users/7"})
+        );
+        assert_eq!(captured["token"], "JSONPath $.token");
+    }
+}
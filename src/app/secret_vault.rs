@@ -0,0 +1,381 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use poll_promise::Promise;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+
+use super::variable_scopes::ResolvedVariable;
+
+const VAULT_FILE: &str = "secrets.vault";
+#[cfg(not(test))]
+const PBKDF2_ROUNDS: u32 = 600_000;
+// The derivation is the same, the tests don't have to wait for it
+#[cfg(test)]
+const PBKDF2_ROUNDS: u32 = 1_000;
+// Shorter secrets would mask every matching letter or digit of the text.
+const MIN_REDACTED_LEN: usize = 4;
+
+// What is written to disk, only the salt and nonce are stored in plain text.
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+// Local store for secret values, encrypted with AES-256-GCM using a key derived
+// from the passphrase with PBKDF2. The decrypted values and the key only live in
+// memory while the vault is unlocked.
+pub struct SecretVault {
+    path: Option<PathBuf>,
+    secrets: Option<BTreeMap<String, String>>,
+    key: Option<[u8; 32]>,
+    salt: Vec<u8>,
+    /// Deriving the key takes a while, it's done on a worker thread.
+    opening: Option<Promise<Result<OpenedVault, String>>>,
+}
+
+impl Default for SecretVault {
+    fn default() -> Self {
+        SecretVault::new(eframe::storage_dir("Requestor").map(|dir| dir.join(VAULT_FILE)))
+    }
+}
+
+// The decrypted contents and the key, before they are moved into the vault.
+struct OpenedVault {
+    secrets: BTreeMap<String, String>,
+    key: [u8; 32],
+    salt: Vec<u8>,
+    created: bool,
+}
+
+impl SecretVault {
+    // The path is None when the app has no data directory.
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            secrets: None,
+            key: None,
+            salt: vec![],
+            opening: None,
+        }
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.as_ref().is_some_and(|path| path.exists())
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.secrets.is_some()
+    }
+
+    pub fn is_opening(&self) -> bool {
+        self.opening.is_some()
+    }
+
+    pub fn secrets(&self) -> Option<&BTreeMap<String, String>> {
+        self.secrets.as_ref()
+    }
+
+    // Creates or unlocks the vault on a worker thread, `poll` takes the result.
+    pub fn open_in_background(&mut self, passphrase: String, ctx: &egui::Context) {
+        let create = !self.exists();
+        let path = self.path();
+        let ctx = ctx.clone();
+        let (sender, promise) = Promise::new();
+        thread::spawn(move || {
+            let opened = match create {
+                true => Ok(create_vault(&passphrase)),
+                false => path.and_then(|path| open_vault(&path, &passphrase)),
+            };
+            ctx.request_repaint();
+            sender.send(opened);
+        });
+        self.opening = Some(promise);
+    }
+
+    // The result of `open_in_background` once the key is derived.
+    pub fn poll(&mut self) -> Option<Result<(), String>> {
+        match self.opening.take()?.try_take() {
+            Ok(opened) => Some(opened.and_then(|opened| self.opened(opened))),
+            Err(promise) => {
+                self.opening = Some(promise);
+                None
+            }
+        }
+    }
+
+    fn opened(&mut self, opened: OpenedVault) -> Result<(), String> {
+        self.secrets = Some(opened.secrets);
+        self.key = Some(opened.key);
+        self.salt = opened.salt;
+        match opened.created {
+            true => self.write(),
+            false => Ok(()),
+        }
+    }
+
+    fn path(&self) -> Result<PathBuf, String> {
+        self.path
+            .clone()
+            .ok_or("Unable to find the app data directory".to_owned())
+    }
+
+    pub fn lock(&mut self) {
+        self.secrets = None;
+        self.key = None;
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match self.secrets.as_mut() {
+            Some(secrets) => {
+                secrets.insert(name.to_owned(), value.to_owned());
+                self.write()
+            }
+            None => Err("The vault is locked".to_owned()),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        match self.secrets.as_mut() {
+            Some(secrets) => {
+                secrets.remove(name);
+                self.write()
+            }
+            None => Err("The vault is locked".to_owned()),
+        }
+    }
+
+    // The vault layer used when resolving variables.
+    pub fn variables(&self) -> Map<String, Value> {
+        self.secrets
+            .iter()
+            .flatten()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect()
+    }
+
+    // Every change is written right away with a fresh nonce.
+    fn write(&self) -> Result<(), String> {
+        let (secrets, key) = match (&self.secrets, &self.key) {
+            (Some(secrets), Some(key)) => (secrets, key),
+            _ => return Err("The vault is locked".to_owned()),
+        };
+        let path = self.path()?;
+
+        let mut nonce = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let plaintext = serde_json::to_vec(secrets).map_err(|err| err.to_string())?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|err| err.to_string())?;
+
+        let file = VaultFile {
+            version: 1,
+            salt: STANDARD.encode(&self.salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        let contents = serde_json::to_string(&file).map_err(|err| err.to_string())?;
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+}
+
+fn create_vault(passphrase: &str) -> OpenedVault {
+    let mut salt = vec![0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    OpenedVault {
+        secrets: BTreeMap::new(),
+        key: derive_key(passphrase, &salt),
+        salt,
+        created: true,
+    }
+}
+
+fn open_vault(path: &Path, passphrase: &str) -> Result<OpenedVault, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let file: VaultFile = serde_json::from_str(&contents).map_err(|err| err.to_string())?;
+
+    let salt = decode(&file.salt)?;
+    let nonce = decode(&file.nonce)?;
+    let ciphertext = decode(&file.ciphertext)?;
+    let key = derive_key(passphrase, &salt);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| "Wrong passphrase or damaged vault".to_owned())?;
+    Ok(OpenedVault {
+        secrets: serde_json::from_slice(&plaintext).map_err(|err| err.to_string())?,
+        key,
+        salt,
+        created: false,
+    })
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+fn decode(text: &str) -> Result<Vec<u8>, String> {
+    STANDARD.decode(text).map_err(|err| err.to_string())
+}
+
+pub const MASK: &str = "••••••";
+
+// Values of the variables marked as secret, longest first so that a secret that
+// contains another one is replaced as a whole.
+pub fn secret_values(
+    resolved: &BTreeMap<String, ResolvedVariable>,
+    secret_names: &BTreeSet<String>,
+) -> Vec<String> {
+    let mut values: Vec<String> = secret_names
+        .iter()
+        .filter_map(|name| resolved.get(name))
+        .map(|variable| match &variable.value {
+            Value::String(text) => text.clone(),
+            value => value.to_string(),
+        })
+        .filter(|value| !value.is_empty())
+        .collect();
+    values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    values.dedup();
+    values
+}
+
+// Values too short to be told apart from the rest of the text are left as is.
+pub fn redact_secrets(text: &str, secret_values: &[String]) -> String {
+    let mut redacted = text.to_owned();
+    for value in secret_values {
+        if value.chars().count() < MIN_REDACTED_LEN {
+            continue;
+        }
+        redacted = redacted.replace(value.as_str(), MASK);
+    }
+    redacted
+}
+
+// Removes the secret variables from a set of variables before it is persisted.
+// Names of nested values use the same dotted form as when they are referenced.
+pub fn strip_secrets(variables: &mut Map<String, Value>, secret_names: &BTreeSet<String>) {
+    for name in secret_names {
+        remove_path(variables, name);
+    }
+}
+
+fn remove_path(variables: &mut Map<String, Value>, name: &str) {
+    if variables.shift_remove(name).is_some() {
+        return;
+    }
+    if let Some((head, rest)) = name.split_once('.') {
+        if let Some(Value::Object(nested)) = variables.get_mut(head) {
+            remove_path(nested, rest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("requestor_vault_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(VAULT_FILE)
+    }
+
+    #[test]
+    fn round_trips_secrets() {
+        let path = vault_path("round_trip");
+        let mut vault = SecretVault::new(Some(path.clone()));
+        assert!(!vault.exists());
+        vault.opened(create_vault("correct horse")).unwrap();
+        vault.set("token", "s3cr3t-value").unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("s3cr3t-value"));
+
+        let mut reopened = SecretVault::new(Some(path.clone()));
+        assert!(reopened.exists());
+        let opened = open_vault(&path, "correct horse").unwrap();
+        reopened.opened(opened).unwrap();
+        assert_eq!(
+            reopened.variables(),
+            Map::from_iter([("token".to_owned(), Value::from("s3cr3t-value"))])
+        );
+
+        reopened.lock();
+        assert!(!reopened.is_unlocked());
+        assert_eq!(
+            reopened.set("other", "value").unwrap_err(),
+            "The vault is locked"
+        );
+        assert_eq!(
+            open_vault(&path, "wrong horse").err(),
+            Some("Wrong passphrase or damaged vault".to_owned())
+        );
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn opens_in_the_background() {
+        let path = vault_path("background");
+        let ctx = egui::Context::default();
+        let mut vault = SecretVault::new(Some(path.clone()));
+        vault.open_in_background("passphrase".to_owned(), &ctx);
+        assert!(vault.is_opening());
+        let result = loop {
+            if let Some(result) = vault.poll() {
+                break result;
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert_eq!(result, Ok(()));
+        assert!(!vault.is_opening());
+        assert!(vault.is_unlocked());
+        // Creating the vault writes it, the next open unlocks it
+        assert!(vault.exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn strips_secrets() {
+        let mut variables = serde_json::json!({
+            "token": "abc",
+            "database": {"host": "localhost", "password": "hunter2"},
+            "user": "alice"
+        });
+        let names = BTreeSet::from(["token".to_owned(), "database.password".to_owned()]);
+        strip_secrets(variables.as_object_mut().unwrap(), &names);
+        assert_eq!(
+            variables,
+            serde_json::json!({"database": {"host": "localhost"}, "user": "alice"})
+        );
+    }
+
+    #[test]
+    fn redacts_secrets() {
+        let secrets = vec![
+            "hunter2-long".to_owned(),
+            "hunter2".to_owned(),
+            "1".to_owned(),
+        ];
+        assert_eq!(
+            redact_secrets("a=hunter2-long&b=hunter2&page=1", &secrets),
+            format!("a={}&b={}&page=1", MASK, MASK)
+        );
+    }
+}
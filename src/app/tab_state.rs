@@ -1,7 +1,14 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+use crate::app::auth::Auth;
+use crate::app::digest_auth::DigestSession;
+use crate::app::environment_diagnostics::Diagnostic;
+use crate::app::environment_file::EnvironmentFormat;
+use crate::app::project::ProjectFile;
 use crate::app::request_method::RequestMethod;
 use crate::app::resource::Resource;
+use crate::app::response_capture::CaptureRule;
 
 use poll_promise::Promise;
 use serde::{Deserialize, Serialize};
@@ -27,6 +34,45 @@ pub struct TabState {
     #[serde(skip)]
     pub promise: Option<Promise<ehttp::Result<Resource>>>,
     pub environment_path: PathBuf,
+    #[serde(default)]
+    pub environment_dirty: bool,
+    #[serde(default)]
+    pub variable_overrides: Map<String, Value>,
+    #[serde(default)]
+    pub environment_format: Option<EnvironmentFormat>,
+    #[serde(default)]
+    pub capture_rules: Vec<CaptureRule>,
+    /// Variables set by a capture rule and the rule that set them.
+    #[serde(default)]
+    pub captured: BTreeMap<String, String>,
+    /// Values of the captured variables, a layer over the environment so that
+    /// the environment file still reloads when it changes on disk.
+    #[serde(default)]
+    pub captured_values: Map<String, Value>,
+    #[serde(skip)]
+    pub capture_pending: bool,
+    #[serde(skip)]
+    pub environment_diagnostics: Vec<Diagnostic>,
+    #[serde(default)]
+    pub auth: Auth,
+    /// The OAuth 2.0 token attached to the request, taken from the token cache.
+    #[serde(skip)]
+    pub oauth2_token: Option<String>,
+    /// The JWT signed for the last request, a new one is signed on every send.
+    #[serde(skip)]
+    pub jwt_token: Option<String>,
+    /// Digest challenges received by this tab.
+    #[serde(skip)]
+    pub digest_session: DigestSession,
+    /// Send was clicked and the request waits for an OAuth 2.0 token.
+    #[serde(skip)]
+    pub send_pending: bool,
+    /// Id of the saved request the tab was opened from or saved to.
+    #[serde(default)]
+    pub saved_request: Option<String>,
+    /// Base url, headers and options of the open project, taken from the app.
+    #[serde(skip)]
+    pub project: ProjectFile,
 }
 
 impl Clone for TabState {
@@ -47,7 +93,22 @@ impl Clone for TabState {
             stx_hgl: self.stx_hgl.clone(),
             environment: self.environment.clone(),
             promise: Default::default(),
-            environment_path: Default::default(),
+            environment_path: self.environment_path.clone(),
+            environment_dirty: self.environment_dirty,
+            variable_overrides: self.variable_overrides.clone(),
+            environment_format: self.environment_format,
+            capture_rules: self.capture_rules.clone(),
+            captured: self.captured.clone(),
+            captured_values: self.captured_values.clone(),
+            capture_pending: false,
+            environment_diagnostics: self.environment_diagnostics.clone(),
+            auth: self.auth.clone(),
+            oauth2_token: None,
+            jwt_token: None,
+            digest_session: Default::default(),
+            send_pending: false,
+            saved_request: self.saved_request.clone(),
+            project: self.project.clone(),
         }
     }
 }
@@ -71,6 +132,21 @@ impl Default for TabState {
             environment: Default::default(),
             promise: Default::default(),
             environment_path: Default::default(),
+            environment_dirty: false,
+            variable_overrides: Default::default(),
+            environment_format: None,
+            capture_rules: vec![],
+            captured: Default::default(),
+            captured_values: Default::default(),
+            capture_pending: false,
+            environment_diagnostics: vec![],
+            auth: Default::default(),
+            oauth2_token: None,
+            jwt_token: None,
+            digest_session: Default::default(),
+            send_pending: false,
+            saved_request: None,
+            project: Default::default(),
         }
     }
 }
@@ -3,20 +3,46 @@ use egui_toast::Toasts;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
-use std::collections::BTreeMap;
-use std::fs;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
 
 use crate::app::tab_state::TabState;
 use crate::history_item::history_item::HistoryItem;
 use crate::ui::{
-    ui_body::ui_body, ui_headers::ui_headers, ui_query_params::ui_query_params,
-    ui_response::ui_response, ui_url::ui_url,
+    ui_auth::ui_auth,
+    ui_body::ui_body,
+    ui_captures::{ui_captured_values, ui_captures},
+    ui_environment::{ui_environment_editor, ui_environment_save},
+    ui_environment_diagnostics::ui_environment_diagnostics,
+    ui_headers::ui_headers,
+    ui_query_params::ui_query_params,
+    ui_request_preview::ui_request_preview,
+    ui_resolved_variables::ui_resolved_variables,
+    ui_response::ui_response,
+    ui_url::ui_url,
+    ui_variable_input::variable_previews,
 };
 use egui_dock::{NodeIndex, SurfaceIndex};
 use egui_modal::Modal;
 
-use super::request_sender::send_request;
+use super::auth::{auth_credentials, AuthMode};
+use super::code_snippets::{code_snippet, SnippetLanguage};
+use super::collections::{find_request_mut, has_unsaved_changes, Folder, SavedRequest};
+use super::curl_import::{apply_curl_command, is_curl_command};
+use super::dynamic_variables::{
+    command_variables, os_environment_variables, request_references, CommandRunner, CommandVariable,
+};
+use super::environment_diagnostics::{check_environment_file, missing_variables};
+use super::environment_file::{
+    create_environment, environment_file_dialog, load_environment, EnvironmentFormat,
+};
+use super::http_file::{exported_message, write_http_file};
+use super::oauth2::OAuth2Tokens;
+use super::project::{project_environments, ProjectFile};
+use super::request_sender::{resolve_request, send_request};
+use super::response_capture::apply_captures;
+use super::secret_vault::{redact_secrets, secret_values, strip_secrets, SecretVault};
+use super::variable_scopes::{resolve_tab_variables, variables_map, VariableScope};
 
 pub type Tab = String;
 
@@ -34,13 +60,96 @@ pub struct TabViewer {
     pub new_tab_name_temp: String,
     pub tab_name_to_change: String,
     pub env_modal_opened: bool,
+    #[serde(default)]
+    pub global_variables: Map<String, Value>,
+    #[serde(default)]
+    pub project_variables: Map<String, Value>,
+    #[serde(default)]
+    pub project_variables_path: PathBuf,
+    #[serde(default)]
+    pub project_variables_dirty: bool,
+    #[serde(default)]
+    pub globals_modal_opened: bool,
+    #[serde(default)]
+    pub project_modal_opened: bool,
+    #[serde(default)]
+    pub overrides_modal_opened: bool,
+    #[serde(default)]
+    pub resolved_modal_opened: bool,
+    #[serde(default)]
+    pub secret_names: BTreeSet<String>,
+    #[serde(default)]
+    pub secrets_modal_opened: bool,
+    #[serde(skip)]
+    pub vault: SecretVault,
+    #[serde(skip)]
+    pub vault_passphrase: String,
+    #[serde(skip)]
+    pub reveal_secrets: bool,
+    /// Copy as... leaves `{variables}` in the generated code.
+    #[serde(default)]
+    pub snippet_keep_variables: bool,
+    #[serde(skip)]
+    pub new_secret_name: String,
+    #[serde(skip)]
+    pub new_secret_value: String,
+    #[serde(default)]
+    pub commands: Vec<CommandVariable>,
+    #[serde(default)]
+    pub commands_enabled: bool,
+    #[serde(default)]
+    pub commands_modal_opened: bool,
+    #[serde(skip)]
+    pub command_runner: CommandRunner,
+    #[serde(default)]
+    pub diagnostics_modal_opened: bool,
+    #[serde(skip)]
+    pub oauth2_tokens: OAuth2Tokens,
+    #[serde(default)]
+    pub collections: Vec<Folder>,
+    /// The tab whose request is being saved in the Save request window.
+    #[serde(skip)]
+    pub save_request_tab: Option<String>,
+    #[serde(skip)]
+    pub save_request_name: String,
+    #[serde(skip)]
+    pub save_request_folder: Vec<usize>,
+    /// Directory of the open project, empty when no project is open.
+    #[serde(default)]
+    pub project_path: PathBuf,
+    #[serde(skip)]
+    pub project: ProjectFile,
+    /// Projects whose requests accept invalid certificates on this machine.
+    #[serde(default)]
+    pub insecure_projects: BTreeSet<PathBuf>,
+    /// The project settings were edited and not saved to the project file yet.
+    #[serde(skip)]
+    pub project_dirty: bool,
+    #[serde(default)]
+    pub project_settings_opened: bool,
+    /// Lines of the import report window, None when it's closed.
+    #[serde(skip)]
+    pub import_report: Option<Vec<String>>,
+    /// The tab a curl command is being imported into.
+    #[serde(skip)]
+    pub curl_import_tab: Option<String>,
+    #[serde(skip)]
+    pub curl_command: String,
 }
 
 impl egui_dock::TabViewer for TabViewer {
     type Tab = Tab;
 
     fn title(&mut self, title: &mut String) -> egui::WidgetText {
-        egui::WidgetText::from(&*title)
+        let unsaved = self
+            .open_requests
+            .get(title.as_str())
+            .is_some_and(|state| has_unsaved_changes(&self.collections, state));
+        if unsaved {
+            egui::WidgetText::from(format!("{} *", title))
+        } else {
+            egui::WidgetText::from(&*title)
+        }
     }
 
     fn on_add(&mut self, surface: SurfaceIndex, node: NodeIndex) {
@@ -71,23 +180,170 @@ impl egui_dock::TabViewer for TabViewer {
             .anchor(egui::Align2::CENTER_TOP, (10.0, 10.0))
             .direction(egui::Direction::TopDown);
 
+        // Names used by the tabs that share the environment file of this tab
+        let mut shared_references = vec![];
+        if self.diagnostics_modal_opened {
+            let path = self
+                .open_requests
+                .get(tab.as_str())
+                .map(|state| state.environment_path.clone())
+                .unwrap_or_default();
+            for (name, other) in &self.open_requests {
+                let shared = !path.as_os_str().is_empty() && other.environment_path == path;
+                if name == tab || shared {
+                    shared_references.push((name.clone(), request_references(other)));
+                }
+            }
+        }
+
         let state = self.open_requests.entry(tab.clone()).or_default();
+        state.project = self.project.clone();
+        state.project.request.accept_invalid_certs =
+            self.insecure_projects.contains(&self.project_path);
 
+        // Generated once the variables are resolved, below the menu
+        let mut copy_as = None;
         let menu_response = egui::menu::bar(ui, |ui| {
             ui.menu_button("Environment", |ui| {
+                if ui.button("New").clicked() {
+                    let file = environment_file_dialog()
+                        .set_file_name("environment.json")
+                        .save_file();
+
+                    if let Some(file_path) = file {
+                        match create_environment(&file_path) {
+                            Ok(()) => {
+                                state.environment_path = file_path;
+                                state.environment = Default::default();
+                                state.environment_dirty = false;
+                                self.env_modal_opened = true;
+                                toasts.add(egui_toast::Toast {
+                                    text: "Environment created".into(),
+                                    kind: egui_toast::ToastKind::Success,
+                                    options: egui_toast::ToastOptions::default()
+                                        .duration_in_seconds(3.0)
+                                        .show_progress(true)
+                                        .show_icon(true),
+                                });
+                            }
+                            Err(error) => {
+                                toasts.add(egui_toast::Toast {
+                                    text: format!("Unable to create environment: {}", error).into(),
+                                    kind: egui_toast::ToastKind::Error,
+                                    options: egui_toast::ToastOptions::default()
+                                        .duration_in_seconds(3.0)
+                                        .show_progress(true)
+                                        .show_icon(true),
+                                });
+                            }
+                        }
+                    }
+                    ui.close_menu();
+                }
                 if ui.button("Load").clicked() {
-                    let file = rfd::FileDialog::new()
-                        .add_filter("text", &["txt"])
-                        .add_filter("json", &["json"])
-                        .pick_file();
+                    let file = environment_file_dialog().pick_file();
 
                     match file {
                         Some(file_path) => {
-                            println!("File: {:?}", file_path);
-                            state.environment_path = file_path.clone();
-                            load_environment(file_path, state);
+                            state.environment_path = file_path;
+                            reload_environment(state, &mut toasts);
+                        }
+                        None => (),
+                    }
+                    ui.close_menu();
+                }
+                let environments = project_environments(&self.project_path);
+                if !environments.is_empty() {
+                    ui.menu_button("From project", |ui| {
+                        for file_path in environments {
+                            let name = file_path.file_name().unwrap_or_default().to_string_lossy();
+                            if ui.button(name).clicked() {
+                                state.environment_path = file_path.clone();
+                                reload_environment(state, &mut toasts);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                }
+                let diagnostics_label = match state.environment_diagnostics.len() {
+                    0 => "Diagnostics".to_owned(),
+                    count => format!("Diagnostics ({})", count),
+                };
+                if ui.button(diagnostics_label).clicked() {
+                    refresh_diagnostics(state);
+                    self.diagnostics_modal_opened = true;
+                    ui.close_menu();
+                }
+                if ui.button("Clear").clicked() {
+                    state.environment = Default::default();
+                    state.environment_path = Default::default();
+                    state.environment_dirty = false;
+                    ui.close_menu();
+                }
+                ui.separator();
+                ui.menu_button("Format", |ui| {
+                    let previous = state.environment_format;
+                    let detected = EnvironmentFormat::from_path(&state.environment_path);
+                    ui.radio_value(
+                        &mut state.environment_format,
+                        None,
+                        format!("From extension ({})", detected),
+                    );
+                    for format in EnvironmentFormat::ALL {
+                        ui.radio_value(
+                            &mut state.environment_format,
+                            Some(format),
+                            format.to_string(),
+                        );
+                    }
+                    if state.environment_format != previous {
+                        if !state.environment_path.as_os_str().is_empty() {
+                            reload_environment(state, &mut toasts);
+                        }
+                        ui.close_menu();
+                    }
+                });
+            });
+            ui.menu_button("Variables", |ui| {
+                if ui.button("Globals").clicked() {
+                    self.globals_modal_opened = true;
+                    ui.close_menu();
+                }
+                if ui.button("Project").clicked() {
+                    self.project_modal_opened = true;
+                    ui.close_menu();
+                }
+                if ui.button("Tab overrides").clicked() {
+                    self.overrides_modal_opened = true;
+                    ui.close_menu();
+                }
+                if ui.button("Resolved").clicked() {
+                    self.resolved_modal_opened = true;
+                    ui.close_menu();
+                }
+                if ui.button("Commands").clicked() {
+                    self.commands_modal_opened = true;
+                    ui.close_menu();
+                }
+                ui.separator();
+                if ui.button("Secrets").clicked() {
+                    self.secrets_modal_opened = true;
+                    ui.close_menu();
+                }
+                ui.checkbox(&mut self.reveal_secrets, "Reveal secrets")
+                    .on_hover_text("Show secret values and include them when copying");
+            });
+            ui.menu_button("Request", |ui| {
+                if ui.button("Save").clicked() {
+                    let saved = state
+                        .saved_request
+                        .as_ref()
+                        .and_then(|id| find_request_mut(&mut self.collections, id));
+                    match saved {
+                        Some(saved) => {
+                            saved.update(state);
                             toasts.add(egui_toast::Toast {
-                                text: "Environment loaded".into(),
+                                text: format!("Saved {}", saved.name).into(),
                                 kind: egui_toast::ToastKind::Success,
                                 options: egui_toast::ToastOptions::default()
                                     .duration_in_seconds(3.0)
@@ -95,12 +351,66 @@ impl egui_dock::TabViewer for TabViewer {
                                     .show_icon(true),
                             });
                         }
-                        None => (),
+                        // Not saved yet, or its saved request was deleted
+                        None => {
+                            self.save_request_tab = Some(tab.clone());
+                            self.save_request_name = tab.clone();
+                        }
                     }
                     ui.close_menu();
                 }
-                if ui.button("Clear").clicked() {
-                    state.environment = Default::default();
+                if ui.button("Save as").clicked() {
+                    self.save_request_tab = Some(tab.clone());
+                    self.save_request_name = tab.clone();
+                    ui.close_menu();
+                }
+                ui.separator();
+                ui.menu_button("Copy as...", |ui| {
+                    for language in SnippetLanguage::ALL {
+                        if ui.button(language.label()).clicked() {
+                            copy_as = Some(language);
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.snippet_keep_variables, "Keep {variables}")
+                        .on_hover_text("Leave the variables in the code instead of their values");
+                });
+                if ui.button("Import curl").clicked() {
+                    self.curl_import_tab = Some(tab.clone());
+                    self.curl_command = "".to_owned();
+                    ui.close_menu();
+                }
+                if ui.button("Export as .http").clicked() {
+                    let file = rfd::FileDialog::new()
+                        .add_filter("http", &["http", "rest"])
+                        .set_file_name(format!("{}.http", tab))
+                        .save_file();
+                    if let Some(file_path) = file {
+                        // Tab overrides are where the variables of an opened file went
+                        let request = SavedRequest::new(tab.clone(), state);
+                        let mut variables = state.variable_overrides.clone();
+                        strip_secrets(&mut variables, &self.secret_names);
+                        let (text, kind) = match write_http_file(&file_path, &[request], &variables)
+                        {
+                            Ok(replaced) => (
+                                exported_message(tab, &replaced),
+                                egui_toast::ToastKind::Success,
+                            ),
+                            Err(error) => (
+                                format!("Unable to export {}: {}", tab, error),
+                                egui_toast::ToastKind::Error,
+                            ),
+                        };
+                        toasts.add(egui_toast::Toast {
+                            text: text.into(),
+                            kind,
+                            options: egui_toast::ToastOptions::default()
+                                .duration_in_seconds(3.0)
+                                .show_progress(true)
+                                .show_icon(true),
+                        });
+                    }
                     ui.close_menu();
                 }
             });
@@ -110,31 +420,210 @@ impl egui_dock::TabViewer for TabViewer {
             ui.ctx(),
             tab,
             &mut self.env_modal_opened,
-            state.environment.len() > 0,
+            has_environment(state),
             menu_response.response.rect,
             state,
             &mut toasts,
         );
 
-        if state.environment.len() > 0 {
+        // Capture rules run once for every response that arrives
+        if state.capture_pending {
+            if let Some(result) = state.promise.as_ref().and_then(|p| p.ready()) {
+                if let Ok(resource) = result {
+                    let errors = apply_captures(
+                        &state.capture_rules,
+                        &resource.response,
+                        &mut state.captured_values,
+                        &mut state.captured,
+                    );
+                    for error in errors {
+                        toasts.add(egui_toast::Toast {
+                            text: error.into(),
+                            kind: egui_toast::ToastKind::Warning,
+                            options: egui_toast::ToastOptions::default()
+                                .duration_in_seconds(3.0)
+                                .show_progress(true)
+                                .show_icon(true),
+                        });
+                    }
+                }
+                state.capture_pending = false;
+            }
+        }
+
+        if has_environment(state) {
             // Env values modal window
             let mut modal_title = "Environment variables for ".to_owned();
             modal_title.push_str(tab.as_str());
             egui::Window::new(modal_title)
                 .open(&mut self.env_modal_opened)
                 .show(ui.ctx(), |ui| {
-                    egui::Grid::new("env_values")
-                        .spacing(egui::vec2(ui.spacing().item_spacing.x * 4.0, 4.0))
+                    egui::ScrollArea::vertical()
+                        .max_height(400.0)
                         .show(ui, |ui| {
-                            for (k, v) in &state.environment {
-                                ui.label(k);
-                                ui.label(v.as_str().unwrap_or("Invalid value"));
-                                ui.end_row();
+                            let mut id_source = "env_editor".to_owned();
+                            id_source.push_str(tab.as_str());
+                            if ui_environment_editor(
+                                ui,
+                                &mut state.environment,
+                                &id_source,
+                                &mut self.secret_names,
+                            ) {
+                                state.environment_dirty = true;
                             }
                         });
+                    if !state.captured.is_empty() {
+                        ui.separator();
+                        ui_captured_values(
+                            ui,
+                            &state.captured,
+                            &state.captured_values,
+                            &self.secret_names,
+                            self.reveal_secrets,
+                        );
+                    }
+                    ui.separator();
+                    ui_environment_save(
+                        ui,
+                        &state.environment,
+                        &state.environment_path,
+                        state.environment_format,
+                        &mut state.environment_dirty,
+                        &mut toasts,
+                    );
                 });
         }
 
+        let references = request_references(state);
+        let commands = command_variables(
+            ui.ctx(),
+            &references,
+            &self.commands,
+            &self.command_runner,
+            self.commands_enabled,
+        );
+        let dynamic = [
+            (
+                VariableScope::OsEnvironment,
+                &os_environment_variables(&references),
+            ),
+            (VariableScope::Command, &commands),
+        ];
+        let resolved = resolve_tab_variables(
+            &dynamic,
+            &self.global_variables,
+            &self.project_variables,
+            &self.vault.variables(),
+            state,
+        );
+        let variables = variables_map(&resolved);
+        let oauth2 = state.auth.oauth2.resolved(&variables);
+        state.oauth2_token = match state.auth.mode {
+            AuthMode::OAuth2 => {
+                let options = &state.project.request;
+                self.oauth2_tokens.access_token(ui.ctx(), &oauth2, options)
+            }
+            _ => None,
+        };
+        let secrets = if self.reveal_secrets {
+            vec![]
+        } else {
+            let mut secrets = secret_values(&resolved, &self.secret_names);
+            let (oauth2_token, jwt_token) =
+                (state.oauth2_token.as_deref(), state.jwt_token.as_deref());
+            secrets.extend(auth_credentials(
+                &state.auth,
+                &variables,
+                oauth2_token,
+                jwt_token,
+            ));
+            secrets
+        };
+        let previews = variable_previews(&resolved, &self.secret_names, self.reveal_secrets);
+
+        if let Some(language) = copy_as {
+            match code_snippet(language, state, &variables, self.snippet_keep_variables) {
+                Ok(code) => {
+                    ui.ctx().copy_text(redact_secrets(&code, &secrets));
+                    toasts.add(egui_toast::Toast {
+                        text: format!("Copied as {}", language.label()).into(),
+                        kind: egui_toast::ToastKind::Success,
+                        options: egui_toast::ToastOptions::default()
+                            .duration_in_seconds(3.0)
+                            .show_progress(true)
+                            .show_icon(true),
+                    });
+                }
+                Err(error) => {
+                    toasts.add(egui_toast::Toast {
+                        text: format!("Unable to copy the request: {}", error).into(),
+                        kind: egui_toast::ToastKind::Error,
+                        options: egui_toast::ToastOptions::default()
+                            .duration_in_seconds(3.0)
+                            .show_progress(true)
+                            .show_icon(true),
+                    });
+                }
+            }
+        }
+
+        let mut diagnostics_title = "Environment diagnostics for ".to_owned();
+        diagnostics_title.push_str(tab.as_str());
+        egui::Window::new(diagnostics_title)
+            .open(&mut self.diagnostics_modal_opened)
+            .show(ui.ctx(), |ui| {
+                if ui.button("Check again").clicked() {
+                    refresh_diagnostics(state);
+                }
+                let mut diagnostics = state.environment_diagnostics.clone();
+                diagnostics.extend(missing_variables(&shared_references, &resolved));
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        ui_environment_diagnostics(ui, &diagnostics);
+                    });
+            });
+
+        let mut overrides_title = "Tab overrides for ".to_owned();
+        overrides_title.push_str(tab.as_str());
+        egui::Window::new(overrides_title)
+            .open(&mut self.overrides_modal_opened)
+            .show(ui.ctx(), |ui| {
+                ui.label("Values set here apply only to this tab and win over every other layer.");
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        let mut id_source = "overrides_editor".to_owned();
+                        id_source.push_str(tab.as_str());
+                        ui_environment_editor(
+                            ui,
+                            &mut state.variable_overrides,
+                            &id_source,
+                            &mut self.secret_names,
+                        );
+                    });
+            });
+
+        let mut resolved_title = "Resolved variables for ".to_owned();
+        resolved_title.push_str(tab.as_str());
+        egui::Window::new(resolved_title)
+            .open(&mut self.resolved_modal_opened)
+            .show(ui.ctx(), |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        ui_resolved_variables(
+                            ui,
+                            &resolved,
+                            state,
+                            &mut self.secret_names,
+                            &mut self.vault,
+                            self.reveal_secrets,
+                            &mut toasts,
+                        );
+                    });
+            });
+
         if self.new_tab_name != ""
             && self.tab_name_to_change != ""
             && self.tab_name_to_change == tab.clone()
@@ -159,7 +648,20 @@ impl egui_dock::TabViewer for TabViewer {
         egui::CollapsingHeader::new("Request")
             .default_open(true)
             .show(ui, |ui| {
-                let trigger_fetch = ui_url(ui, &mut state.url, &mut state.method);
+                if state.project.request.accept_invalid_certs {
+                    ui.colored_label(
+                        catppuccin_egui::MOCHA.yellow,
+                        "⚠ Certificates aren't checked, invalid certificates are accepted for this project",
+                    );
+                }
+                let typed_url = state.url.clone();
+                let mut trigger_fetch = ui_url(ui, &mut state.url, &mut state.method, &previews);
+                // A pasted curl command replaces the request, a command that can't
+                // be parsed stays in the url to be fixed. Pasting never reads files.
+                if state.url != typed_url && is_curl_command(&state.url) {
+                    let command = state.url.clone();
+                    import_curl(state, &command, false, &mut self.import_report, &mut toasts);
+                }
 
                 ui_query_params(
                     ui,
@@ -172,14 +674,62 @@ impl egui_dock::TabViewer for TabViewer {
                     ui,
                     &mut state.request_header_keys,
                     &mut state.request_header_values,
+                    &previews,
+                );
+
+                ui_auth(
+                    ui,
+                    &mut state.auth,
+                    &previews,
+                    &self.oauth2_tokens,
+                    &oauth2,
+                    &state.project.request,
                 );
 
                 ui_body(ui, &mut state.request_body);
 
+                ui_captures(ui, &mut state.capture_rules);
+
+                let (request, signed) = match resolve_request(state, &variables) {
+                    Ok((request, signed)) => (Ok(request), signed),
+                    Err(error) => (Err(error), None),
+                };
+                ui_request_preview(ui, &request, signed.as_deref(), &secrets);
+
+                // Without a token the request waits until one is fetched
+                let oauth2_mode = state.auth.mode == AuthMode::OAuth2;
+                if trigger_fetch && oauth2_mode && state.oauth2_token.is_none() {
+                    let options = &state.project.request;
+                    self.oauth2_tokens.request_token(ui.ctx(), &oauth2, options);
+                    state.send_pending = true;
+                    trigger_fetch = false;
+                }
+                if state.send_pending {
+                    let entry = self.oauth2_tokens.entry(&oauth2);
+                    if !oauth2_mode {
+                        state.send_pending = false;
+                    } else if let Some(error) = entry.error {
+                        toasts.add(egui_toast::Toast {
+                            text: error.into(),
+                            kind: egui_toast::ToastKind::Error,
+                            options: egui_toast::ToastOptions::default()
+                                .duration_in_seconds(3.0)
+                                .show_progress(true)
+                                .show_icon(true),
+                        });
+                        state.send_pending = false;
+                    } else if state.oauth2_token.is_some() {
+                        state.send_pending = false;
+                        trigger_fetch = true;
+                    }
+                }
+
                 if trigger_fetch {
                     send_request(
                         ui,
                         state,
+                        &variables,
+                        &secrets,
                         &mut toasts,
                         &mut self.active_request,
                         self.history_items.len(),
@@ -218,6 +768,7 @@ impl egui_dock::TabViewer for TabViewer {
                                     &mut state.show_info,
                                     &mut state.wrap_text,
                                     &mut state.stx_hgl,
+                                    &secrets,
                                 );
                                 state.resource = Some(resource.clone());
                             }
@@ -297,15 +848,7 @@ fn environment_status_icons(
                         .on_hover_text("Reload environment values.")
                         .clicked()
                     {
-                        load_environment(state.environment_path.clone(), state);
-                        toasts.add(egui_toast::Toast {
-                            text: "Environment loaded".into(),
-                            kind: egui_toast::ToastKind::Success,
-                            options: egui_toast::ToastOptions::default()
-                                .duration_in_seconds(3.0)
-                                .show_progress(true)
-                                .show_icon(true),
-                        });
+                        reload_environment(state, toasts);
                     }
                     if ui
                         .button("✅")
@@ -322,16 +865,86 @@ fn environment_status_icons(
         });
 }
 
-fn load_environment(file_path: PathBuf, state: &mut TabState) {
-    match fs::read_to_string(file_path) {
-        Ok(contents) => {
-            let parsed: Value = serde_json::from_str(&contents).unwrap();
-            let obj: Map<String, Value> = parsed.as_object().unwrap().clone();
-            println!("Parsed: {:?} ", obj);
-            state.environment = obj;
+// An environment counts as loaded once it has values or is backed by a file, a
+// freshly created environment file is empty but can still be edited.
+fn has_environment(state: &TabState) -> bool {
+    !state.environment.is_empty() || !state.environment_path.as_os_str().is_empty()
+}
+
+fn refresh_diagnostics(state: &mut TabState) {
+    state.environment_diagnostics = if state.environment_path.as_os_str().is_empty() {
+        vec![]
+    } else {
+        check_environment_file(&state.environment_path, state.environment_format)
+    };
+}
+
+fn reload_environment(state: &mut TabState, toasts: &mut Toasts) {
+    refresh_diagnostics(state);
+    match load_environment(&state.environment_path, state.environment_format) {
+        Ok(environment) => {
+            state.environment = environment;
+            state.environment_dirty = false;
+            toasts.add(egui_toast::Toast {
+                text: "Environment loaded".into(),
+                kind: egui_toast::ToastKind::Success,
+                options: egui_toast::ToastOptions::default()
+                    .duration_in_seconds(3.0)
+                    .show_progress(true)
+                    .show_icon(true),
+            });
         }
         Err(error) => {
-            println!("{:?}", error);
+            toasts.add(egui_toast::Toast {
+                text: format!(
+                    "Unable to load environment, see Environment > Diagnostics: {}",
+                    error
+                )
+                .into(),
+                kind: egui_toast::ToastKind::Error,
+                options: egui_toast::ToastOptions::default()
+                    .duration_in_seconds(3.0)
+                    .show_progress(true)
+                    .show_icon(true),
+            });
+        }
+    }
+}
+
+// Fills the tab from a curl command, the report window lists what was skipped
+// and the files that were read.
+pub fn import_curl(
+    state: &mut TabState,
+    command: &str,
+    read_files: bool,
+    import_report: &mut Option<Vec<String>>,
+    toasts: &mut Toasts,
+) -> bool {
+    match apply_curl_command(state, command, read_files) {
+        Ok(report) => {
+            if !report.is_empty() {
+                *import_report = Some(report);
+            }
+            toasts.add(egui_toast::Toast {
+                text: "Request imported from curl".into(),
+                kind: egui_toast::ToastKind::Success,
+                options: egui_toast::ToastOptions::default()
+                    .duration_in_seconds(3.0)
+                    .show_progress(true)
+                    .show_icon(true),
+            });
+            true
+        }
+        Err(error) => {
+            toasts.add(egui_toast::Toast {
+                text: format!("Unable to import the curl command: {}", error).into(),
+                kind: egui_toast::ToastKind::Error,
+                options: egui_toast::ToastOptions::default()
+                    .duration_in_seconds(5.0)
+                    .show_progress(true)
+                    .show_icon(true),
+            });
+            false
         }
     }
 }
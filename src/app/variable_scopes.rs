@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use super::environment_injector::flatten_environment;
+use super::tab_state::TabState;
+
+// Layers are listed from the lowest to the highest priority, a value set in a later
+// layer replaces the one from the earlier layers.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum VariableScope {
+    OsEnvironment,
+    Command,
+    Global,
+    Project,
+    Environment,
+    Captured,
+    Vault,
+    Tab,
+}
+
+impl fmt::Display for VariableScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariableScope::OsEnvironment => write!(f, "OS environment"),
+            VariableScope::Command => write!(f, "Command output"),
+            VariableScope::Global => write!(f, "Global"),
+            VariableScope::Project => write!(f, "Project"),
+            VariableScope::Environment => write!(f, "Environment"),
+            VariableScope::Captured => write!(f, "Captured"),
+            VariableScope::Vault => write!(f, "Secret vault"),
+            VariableScope::Tab => write!(f, "Tab override"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedVariable {
+    pub value: Value,
+    pub scope: VariableScope,
+    /// Lower layers that also define the variable.
+    pub shadowed: Vec<VariableScope>,
+}
+
+// Resolves the variables of all layers, which are passed from the lowest to the
+// highest priority. Nested values are flattened first so that a single key of an
+// object can be overridden without repeating the whole object.
+pub fn resolve_variables(
+    layers: &[(VariableScope, &Map<String, Value>)],
+) -> BTreeMap<String, ResolvedVariable> {
+    let mut resolved: BTreeMap<String, ResolvedVariable> = BTreeMap::new();
+
+    for (scope, variables) in layers.iter().copied() {
+        for (k, v) in flatten_environment(variables) {
+            let shadowed = match resolved.get(&k) {
+                Some(previous) => {
+                    let mut shadowed = previous.shadowed.clone();
+                    shadowed.push(previous.scope);
+                    shadowed
+                }
+                None => vec![],
+            };
+            resolved.insert(
+                k,
+                ResolvedVariable {
+                    value: v,
+                    scope,
+                    shadowed,
+                },
+            );
+        }
+    }
+
+    resolved
+}
+
+// All layers that apply to a tab. `$env` and `$cmd` values come first, their
+// names can't clash with regular variables unless those are named on purpose.
+pub fn resolve_tab_variables(
+    dynamic: &[(VariableScope, &Map<String, Value>)],
+    globals: &Map<String, Value>,
+    project: &Map<String, Value>,
+    vault: &Map<String, Value>,
+    state: &TabState,
+) -> BTreeMap<String, ResolvedVariable> {
+    let mut layers = dynamic.to_vec();
+    layers.extend([
+        (VariableScope::Global, globals),
+        (VariableScope::Project, project),
+        (VariableScope::Environment, &state.environment),
+        (VariableScope::Captured, &state.captured_values),
+        (VariableScope::Vault, vault),
+        (VariableScope::Tab, &state.variable_overrides),
+    ]);
+    resolve_variables(&layers)
+}
+
+// The resolved variables in the shape `inject_environment` expects.
+pub fn variables_map(resolved: &BTreeMap<String, ResolvedVariable>) -> Map<String, Value> {
+    resolved
+        .iter()
+        .map(|(k, v)| (k.clone(), v.value.clone()))
+        .collect()
+}
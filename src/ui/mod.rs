@@ -1,6 +1,17 @@
+pub mod ui_auth;
 pub mod ui_body;
+pub mod ui_captures;
+pub mod ui_collections;
+pub mod ui_commands;
+pub mod ui_environment;
+pub mod ui_environment_diagnostics;
 pub mod ui_headers;
 pub mod ui_history;
+pub mod ui_project_settings;
 pub mod ui_query_params;
+pub mod ui_request_preview;
+pub mod ui_resolved_variables;
 pub mod ui_response;
+pub mod ui_secret_vault;
 pub mod ui_url;
+pub mod ui_variable_input;
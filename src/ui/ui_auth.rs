@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use crate::app::auth::{ApiKeyLocation, Auth, AuthMode};
+use crate::app::digest_auth::DigestAlgorithm;
+use crate::app::hmac_signature::{
+    HmacAlgorithm, HmacSigning, KeySource, SignatureEncoding, PLACEHOLDERS,
+};
+use crate::app::http_client::RequestOptions;
+use crate::app::jwt::{JwtAlgorithm, JwtAuth, JwtKeySource};
+use crate::app::oauth2::{ClientAuthentication, OAuth2Config, OAuth2Grant, OAuth2Tokens};
+use crate::ui::ui_variable_input::ui_variable_input;
+
+// `oauth2` is the OAuth 2.0 configuration with the variables injected, the key
+// of the cached token. `options` are the connection settings of the project.
+pub fn ui_auth(
+    ui: &mut egui::Ui,
+    auth: &mut Auth,
+    previews: &BTreeMap<String, String>,
+    tokens: &OAuth2Tokens,
+    oauth2: &OAuth2Config,
+    options: &RequestOptions,
+) {
+    ui.separator();
+    egui::CollapsingHeader::new("Authorization")
+        .default_open(false)
+        .show(ui, |ui| {
+            egui::ComboBox::from_id_source("auth_mode")
+                .selected_text(auth.mode.to_string())
+                .show_ui(ui, |ui| {
+                    for option in AuthMode::ALL {
+                        ui.selectable_value(&mut auth.mode, option, option.to_string());
+                    }
+                });
+
+            let width = ui.spacing().text_edit_width;
+            egui::Grid::new("auth_fields").show(ui, |ui| match auth.mode {
+                AuthMode::None => (),
+                AuthMode::Basic => {
+                    ui.label("Username:");
+                    ui_variable_input(ui, &mut auth.basic.username, previews, width);
+                    ui.end_row();
+                    ui.label("Password:");
+                    ui.add(egui::TextEdit::singleline(&mut auth.basic.password).password(true));
+                    ui.end_row();
+                }
+                AuthMode::Bearer => {
+                    ui.label("Token:");
+                    ui_variable_input(ui, &mut auth.bearer.token, previews, width);
+                    ui.end_row();
+                }
+                AuthMode::ApiKey => {
+                    ui.label("Name:");
+                    ui_variable_input(ui, &mut auth.api_key.name, previews, width);
+                    ui.end_row();
+                    ui.label("Value:");
+                    ui_variable_input(ui, &mut auth.api_key.value, previews, width);
+                    ui.end_row();
+                    ui.label("Add to:");
+                    ui.horizontal(|ui| {
+                        for location in [ApiKeyLocation::Header, ApiKeyLocation::QueryParam] {
+                            let text = location.to_string();
+                            ui.radio_value(&mut auth.api_key.location, location, text);
+                        }
+                    });
+                    ui.end_row();
+                }
+                AuthMode::Digest => {
+                    ui.label("Username:");
+                    ui_variable_input(ui, &mut auth.digest.username, previews, width);
+                    ui.end_row();
+                    ui.label("Password:");
+                    ui.add(egui::TextEdit::singleline(&mut auth.digest.password).password(true));
+                    ui.end_row();
+                    ui.label("Algorithm:");
+                    ui.horizontal(|ui| {
+                        for algorithm in [DigestAlgorithm::Md5, DigestAlgorithm::Sha256] {
+                            let text = algorithm.to_string();
+                            ui.radio_value(&mut auth.digest.algorithm, algorithm, text);
+                        }
+                    });
+                    ui.end_row();
+                }
+                AuthMode::AwsSigV4 => {
+                    ui.label("Access key:");
+                    ui_variable_input(ui, &mut auth.aws.access_key, previews, width);
+                    ui.end_row();
+                    ui.label("Secret key:");
+                    ui.add(egui::TextEdit::singleline(&mut auth.aws.secret_key).password(true));
+                    ui.end_row();
+                    ui.label("Session token:");
+                    ui.add(egui::TextEdit::singleline(&mut auth.aws.session_token).password(true));
+                    ui.end_row();
+                    ui.label("Region:");
+                    ui_variable_input(ui, &mut auth.aws.region, previews, width);
+                    ui.end_row();
+                    ui.label("Service:");
+                    ui_variable_input(ui, &mut auth.aws.service, previews, width);
+                    ui.end_row();
+                }
+                AuthMode::Hmac => ui_hmac_fields(ui, &mut auth.hmac, previews, width),
+                AuthMode::Jwt => ui_jwt_fields(ui, &mut auth.jwt, previews, width),
+                AuthMode::OAuth2 => ui_oauth2_fields(ui, &mut auth.oauth2, previews, width),
+            });
+
+            if auth.mode == AuthMode::OAuth2 {
+                ui_oauth2_token(ui, tokens, oauth2, options);
+            }
+        });
+}
+
+fn ui_hmac_fields(
+    ui: &mut egui::Ui,
+    signing: &mut HmacSigning,
+    previews: &BTreeMap<String, String>,
+    width: f32,
+) {
+    ui.label("Algorithm:");
+    egui::ComboBox::from_id_source("hmac_algorithm")
+        .selected_text(signing.algorithm.to_string())
+        .show_ui(ui, |ui| {
+            for option in HmacAlgorithm::ALL {
+                ui.selectable_value(&mut signing.algorithm, option, option.to_string());
+            }
+        });
+    ui.end_row();
+    ui.label("Key:");
+    ui.horizontal(|ui| {
+        let password = signing.key_source != KeySource::File;
+        ui.add(egui::TextEdit::singleline(&mut signing.key).password(password));
+        egui::ComboBox::from_id_source("hmac_key_source")
+            .selected_text(signing.key_source.to_string())
+            .show_ui(ui, |ui| {
+                for option in KeySource::ALL {
+                    ui.selectable_value(&mut signing.key_source, option, option.to_string());
+                }
+            });
+    });
+    ui.end_row();
+    ui.label("Signed text:");
+    let hint = format!("{}, <header:Name>", PLACEHOLDERS.join(", "));
+    ui.add(
+        egui::TextEdit::multiline(&mut signing.template)
+            .code_editor()
+            .desired_rows(4)
+            .desired_width(width),
+    )
+    .on_hover_text(hint);
+    ui.end_row();
+    ui.label("Timestamp header:");
+    ui_variable_input(ui, &mut signing.timestamp_header, previews, width);
+    ui.end_row();
+    ui.label("Nonce header:");
+    ui_variable_input(ui, &mut signing.nonce_header, previews, width);
+    ui.end_row();
+    ui.label("Signature header:");
+    ui_variable_input(ui, &mut signing.header, previews, width);
+    ui.end_row();
+    ui.label("Header value:");
+    ui_variable_input(ui, &mut signing.header_value, previews, width);
+    ui.end_row();
+    ui.label("Encoding:");
+    ui.horizontal(|ui| {
+        for encoding in [SignatureEncoding::Hex, SignatureEncoding::Base64] {
+            let text = encoding.to_string();
+            ui.radio_value(&mut signing.encoding, encoding, text);
+        }
+    });
+    ui.end_row();
+}
+
+fn ui_jwt_fields(
+    ui: &mut egui::Ui,
+    jwt: &mut JwtAuth,
+    previews: &BTreeMap<String, String>,
+    width: f32,
+) {
+    ui.label("Algorithm:");
+    egui::ComboBox::from_id_source("jwt_algorithm")
+        .selected_text(jwt.algorithm.to_string())
+        .show_ui(ui, |ui| {
+            for option in JwtAlgorithm::ALL {
+                ui.selectable_value(&mut jwt.algorithm, option, option.to_string());
+            }
+        });
+    ui.end_row();
+    ui.label("Key:");
+    ui.horizontal(|ui| {
+        let text_key = jwt.key_source == JwtKeySource::Text;
+        if text_key && jwt.algorithm != JwtAlgorithm::Hs256 {
+            // PEM keys span several lines
+            ui.add(
+                egui::TextEdit::multiline(&mut jwt.key)
+                    .code_editor()
+                    .desired_rows(3)
+                    .desired_width(width),
+            );
+        } else {
+            ui.add(egui::TextEdit::singleline(&mut jwt.key).password(text_key));
+        }
+        for source in [JwtKeySource::Text, JwtKeySource::File] {
+            let text = source.to_string();
+            ui.radio_value(&mut jwt.key_source, source, text);
+        }
+    });
+    ui.end_row();
+    ui.label("Key ID:");
+    ui_variable_input(ui, &mut jwt.key_id, previews, width);
+    ui.end_row();
+    ui.label("Claims:");
+    ui.add(
+        egui::TextEdit::multiline(&mut jwt.claims)
+            .code_editor()
+            .desired_rows(4)
+            .desired_width(width),
+    );
+    ui.end_row();
+    ui.label("Add:");
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut jwt.add_issued_at, "iat");
+        ui.checkbox(&mut jwt.add_expiry, "exp, valid for");
+        ui.add_enabled(
+            jwt.add_expiry,
+            egui::DragValue::new(&mut jwt.lifetime_seconds).suffix(" s"),
+        );
+    });
+    ui.end_row();
+}
+
+fn ui_oauth2_fields(
+    ui: &mut egui::Ui,
+    config: &mut OAuth2Config,
+    previews: &BTreeMap<String, String>,
+    width: f32,
+) {
+    ui.label("Grant:");
+    egui::ComboBox::from_id_source("oauth2_grant")
+        .selected_text(config.grant.to_string())
+        .show_ui(ui, |ui| {
+            for option in OAuth2Grant::ALL {
+                ui.selectable_value(&mut config.grant, option, option.to_string());
+            }
+        });
+    ui.end_row();
+    if config.grant == OAuth2Grant::AuthorizationCode {
+        ui.label("Authorization URL:");
+        ui_variable_input(ui, &mut config.authorization_url, previews, width);
+        ui.end_row();
+    }
+    ui.label("Token URL:");
+    ui_variable_input(ui, &mut config.token_url, previews, width);
+    ui.end_row();
+    ui.label("Client ID:");
+    ui_variable_input(ui, &mut config.client_id, previews, width);
+    ui.end_row();
+    ui.label("Client secret:");
+    ui.add(egui::TextEdit::singleline(&mut config.client_secret).password(true));
+    ui.end_row();
+    ui.label("Send client as:");
+    ui.horizontal(|ui| {
+        for option in [
+            ClientAuthentication::BasicHeader,
+            ClientAuthentication::RequestBody,
+        ] {
+            let text = option.to_string();
+            ui.radio_value(&mut config.client_authentication, option, text);
+        }
+    });
+    ui.end_row();
+    ui.label("Scope:");
+    ui_variable_input(ui, &mut config.scope, previews, width);
+    ui.end_row();
+    match config.grant {
+        OAuth2Grant::Password => {
+            ui.label("Username:");
+            ui_variable_input(ui, &mut config.username, previews, width);
+            ui.end_row();
+            ui.label("Password:");
+            ui.add(egui::TextEdit::singleline(&mut config.password).password(true));
+            ui.end_row();
+        }
+        OAuth2Grant::RefreshToken => {
+            ui.label("Refresh token:");
+            ui.add(egui::TextEdit::singleline(&mut config.refresh_token).password(true));
+            ui.end_row();
+        }
+        OAuth2Grant::AuthorizationCode => {
+            ui.label("Redirect port:");
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut config.redirect_port).clamp_range(1024..=65535));
+                ui.label(config.redirect_uri());
+            });
+            ui.end_row();
+        }
+        OAuth2Grant::ClientCredentials => (),
+    }
+}
+
+fn ui_oauth2_token(
+    ui: &mut egui::Ui,
+    tokens: &OAuth2Tokens,
+    config: &OAuth2Config,
+    options: &RequestOptions,
+) {
+    let entry = tokens.entry(config);
+    ui.horizontal(|ui| {
+        if entry.fetching {
+            ui.spinner();
+            ui.label("Getting a token...");
+        } else if let Some(error) = &entry.error {
+            ui.colored_label(catppuccin_egui::MOCHA.red, error);
+        } else if let Some(token) = &entry.token {
+            let text = match token.expires_at {
+                Some(at) => {
+                    let seconds = at.saturating_duration_since(Instant::now()).as_secs();
+                    format!("Token expires in {}s", seconds)
+                }
+                None => "Token without expiry".to_owned(),
+            };
+            ui.colored_label(catppuccin_egui::MOCHA.green, text);
+        } else {
+            ui.label("No token yet, it's requested when the request is sent");
+        }
+    });
+    ui.horizontal(|ui| {
+        let get = ui.add_enabled(!entry.fetching, egui::Button::new("Get token"));
+        if get.clicked() {
+            tokens.request_token(ui.ctx(), config, options);
+        }
+        if ui.button("Clear token").clicked() {
+            tokens.clear(config);
+        }
+    });
+}
@@ -0,0 +1,71 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{Map, Value};
+
+use crate::app::response_capture::{CaptureRule, CaptureSource};
+use crate::app::secret_vault::MASK;
+use crate::ui::ui_resolved_variables::display_value;
+
+pub fn ui_captures(ui: &mut egui::Ui, rules: &mut Vec<CaptureRule>) {
+    ui.separator();
+    egui::CollapsingHeader::new("Capture from response")
+        .default_open(false)
+        .show(ui, |ui| {
+            let mut remove = None;
+            for (idx, rule) in rules.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label("Variable:");
+                    ui.add(egui::TextEdit::singleline(&mut rule.variable).desired_width(120.0));
+                    egui::ComboBox::from_id_source(("capture_source", idx))
+                        .selected_text(rule.source.to_string())
+                        .show_ui(ui, |ui| {
+                            for option in CaptureSource::ALL {
+                                ui.selectable_value(&mut rule.source, option, option.to_string());
+                            }
+                        });
+                    ui.add(
+                        egui::TextEdit::singleline(&mut rule.expression)
+                            .hint_text(rule.source.hint()),
+                    );
+                    if ui.button("Remove").clicked() {
+                        remove = Some(idx);
+                    }
+                });
+            }
+            if let Some(idx) = remove {
+                rules.remove(idx);
+            }
+            if ui.button("Add rule").clicked() {
+                rules.push(CaptureRule::default());
+            }
+        });
+}
+
+// Shown in the environment window next to the variables the rules wrote.
+pub fn ui_captured_values(
+    ui: &mut egui::Ui,
+    captured: &BTreeMap<String, String>,
+    values: &Map<String, Value>,
+    secret_names: &BTreeSet<String>,
+    reveal_secrets: bool,
+) {
+    ui.label("Captured from responses");
+    egui::Grid::new("captured_values")
+        .striped(true)
+        .show(ui, |ui| {
+            for (variable, rule) in captured {
+                let value = match values.get(variable) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                ui.label(variable);
+                if secret_names.contains(variable) && !reveal_secrets {
+                    ui.label(MASK);
+                } else {
+                    ui.label(display_value(value));
+                }
+                ui.label(rule);
+                ui.end_row();
+            }
+        });
+}
@@ -0,0 +1,158 @@
+use serde_json::Map;
+
+use crate::app::collections::{folder_paths, Folder, SavedRequest};
+use crate::app::http_file::{exported_message, folder_requests, write_http_file};
+
+// Shows the collections as a tree, returns the request that was clicked.
+// Folders and requests are renamed and deleted from their context menu.
+pub fn ui_collections(
+    ui: &mut egui::Ui,
+    collections: &mut Vec<Folder>,
+    toasts: &mut egui_toast::Toasts,
+) -> Option<SavedRequest> {
+    let mut selected: Option<SavedRequest> = None;
+    let mut removed = None;
+    for (index, collection) in collections.iter_mut().enumerate() {
+        if ui_folder(ui, collection, vec![index], &mut selected, toasts) {
+            removed = Some(index);
+        }
+    }
+    if let Some(index) = removed {
+        collections.remove(index);
+    }
+    if ui.button("New collection").clicked() {
+        collections.push(Folder::new("New collection"));
+    }
+    selected
+}
+
+// Returns true when the folder was deleted.
+fn ui_folder(
+    ui: &mut egui::Ui,
+    folder: &mut Folder,
+    path: Vec<usize>,
+    selected: &mut Option<SavedRequest>,
+    toasts: &mut egui_toast::Toasts,
+) -> bool {
+    let mut deleted = false;
+    // Keyed by position so renaming doesn't collapse the folder
+    let response = egui::CollapsingHeader::new(folder.name.clone())
+        .id_source(("collection_folder", &path))
+        .show(ui, |ui| {
+            let mut removed_folder = None;
+            for (index, child) in folder.folders.iter_mut().enumerate() {
+                let mut child_path = path.clone();
+                child_path.push(index);
+                if ui_folder(ui, child, child_path, selected, toasts) {
+                    removed_folder = Some(index);
+                }
+            }
+            if let Some(index) = removed_folder {
+                folder.folders.remove(index);
+            }
+
+            let mut removed_request = None;
+            for (index, request) in folder.requests.iter_mut().enumerate() {
+                let label = format!("{} {}", request.method, request.name);
+                let response = ui
+                    .selectable_label(false, label)
+                    .on_hover_text(&request.url);
+                if response.clicked() {
+                    *selected = Some(request.clone());
+                }
+                response.context_menu(|ui| {
+                    ui.text_edit_singleline(&mut request.name);
+                    if ui.button("Delete").clicked() {
+                        removed_request = Some(index);
+                        ui.close_menu();
+                    }
+                });
+            }
+            if let Some(index) = removed_request {
+                folder.requests.remove(index);
+            }
+
+            if folder.folders.is_empty() && folder.requests.is_empty() {
+                ui.weak("Empty");
+            }
+        });
+
+    response.header_response.context_menu(|ui| {
+        ui.text_edit_singleline(&mut folder.name);
+        if ui.button("New folder").clicked() {
+            folder.folders.push(Folder::new("New folder"));
+            ui.close_menu();
+        }
+        if ui.button("Export as .http").clicked() {
+            let file = rfd::FileDialog::new()
+                .add_filter("http", &["http", "rest"])
+                .set_file_name(format!("{}.http", folder.name))
+                .save_file();
+            if let Some(file_path) = file {
+                let (text, kind) =
+                    match write_http_file(&file_path, &folder_requests(folder), &Map::new()) {
+                        Ok(replaced) => (
+                            exported_message(&folder.name, &replaced),
+                            egui_toast::ToastKind::Success,
+                        ),
+                        Err(error) => (
+                            format!("Unable to export {}: {}", folder.name, error),
+                            egui_toast::ToastKind::Error,
+                        ),
+                    };
+                toasts.add(egui_toast::Toast {
+                    text: text.into(),
+                    kind,
+                    options: egui_toast::ToastOptions::default()
+                        .duration_in_seconds(3.0)
+                        .show_progress(true)
+                        .show_icon(true),
+                });
+            }
+            ui.close_menu();
+        }
+        if ui.button("Delete").clicked() {
+            deleted = true;
+            ui.close_menu();
+        }
+    });
+    deleted
+}
+
+// Name and folder of a request being saved, returns true when Save is clicked.
+pub fn ui_save_request(
+    ui: &mut egui::Ui,
+    collections: &[Folder],
+    name: &mut String,
+    folder: &mut Vec<usize>,
+) -> bool {
+    let paths = folder_paths(collections);
+    egui::Grid::new("save_request_grid")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(name);
+            ui.end_row();
+
+            ui.label("Folder");
+            if paths.is_empty() {
+                ui.weak("A new collection is created");
+            } else {
+                let selected_text = paths
+                    .iter()
+                    .find(|(path, _)| path == folder)
+                    .map(|(_, label)| label.clone())
+                    .unwrap_or_default();
+                egui::ComboBox::from_id_source("save_request_folder")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for (path, label) in &paths {
+                            ui.selectable_value(folder, path.clone(), label);
+                        }
+                    });
+            }
+            ui.end_row();
+        });
+    ui.add_enabled(!name.trim().is_empty(), egui::Button::new("Save"))
+        .clicked()
+}
@@ -0,0 +1,54 @@
+use crate::app::dynamic_variables::{CommandRunner, CommandVariable};
+
+pub fn ui_commands(
+    ui: &mut egui::Ui,
+    commands: &mut Vec<CommandVariable>,
+    runner: &CommandRunner,
+    enabled: &mut bool,
+) {
+    ui.label("Command output is available as {$cmd.name} until its TTL runs out.");
+    ui.label("A TTL of 0 keeps the output until it's refreshed.");
+    ui.checkbox(enabled, "Allow running commands")
+        .on_hover_text("Only enable this for commands you trust");
+    ui.separator();
+
+    let mut remove = None;
+    egui::Grid::new("command_variables")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Name");
+            ui.strong("Command");
+            ui.strong("TTL (s)");
+            ui.strong("Output");
+            ui.end_row();
+
+            for (idx, command) in commands.iter_mut().enumerate() {
+                ui.add(egui::TextEdit::singleline(&mut command.name).desired_width(100.0));
+                ui.add(egui::TextEdit::singleline(&mut command.command).code_editor());
+                ui.add(egui::DragValue::new(&mut command.ttl_seconds));
+                match runner
+                    .output(&command.name)
+                    .and_then(|output| output.result)
+                {
+                    Some(Ok(_)) => ui.label("✔"),
+                    Some(Err(error)) => ui.colored_label(catppuccin_egui::MOCHA.red, error),
+                    None => ui.label("-"),
+                };
+                ui.horizontal(|ui| {
+                    if ui.small_button("Refresh").clicked() {
+                        runner.refresh(&command.name);
+                    }
+                    if ui.small_button("🗑").clicked() {
+                        remove = Some(idx);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+    if let Some(idx) = remove {
+        commands.remove(idx);
+    }
+    if ui.button("Add command").clicked() {
+        commands.push(CommandVariable::default());
+    }
+}
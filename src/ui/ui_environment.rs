@@ -0,0 +1,331 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use egui_toast::Toasts;
+use serde_json::{Map, Value};
+
+use crate::app::environment_file::{save_environment, EnvironmentFormat};
+
+const VALUE_KINDS: [&str; 6] = ["String", "Number", "Boolean", "Null", "Object", "Array"];
+
+// Editable view of the environment variables. Nested objects and arrays are shown
+// indented below their key. Values can be marked as secret with 🔒, which masks them.
+// Returns true if anything was changed.
+pub fn ui_environment_editor(
+    ui: &mut egui::Ui,
+    environment: &mut Map<String, Value>,
+    id_source: &str,
+    secret_names: &mut BTreeSet<String>,
+) -> bool {
+    let id = egui::Id::new(id_source);
+    let mut changed = ui_object(ui, environment, id, "", secret_names);
+
+    ui.horizontal(|ui| {
+        if ui.button("Add variable").clicked() {
+            environment.insert(unique_key(environment), Value::String("".to_owned()));
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+// Save button and file status shown below an editor that is backed by a file.
+pub fn ui_environment_save(
+    ui: &mut egui::Ui,
+    environment: &Map<String, Value>,
+    path: &Path,
+    format: Option<EnvironmentFormat>,
+    dirty: &mut bool,
+    toasts: &mut Toasts,
+) {
+    ui.horizontal(|ui| {
+        let has_path = !path.as_os_str().is_empty();
+        if ui
+            .add_enabled(has_path && *dirty, egui::Button::new("Save"))
+            .on_hover_text("Write the changes back to the file")
+            .clicked()
+        {
+            match save_environment(path, environment, format) {
+                Ok(()) => {
+                    *dirty = false;
+                    toasts.add(egui_toast::Toast {
+                        text: "Variables saved".into(),
+                        kind: egui_toast::ToastKind::Success,
+                        options: egui_toast::ToastOptions::default()
+                            .duration_in_seconds(3.0)
+                            .show_progress(true)
+                            .show_icon(true),
+                    });
+                }
+                Err(error) => {
+                    toasts.add(egui_toast::Toast {
+                        text: format!("Unable to save variables: {}", error).into(),
+                        kind: egui_toast::ToastKind::Error,
+                        options: egui_toast::ToastOptions::default()
+                            .duration_in_seconds(3.0)
+                            .show_progress(true)
+                            .show_icon(true),
+                    });
+                }
+            }
+        }
+        if *dirty {
+            ui.label("Unsaved changes");
+        }
+        if has_path {
+            ui.weak(path.display().to_string());
+        }
+    });
+}
+
+fn ui_object(
+    ui: &mut egui::Ui,
+    map: &mut Map<String, Value>,
+    id: egui::Id,
+    prefix: &str,
+    secret_names: &mut BTreeSet<String>,
+) -> bool {
+    let mut changed = false;
+    let mut rename: Option<(usize, String)> = None;
+    let mut remove: Option<usize> = None;
+
+    for (idx, (key, value)) in map.iter_mut().enumerate() {
+        // Ids are based on the position so that renaming a key doesn't steal focus
+        let row_id = id.with(idx);
+        let name = variable_name(prefix, key);
+        ui.horizontal(|ui| {
+            let mut new_key = key.clone();
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut new_key)
+                        .id(row_id.with("key"))
+                        .desired_width(140.0),
+                )
+                .changed()
+            {
+                rename = Some((idx, new_key));
+            }
+            changed |= ui_value(ui, value, row_id, &name, secret_names);
+            if ui.button("🗑").on_hover_text("Delete variable").clicked() {
+                remove = Some(idx);
+            }
+        });
+        changed |= ui_children(ui, value, row_id, &name, secret_names);
+    }
+
+    if let Some((idx, new_key)) = rename {
+        if !map.contains_key(&new_key) {
+            *map = map
+                .iter()
+                .enumerate()
+                .map(|(i, (k, v))| {
+                    let k = if i == idx { new_key.clone() } else { k.clone() };
+                    (k, v.clone())
+                })
+                .collect();
+            changed = true;
+        }
+    }
+    if let Some(idx) = remove {
+        let mut i = 0;
+        map.retain(|_, _| {
+            i += 1;
+            i - 1 != idx
+        });
+        changed = true;
+    }
+
+    changed
+}
+
+fn ui_array(
+    ui: &mut egui::Ui,
+    items: &mut Vec<Value>,
+    id: egui::Id,
+    prefix: &str,
+    secret_names: &mut BTreeSet<String>,
+) -> bool {
+    let mut changed = false;
+    let mut remove: Option<usize> = None;
+
+    for (idx, value) in items.iter_mut().enumerate() {
+        let row_id = id.with(idx);
+        let name = variable_name(prefix, &idx.to_string());
+        ui.horizontal(|ui| {
+            ui.label(format!("[{}]", idx));
+            changed |= ui_value(ui, value, row_id, &name, secret_names);
+            if ui.button("🗑").on_hover_text("Delete item").clicked() {
+                remove = Some(idx);
+            }
+        });
+        changed |= ui_children(ui, value, row_id, &name, secret_names);
+    }
+
+    if let Some(idx) = remove {
+        items.remove(idx);
+        changed = true;
+    }
+
+    changed
+}
+
+// Nested values are edited in an indented block below the row of their parent.
+fn ui_children(
+    ui: &mut egui::Ui,
+    value: &mut Value,
+    id: egui::Id,
+    name: &str,
+    secret_names: &mut BTreeSet<String>,
+) -> bool {
+    let mut changed = false;
+    match value {
+        Value::Object(map) => {
+            ui.indent(id, |ui| {
+                changed |= ui_object(ui, map, id.with("object"), name, secret_names);
+                if ui.small_button("Add key").clicked() {
+                    map.insert(unique_key(map), Value::String("".to_owned()));
+                    changed = true;
+                }
+            });
+        }
+        Value::Array(items) => {
+            ui.indent(id, |ui| {
+                changed |= ui_array(ui, items, id.with("array"), name, secret_names);
+                if ui.small_button("Add item").clicked() {
+                    items.push(Value::String("".to_owned()));
+                    changed = true;
+                }
+            });
+        }
+        _ => (),
+    }
+    changed
+}
+
+fn ui_value(
+    ui: &mut egui::Ui,
+    value: &mut Value,
+    id: egui::Id,
+    name: &str,
+    secret_names: &mut BTreeSet<String>,
+) -> bool {
+    let mut changed = false;
+    let secret = secret_names.contains(name);
+
+    let current_kind = value_kind(value);
+    let mut kind = current_kind;
+    egui::ComboBox::from_id_source(id.with("kind"))
+        .width(80.0)
+        .selected_text(kind)
+        .show_ui(ui, |ui| {
+            for option in VALUE_KINDS {
+                ui.selectable_value(&mut kind, option, option);
+            }
+        });
+    if kind != current_kind {
+        *value = convert_value(value, kind);
+        changed = true;
+    }
+
+    match value {
+        Value::String(text) => {
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(text)
+                        .password(secret)
+                        .desired_width(240.0),
+                )
+                .changed();
+        }
+        Value::Number(number) => {
+            if number.is_f64() {
+                let mut float = number.as_f64().unwrap_or_default();
+                if ui.add(egui::DragValue::new(&mut float)).changed() {
+                    if let Some(new_number) = serde_json::Number::from_f64(float) {
+                        *number = new_number;
+                        changed = true;
+                    }
+                }
+            } else {
+                let mut integer = number.as_i64().unwrap_or_default();
+                if ui.add(egui::DragValue::new(&mut integer)).changed() {
+                    *number = integer.into();
+                    changed = true;
+                }
+            }
+        }
+        Value::Bool(flag) => {
+            changed |= ui.checkbox(flag, "").changed();
+        }
+        _ => (),
+    }
+
+    if !value.is_object() && !value.is_array() {
+        let tooltip = "Secrets are masked and never stored in plain text";
+        if ui
+            .selectable_label(secret, "🔒")
+            .on_hover_text(tooltip)
+            .clicked()
+        {
+            if secret {
+                secret_names.remove(name);
+            } else {
+                secret_names.insert(name.to_owned());
+            }
+        }
+    }
+
+    changed
+}
+
+// The dotted name used to reference a nested value, see `flatten_environment`.
+fn variable_name(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "String",
+        Value::Number(_) => "Number",
+        Value::Bool(_) => "Boolean",
+        Value::Null => "Null",
+        Value::Object(_) => "Object",
+        Value::Array(_) => "Array",
+    }
+}
+
+// Keeps as much of the old value as makes sense for the new type.
+fn convert_value(value: &Value, kind: &str) -> Value {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        Value::Number(_) | Value::Bool(_) => value.to_string(),
+        _ => "".to_owned(),
+    };
+    match kind {
+        "String" => Value::String(text),
+        "Number" => text
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| text.parse::<f64>().map(Value::from))
+            .unwrap_or(Value::from(0)),
+        "Boolean" => Value::Bool(text == "true"),
+        "Object" => Value::Object(Map::new()),
+        "Array" => Value::Array(vec![]),
+        _ => Value::Null,
+    }
+}
+
+fn unique_key(map: &Map<String, Value>) -> String {
+    let mut key = "new_variable".to_owned();
+    let mut counter = 2;
+    while map.contains_key(&key) {
+        key = format!("new_variable_{}", counter);
+        counter += 1;
+    }
+    key
+}
@@ -0,0 +1,25 @@
+use crate::app::environment_diagnostics::{Diagnostic, Severity};
+
+pub fn ui_environment_diagnostics(ui: &mut egui::Ui, diagnostics: &[Diagnostic]) {
+    if diagnostics.is_empty() {
+        ui.label("No problems found.");
+        return;
+    }
+    egui::Grid::new("environment_diagnostics")
+        .striped(true)
+        .show(ui, |ui| {
+            for diagnostic in diagnostics {
+                let color = match diagnostic.severity {
+                    Severity::Error => catppuccin_egui::MOCHA.red,
+                    Severity::Warning => catppuccin_egui::MOCHA.yellow,
+                };
+                ui.colored_label(color, diagnostic.severity.to_string());
+                match diagnostic.position {
+                    Some((line, column)) => ui.label(format!("{}:{}", line, column)),
+                    None => ui.label(""),
+                };
+                ui.label(&diagnostic.message);
+                ui.end_row();
+            }
+        });
+}
@@ -1,7 +1,12 @@
+use std::collections::BTreeMap;
+
+use crate::ui::ui_variable_input::ui_variable_input;
+
 pub fn ui_headers(
     ui: &mut egui::Ui,
     request_header_keys: &mut Vec<String>,
     request_header_values: &mut Vec<String>,
+    previews: &BTreeMap<String, String>,
 ) {
     ui.separator();
     egui::CollapsingHeader::new("Request headers")
@@ -13,9 +18,10 @@ pub fn ui_headers(
                 }
                 ui.horizontal(|ui| {
                     ui.label("Key:");
-                    ui.add(egui::TextEdit::singleline(&mut request_header_keys[idx]));
+                    let width = ui.spacing().text_edit_width;
+                    ui_variable_input(ui, &mut request_header_keys[idx], previews, width);
                     ui.label("Value:");
-                    ui.add(egui::TextEdit::singleline(&mut request_header_values[idx]));
+                    ui_variable_input(ui, &mut request_header_values[idx], previews, width);
                     if ui.button("Remove").clicked() {
                         request_header_keys.remove(idx);
                     }
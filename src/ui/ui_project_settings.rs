@@ -0,0 +1,91 @@
+use std::path::{Path, PathBuf};
+
+use crate::app::project::{Pair, ProjectFile};
+
+// Edits the project file, returns true when a setting of the file changed.
+// Accepting invalid certificates is a setting of this machine only.
+pub fn ui_project_settings(
+    ui: &mut egui::Ui,
+    project: &mut ProjectFile,
+    project_path: &Path,
+    environments: &[PathBuf],
+    accept_invalid_certs: &mut bool,
+) -> bool {
+    let previous = project.clone();
+    egui::Grid::new("project_settings_grid")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut project.name);
+            ui.end_row();
+
+            ui.label("Base URL");
+            ui.add(
+                egui::TextEdit::singleline(&mut project.base_url)
+                    .hint_text("https://api.example.com/{version}"),
+            )
+            .on_hover_text("Request urls without a scheme are appended to it");
+            ui.end_row();
+
+            ui.label("Default environment");
+            let selected_text = match project.environment.as_str() {
+                "" => "None",
+                environment => environment,
+            };
+            egui::ComboBox::from_id_source("project_environment")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut project.environment, "".to_owned(), "None");
+                    for file_path in environments {
+                        let relative = file_path
+                            .strip_prefix(project_path)
+                            .unwrap_or(file_path)
+                            .to_string_lossy()
+                            .replace('\\', "/");
+                        ui.selectable_value(&mut project.environment, relative.clone(), relative);
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Timeout");
+            ui.add(
+                egui::DragValue::new(&mut project.request.timeout_seconds)
+                    .clamp_range(0..=3600)
+                    .suffix(" s"),
+            )
+            .on_hover_text("0 waits forever");
+            ui.end_row();
+
+            ui.label("Redirects");
+            ui.checkbox(&mut project.request.follow_redirects, "Follow redirects");
+            ui.end_row();
+
+            ui.label("TLS");
+            ui.checkbox(accept_invalid_certs, "Accept invalid certificates")
+                .on_hover_text("For local servers with self-signed certificates. Only applies on this machine, it isn't saved to the project file");
+            ui.end_row();
+        });
+
+    ui.separator();
+    ui.label("Default headers");
+    let mut remove = None;
+    for (idx, header) in project.headers.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label("Key:");
+            ui.text_edit_singleline(&mut header.name);
+            ui.label("Value:");
+            ui.text_edit_singleline(&mut header.value);
+            if ui.button("Remove").clicked() {
+                remove = Some(idx);
+            }
+        });
+    }
+    if let Some(idx) = remove {
+        project.headers.remove(idx);
+    }
+    if ui.button("Add header").clicked() {
+        project.headers.push(Pair::default());
+    }
+
+    *project != previous
+}
@@ -0,0 +1,73 @@
+use url::Url;
+
+use crate::app::secret_vault::redact_secrets;
+
+// What the request looks like after the variables are injected, it's rebuilt
+// every frame so it follows the inputs while typing. `signed` is the text a
+// signing auth mode signed.
+pub fn ui_request_preview(
+    ui: &mut egui::Ui,
+    request: &Result<ehttp::Request, String>,
+    signed: Option<&str>,
+    secrets: &[String],
+) {
+    ui.separator();
+    egui::CollapsingHeader::new("Resolved request")
+        .default_open(false)
+        .show(ui, |ui| {
+            let request = match request {
+                Ok(request) => request,
+                Err(error) => {
+                    ui.colored_label(catppuccin_egui::MOCHA.red, error);
+                    return;
+                }
+            };
+
+            ui.horizontal(|ui| {
+                ui.strong(&request.method);
+                ui.add(egui::Label::new(redact_secrets(&request.url, secrets)).wrap(true));
+            });
+            if let Err(error) = Url::parse(&request.url) {
+                let text = format!("Invalid URL: {}", error);
+                ui.colored_label(catppuccin_egui::MOCHA.red, text);
+            }
+
+            ui.add_space(5.0);
+            egui::Grid::new("resolved_headers")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (k, v) in &request.headers {
+                        ui.label(redact_secrets(k, secrets));
+                        ui.label(redact_secrets(v, secrets));
+                        ui.end_row();
+                    }
+                });
+
+            if let Some(signed) = signed {
+                ui.add_space(5.0);
+                egui::CollapsingHeader::new("Signed text")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        let mut text = redact_secrets(signed, secrets);
+                        ui.add(
+                            egui::TextEdit::multiline(&mut text)
+                                .code_editor()
+                                .interactive(false)
+                                .desired_width(ui.available_width() - 15.0),
+                        );
+                    });
+            }
+
+            if !request.body.is_empty() {
+                ui.add_space(5.0);
+                let body = String::from_utf8_lossy(&request.body);
+                let mut body = redact_secrets(&body, secrets);
+                ui.add(
+                    egui::TextEdit::multiline(&mut body)
+                        .code_editor()
+                        .interactive(false)
+                        .desired_width(ui.available_width() - 15.0),
+                );
+            }
+        });
+}
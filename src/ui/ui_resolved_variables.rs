@@ -0,0 +1,115 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use egui_toast::Toasts;
+use serde_json::Value;
+
+use crate::app::secret_vault::{SecretVault, MASK};
+use crate::app::tab_state::TabState;
+use crate::app::variable_scopes::{ResolvedVariable, VariableScope};
+
+// Final value of every variable available in the tab and the layer it came from.
+pub fn ui_resolved_variables(
+    ui: &mut egui::Ui,
+    resolved: &BTreeMap<String, ResolvedVariable>,
+    state: &mut TabState,
+    secret_names: &mut BTreeSet<String>,
+    vault: &mut SecretVault,
+    reveal_secrets: bool,
+    toasts: &mut Toasts,
+) {
+    egui::Grid::new("resolved_values")
+        .striped(true)
+        .spacing(egui::vec2(ui.spacing().item_spacing.x * 4.0, 4.0))
+        .show(ui, |ui| {
+            ui.strong("Variable");
+            ui.strong("Value");
+            ui.strong("Layer");
+            ui.strong("Secret");
+            ui.end_row();
+
+            for (k, v) in resolved {
+                let secret = secret_names.contains(k);
+                ui.label(k);
+                if secret && !reveal_secrets {
+                    ui.label(MASK);
+                } else {
+                    ui.label(display_value(&v.value));
+                }
+
+                let mut layer = v.scope.to_string();
+                if !v.shadowed.is_empty() {
+                    let shadowed: Vec<String> = v.shadowed.iter().map(|s| s.to_string()).collect();
+                    layer.push_str(" (overrides ");
+                    layer.push_str(&shadowed.join(", "));
+                    layer.push(')');
+                }
+                ui.label(layer);
+
+                let mut marked = secret;
+                if ui.checkbox(&mut marked, "").changed() {
+                    if marked {
+                        secret_names.insert(k.clone());
+                    } else {
+                        secret_names.remove(k);
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    if v.scope == VariableScope::Tab {
+                        if ui.small_button("Remove override").clicked() {
+                            state.variable_overrides.shift_remove(k);
+                        }
+                    } else if ui
+                        .small_button("Override")
+                        .on_hover_text("Override this value for this tab only")
+                        .clicked()
+                    {
+                        state.variable_overrides.insert(k.clone(), v.value.clone());
+                    }
+
+                    if secret && v.scope != VariableScope::Vault {
+                        let store = ui
+                            .add_enabled(
+                                vault.is_unlocked(),
+                                egui::Button::new("Store in vault").small(),
+                            )
+                            .on_hover_text("Keep the value available after a restart")
+                            .on_disabled_hover_text("Unlock the vault from Variables > Secrets")
+                            .clicked();
+                        if store {
+                            store_in_vault(vault, k, &v.value, toasts);
+                        }
+                    }
+                });
+                ui.end_row();
+            }
+        });
+}
+
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn store_in_vault(vault: &mut SecretVault, name: &str, value: &Value, toasts: &mut Toasts) {
+    let (text, kind) = match vault.set(name, &display_value(value)) {
+        Ok(()) => (
+            format!("{} stored in the vault", name),
+            egui_toast::ToastKind::Success,
+        ),
+        Err(error) => (
+            format!("Unable to store {}: {}", name, error),
+            egui_toast::ToastKind::Error,
+        ),
+    };
+    toasts.add(egui_toast::Toast {
+        text: text.into(),
+        kind,
+        options: egui_toast::ToastOptions::default()
+            .duration_in_seconds(3.0)
+            .show_progress(true)
+            .show_icon(true),
+    });
+}
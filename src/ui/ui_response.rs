@@ -1,6 +1,10 @@
+use chrono::Utc;
+
 use crate::app::syntax_highlighting::{code_view_ui, get_type_from_mime, CodeTheme};
 
+use crate::app::jwt::DecodedJwt;
 use crate::app::resource::Resource;
+use crate::app::secret_vault::redact_secrets;
 
 pub fn ui_response(
     ui: &mut egui::Ui,
@@ -11,6 +15,7 @@ pub fn ui_response(
     show_info: &mut bool,
     wrap_text: &mut bool,
     stx_hgl: &mut bool,
+    secrets: &[String],
 ) {
     let Resource {
         response,
@@ -19,6 +24,8 @@ pub fn ui_response(
         text,
         image,
         colored_text,
+        digest_legs,
+        jwts,
     } = resource;
     let big_boi = response.bytes.len() as f32 / 1000.0 > 100.0;
     if big_boi {
@@ -113,6 +120,9 @@ pub fn ui_response(
         }
     }
 
+    // Secrets the server echoed back are masked unless the user revealed them
+    let text_to_copy = redact_secrets(&text_to_copy, secrets);
+
     ui.add_space(5.0); // Top margin
 
     let container = egui::ScrollArea::both()
@@ -188,6 +198,26 @@ pub fn ui_response(
                             ui.end_row();
                             ui.monospace(format!("timing: {:.1}ms", timing.as_millis()));
                             ui.end_row();
+                            for (idx, leg) in digest_legs.iter().enumerate() {
+                                ui.end_row();
+                                ui.monospace(format!("digest leg {}: {}", idx + 1, leg.request));
+                                ui.end_row();
+                                if let Some(authorization) = &leg.authorization {
+                                    let authorization = redact_secrets(authorization, secrets);
+                                    ui.monospace(format!("authorization: {}", authorization));
+                                    ui.end_row();
+                                }
+                                ui.monospace(format!("status: {}", leg.status));
+                                ui.end_row();
+                                if let Some(challenge) = &leg.challenge {
+                                    ui.monospace(format!("www-authenticate: {}", challenge));
+                                    ui.end_row();
+                                }
+                            }
+                            for jwt in jwts {
+                                ui.end_row();
+                                ui_jwt(ui, jwt);
+                            }
                         });
                 }
             });
@@ -203,6 +233,51 @@ pub fn ui_response(
     );
 }
 
+// The decoded token, times are shown in UTC and relative to now.
+fn ui_jwt(ui: &mut egui::Ui, jwt: &DecodedJwt) {
+    ui.monospace(format!("jwt in {}: {}", jwt.source, jwt.header));
+    ui.end_row();
+    let claims = serde_json::to_string_pretty(&jwt.claims).unwrap_or_default();
+    ui.monospace(claims);
+    ui.end_row();
+    for (claim, label) in [("iat", "issued"), ("nbf", "not before"), ("exp", "expires")] {
+        let Some(time) = jwt.time_claim(claim) else {
+            continue;
+        };
+        let seconds = (time - Utc::now()).num_seconds();
+        let text = format!(
+            "{}: {} ({})",
+            label,
+            time.format("%Y-%m-%d %H:%M:%S UTC"),
+            relative_time(seconds)
+        );
+        if claim == "exp" && seconds < 0 {
+            ui.colored_label(
+                catppuccin_egui::MOCHA.red,
+                egui::RichText::new(text).monospace(),
+            );
+        } else {
+            ui.monospace(text);
+        }
+        ui.end_row();
+    }
+}
+
+fn relative_time(seconds: i64) -> String {
+    let span = seconds.unsigned_abs();
+    let text = match span {
+        0..=59 => format!("{}s", span),
+        60..=3599 => format!("{}m {}s", span / 60, span % 60),
+        3600..=86399 => format!("{}h {}m", span / 3600, span % 3600 / 60),
+        _ => format!("{}d {}h", span / 86400, span % 86400 / 3600),
+    };
+    if seconds < 0 {
+        format!("{} ago", text)
+    } else {
+        format!("in {}", text)
+    }
+}
+
 fn clipboard(ctx: &egui::Context, name: String, rect: egui::Rect, text: &String) {
     egui::Area::new(name)
         .current_pos(egui::Pos2 {
@@ -0,0 +1,106 @@
+use std::collections::BTreeSet;
+
+use egui_toast::Toasts;
+
+use crate::app::secret_vault::SecretVault;
+
+// Unlocks the vault or lists the stored secrets once it is unlocked.
+pub fn ui_secret_vault(
+    ui: &mut egui::Ui,
+    vault: &mut SecretVault,
+    passphrase: &mut String,
+    new_name: &mut String,
+    new_value: &mut String,
+    secret_names: &mut BTreeSet<String>,
+    toasts: &mut Toasts,
+) {
+    if let Some(result) = vault.poll() {
+        match result {
+            Ok(()) => {
+                // Everything kept in the vault is a secret
+                let names = vault.secrets().into_iter().flat_map(|s| s.keys());
+                secret_names.extend(names.cloned());
+            }
+            Err(error) => vault_error(toasts, &error),
+        }
+    }
+    if vault.is_opening() {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("Deriving the key from the passphrase...");
+        });
+        return;
+    }
+    if !vault.is_unlocked() {
+        let exists = vault.exists();
+        if exists {
+            ui.label("Enter the passphrase to unlock the vault.");
+        } else {
+            ui.label("Pick a passphrase to create the vault, it can't be recovered.");
+        }
+        let mut submit = false;
+        ui.horizontal(|ui| {
+            let input = ui.add(egui::TextEdit::singleline(passphrase).password(true));
+            submit = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let label = if exists { "Unlock" } else { "Create vault" };
+            submit |= ui.button(label).clicked();
+        });
+        if submit && !passphrase.is_empty() {
+            vault.open_in_background(std::mem::take(passphrase), ui.ctx());
+        }
+        return;
+    }
+
+    if ui.button("Lock").clicked() {
+        vault.lock();
+        return;
+    }
+    ui.separator();
+
+    let secrets = vault.secrets().cloned().unwrap_or_default();
+    let mut result = Ok(());
+    egui::Grid::new("vault_secrets")
+        .striped(true)
+        .show(ui, |ui| {
+            for (name, value) in secrets {
+                let mut value_temp = value.clone();
+                ui.label(&name);
+                ui.add(egui::TextEdit::singleline(&mut value_temp).password(true));
+                if value_temp != value {
+                    result = vault.set(&name, &value_temp);
+                }
+                if ui.button("🗑").clicked() {
+                    result = vault.remove(&name);
+                }
+                ui.end_row();
+            }
+
+            ui.add(egui::TextEdit::singleline(new_name).hint_text("Name"));
+            ui.add(
+                egui::TextEdit::singleline(new_value)
+                    .password(true)
+                    .hint_text("Value"),
+            );
+            if ui.button("Add").clicked() && !new_name.is_empty() {
+                result = vault.set(new_name, new_value);
+                secret_names.insert(new_name.clone());
+                new_name.clear();
+                new_value.clear();
+            }
+            ui.end_row();
+        });
+    if let Err(error) = result {
+        vault_error(toasts, &error);
+    }
+}
+
+fn vault_error(toasts: &mut Toasts, error: &str) {
+    toasts.add(egui_toast::Toast {
+        text: format!("Secret vault: {}", error).into(),
+        kind: egui_toast::ToastKind::Error,
+        options: egui_toast::ToastOptions::default()
+            .duration_in_seconds(3.0)
+            .show_progress(true)
+            .show_icon(true),
+    });
+}
@@ -1,6 +1,14 @@
+use std::collections::BTreeMap;
+
 use crate::app::request_method::RequestMethod;
+use crate::ui::ui_variable_input::ui_variable_input;
 
-pub fn ui_url(ui: &mut egui::Ui, url: &mut String, method: &mut RequestMethod) -> bool {
+pub fn ui_url(
+    ui: &mut egui::Ui,
+    url: &mut String,
+    method: &mut RequestMethod,
+    previews: &BTreeMap<String, String>,
+) -> bool {
     let mut trigger_fetch = false;
 
     ui.style_mut().text_styles.insert(
@@ -30,9 +38,12 @@ pub fn ui_url(ui: &mut egui::Ui, url: &mut String, method: &mut RequestMethod) -
                 }
             });
         ui.label("URL: ");
-        url_input = Some(
-            ui.add(egui::TextEdit::singleline(url).desired_width(ui.available_width() - 60.0)),
-        );
+        url_input = Some(ui_variable_input(
+            ui,
+            url,
+            previews,
+            ui.available_width() - 60.0,
+        ));
         if ui.button("Send").clicked() {
             trigger_fetch = true;
         }
@@ -0,0 +1,208 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+use egui::text::{CCursor, CCursorRange, LayoutJob};
+
+use crate::app::secret_vault::MASK;
+use crate::app::variable_scopes::ResolvedVariable;
+use crate::ui::ui_resolved_variables::display_value;
+
+const MAX_SUGGESTIONS: usize = 10;
+
+// Text shown when hovering a variable, secrets stay masked unless revealed.
+pub fn variable_previews(
+    resolved: &BTreeMap<String, ResolvedVariable>,
+    secret_names: &BTreeSet<String>,
+    reveal_secrets: bool,
+) -> BTreeMap<String, String> {
+    resolved
+        .iter()
+        .map(|(k, v)| {
+            let preview = if secret_names.contains(k) && !reveal_secrets {
+                MASK.to_owned()
+            } else {
+                display_value(&v.value)
+            };
+            (k.clone(), preview)
+        })
+        .collect()
+}
+
+// Single line input that highlights `{name}` references, green when the variable
+// exists and red when it doesn't, shows the value of a reference on hover and
+// suggests variable names after typing `{`.
+pub fn ui_variable_input(
+    ui: &mut egui::Ui,
+    text: &mut String,
+    previews: &BTreeMap<String, String>,
+    desired_width: f32,
+) -> egui::Response {
+    let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
+        let mut job = highlight_variables(ui, string, previews);
+        job.wrap.max_width = wrap_width;
+        ui.fonts(|f| f.layout_job(job))
+    };
+    let output = egui::TextEdit::singleline(text)
+        .desired_width(desired_width)
+        .layouter(&mut layouter)
+        .show(ui);
+    let response = output.response.clone();
+
+    // Value of the reference under the pointer
+    if let Some(pointer) = response.hover_pos() {
+        let cursor = output.galley.cursor_from_pos(pointer - output.galley_pos);
+        let index = cursor.ccursor.index;
+        let hovered = variable_references(text)
+            .into_iter()
+            .find(|reference| reference.contains(&index));
+        if let Some(reference) = hovered {
+            let name = reference_name(text, &reference);
+            let hint = match previews.get(name) {
+                Some(value) => format!("{} = {}", name, value),
+                None => format!("{} isn't defined in any variable layer", name),
+            };
+            egui::show_tooltip_at_pointer(ui.ctx(), response.id.with("variable_hint"), |ui| {
+                ui.label(hint);
+            });
+        }
+    }
+
+    // The popup is also shown on the frame the input loses focus, that is when a
+    // suggestion was clicked.
+    if !response.has_focus() && !response.lost_focus() {
+        return response;
+    }
+    let cursor = match output.state.cursor.char_range() {
+        Some(range) => range.primary.index,
+        None => return response,
+    };
+    let start = match open_reference(text, cursor) {
+        Some(start) => start,
+        None => return response,
+    };
+    let prefix: String = text.chars().skip(start).take(cursor - start).collect();
+    let suggestions: Vec<&String> = previews
+        .keys()
+        .filter(|name| name.starts_with(&prefix))
+        .take(MAX_SUGGESTIONS)
+        .collect();
+    if suggestions.is_empty() {
+        return response;
+    }
+
+    let mut selected = None;
+    egui::Area::new(response.id.with("variable_suggestions"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(response.rect.left_bottom())
+        .show(ui.ctx(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                for name in suggestions {
+                    if ui.selectable_label(false, name.as_str()).clicked() {
+                        selected = Some(name.clone());
+                    }
+                }
+            });
+        });
+
+    if let Some(name) = selected {
+        let end = complete_reference(text, start, cursor, &name);
+        let mut state = output.state;
+        state
+            .cursor
+            .set_char_range(Some(CCursorRange::one(CCursor::new(end))));
+        state.store(ui.ctx(), response.id);
+        response.request_focus();
+    }
+
+    response
+}
+
+fn highlight_variables(
+    ui: &egui::Ui,
+    text: &str,
+    previews: &BTreeMap<String, String>,
+) -> LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let default_color = ui.visuals().text_color();
+    let mut job = LayoutJob::default();
+    let mut append = |range: Range<usize>, color: egui::Color32| {
+        let section: String = text.chars().skip(range.start).take(range.len()).collect();
+        job.append(
+            &section,
+            0.0,
+            egui::TextFormat::simple(font_id.clone(), color),
+        );
+    };
+
+    let mut last = 0;
+    for reference in variable_references(text) {
+        append(last..reference.start, default_color);
+        let color = if previews.contains_key(reference_name(text, &reference)) {
+            catppuccin_egui::MOCHA.green
+        } else {
+            catppuccin_egui::MOCHA.red
+        };
+        last = reference.end;
+        append(reference, color);
+    }
+    append(last..text.chars().count(), default_color);
+
+    job
+}
+
+// Char ranges of the `{name}` references, braces included.
+fn variable_references(text: &str) -> Vec<Range<usize>> {
+    let mut references = vec![];
+    let mut start = None;
+    for (idx, c) in text.chars().enumerate() {
+        match c {
+            '{' => start = Some(idx),
+            '}' => {
+                if let Some(start) = start.take() {
+                    if idx > start + 1 {
+                        references.push(start..idx + 1);
+                    }
+                }
+            }
+            c if c.is_whitespace() => start = None,
+            _ => (),
+        }
+    }
+    references
+}
+
+fn reference_name<'a>(text: &'a str, reference: &Range<usize>) -> &'a str {
+    let byte_index = |idx: usize| text.char_indices().nth(idx).map_or(text.len(), |(b, _)| b);
+    &text[byte_index(reference.start + 1)..byte_index(reference.end - 1)]
+}
+
+// Char index right after the `{` of the reference the cursor is typing, if any.
+fn open_reference(text: &str, cursor: usize) -> Option<usize> {
+    let before: Vec<char> = text.chars().take(cursor).collect();
+    for (idx, c) in before.iter().enumerate().rev() {
+        match c {
+            '{' => return Some(idx + 1),
+            '}' => return None,
+            c if c.is_whitespace() => return None,
+            _ => (),
+        }
+    }
+    None
+}
+
+// Replaces the typed part of the name and closes the brace, returns the char
+// index after the inserted reference.
+fn complete_reference(text: &mut String, start: usize, cursor: usize, name: &str) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    let mut completed: String = chars[..start].iter().collect();
+    completed.push_str(name);
+    completed.push('}');
+    let mut rest = &chars[cursor..];
+    if rest.first() == Some(&'}') {
+        rest = &rest[1..];
+    }
+    let end = completed.chars().count();
+    completed.extend(rest);
+    *text = completed;
+    end
+}